@@ -0,0 +1,94 @@
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use color_eyre::eyre::{eyre, Result};
+use color_eyre::owo_colors::OwoColorize;
+
+use crate::config::{self, validate::Severity};
+
+pub fn command() -> Command {
+    Command::new("config")
+        .about("Inspect the launcher configuration")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("show")
+                .about("Print the effective configuration after defaults, file, environment and --set")
+                .arg(
+                    Arg::new("origin")
+                        .long("origin")
+                        .help("Show where each value came from")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("check")
+                .about("Validate the configuration, exiting non-zero on errors"),
+        )
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("show", sub)) => show(sub),
+        Some(("check", _)) => check(),
+        _ => Ok(()),
+    }
+}
+
+/// Quotes keys TOML wouldn't accept bare, like `"launch::log4j"`
+fn toml_key(key: &str) -> String {
+    if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        key.to_string()
+    } else {
+        toml::Value::String(key.to_string()).to_string()
+    }
+}
+
+fn show(matches: &ArgMatches) -> Result<()> {
+    let show_origin = matches.get_flag("origin");
+    let settings = config::origins(&config::overrides());
+
+    let mut section = None;
+    for (key, (value, layer)) in &settings {
+        let (table, name) = key.rsplit_once('.').unwrap_or(("", key.as_str()));
+        if section != Some(table) {
+            if section.is_some() {
+                println!();
+            }
+            if !table.is_empty() {
+                let path: Vec<String> = table.split('.').map(toml_key).collect();
+                println!("{}", format!("[{}]", path.join(".")).bold());
+            }
+            section = Some(table);
+        }
+        let line = format!("{} = {}", toml_key(name), value);
+        if show_origin {
+            println!("{:<40} {}", line, format!("# {}", layer.origin(key)).dimmed());
+        } else {
+            println!("{}", line);
+        }
+    }
+    Ok(())
+}
+
+fn check() -> Result<()> {
+    let problems = config::problems();
+    for problem in problems {
+        match problem.severity {
+            Severity::Error => println!("{} {}", "error:".red().bold(), problem),
+            Severity::Warning => println!("{} {}", "warning:".yellow(), problem),
+        }
+    }
+    let errors = problems
+        .iter()
+        .filter(|problem| problem.severity == Severity::Error)
+        .count();
+    let warnings = problems.len() - errors;
+    if errors > 0 {
+        return Err(eyre!("{} errors, {} warnings in the configuration", errors, warnings));
+    }
+    if warnings > 0 {
+        println!("{} warnings, no errors", warnings);
+    } else {
+        println!("Configuration is valid");
+    }
+    Ok(())
+}
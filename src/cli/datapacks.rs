@@ -0,0 +1,127 @@
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use color_eyre::eyre::Result;
+
+use crate::{
+    info,
+    instance::{self, Instance},
+    packs::data,
+    worlds::{self, World},
+};
+
+use super::{instance_arg, world_arg};
+
+fn pack_arg() -> Arg {
+    Arg::new("pack")
+        .help("File or folder name in datapacks/")
+        .required(true)
+        .action(ArgAction::Set)
+}
+
+pub fn command() -> Command {
+    Command::new("datapacks")
+        .about("Manage the data packs of a world")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("list")
+                .about("List data packs and whether they are enabled")
+                .arg(instance_arg())
+                .arg(world_arg()),
+        )
+        .subcommand(
+            Command::new("install")
+                .about("Install a data pack from a zip or Modrinth and enable it")
+                .arg(instance_arg())
+                .arg(world_arg())
+                .arg(
+                    Arg::new("source")
+                        .help("Path to a zip, or a Modrinth project slug or id")
+                        .required(true)
+                        .action(ArgAction::Set),
+                ),
+        )
+        .subcommand(
+            Command::new("enable")
+                .about("Enable a data pack")
+                .arg(instance_arg())
+                .arg(world_arg())
+                .arg(pack_arg()),
+        )
+        .subcommand(
+            Command::new("disable")
+                .about("Disable a data pack")
+                .arg(instance_arg())
+                .arg(world_arg())
+                .arg(pack_arg()),
+        )
+        .subcommand(
+            Command::new("remove")
+                .about("Delete a data pack from the world")
+                .arg(instance_arg())
+                .arg(world_arg())
+                .arg(pack_arg()),
+        )
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("list", sub)) => list(sub),
+        Some(("install", sub)) => install(sub),
+        Some(("enable", sub)) => set_enabled(sub, true),
+        Some(("disable", sub)) => set_enabled(sub, false),
+        Some(("remove", sub)) => remove(sub),
+        _ => Ok(()),
+    }
+}
+
+fn find_world(matches: &ArgMatches) -> Result<(Instance, World)> {
+    let instance = instance::find(matches.get_one::<String>("instance").unwrap())?;
+    let world = worlds::find(&instance, matches.get_one::<String>("world").unwrap())?;
+    Ok((instance, world))
+}
+
+fn list(matches: &ArgMatches) -> Result<()> {
+    let (_, world) = find_world(matches)?;
+    let packs = data::list(&world);
+    if packs.is_empty() {
+        println!("No data packs in {}", world.name);
+    }
+    for pack in packs {
+        println!(
+            "{:<9} {}{}  {}",
+            pack.status.label(),
+            pack.pack.name,
+            if pack.pack.folder { "/" } else { "" },
+            pack.pack.description
+        );
+    }
+    Ok(())
+}
+
+fn install(matches: &ArgMatches) -> Result<()> {
+    let (instance, world) = find_world(matches)?;
+    let pack = data::install(&instance, &world, matches.get_one::<String>("source").unwrap())?;
+    info!("Installed and enabled {} in {}", pack.pack.name, world.name);
+    Ok(())
+}
+
+fn set_enabled(matches: &ArgMatches, enabled: bool) -> Result<()> {
+    let (_, world) = find_world(matches)?;
+    let pack = data::find(&world, matches.get_one::<String>("pack").unwrap())?;
+    data::set_enabled(&world, &pack, enabled)?;
+    info!(
+        "{} {} in {}",
+        if enabled { "Enabled" } else { "Disabled" },
+        pack.pack.name,
+        world.name
+    );
+    Ok(())
+}
+
+fn remove(matches: &ArgMatches) -> Result<()> {
+    let (_, world) = find_world(matches)?;
+    let pack = data::find(&world, matches.get_one::<String>("pack").unwrap())?;
+    data::remove(&world, &pack)?;
+    info!("Removed {} from {}", pack.pack.name, world.name);
+    Ok(())
+}
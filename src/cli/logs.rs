@@ -0,0 +1,285 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use chrono::Local;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use color_eyre::eyre::{eyre, Result};
+use color_eyre::owo_colors::OwoColorize;
+
+use crate::{
+    info, instance,
+    logs::{
+        crash::{self, Analysis},
+        history::{self, Filter},
+        redact::Redactor,
+        Event, Level,
+    },
+};
+
+use super::instance_arg;
+
+/// Stack trace lines shown unless --full is given
+const STACK_PREVIEW: usize = 12;
+
+pub fn command() -> Command {
+    Command::new("logs")
+        .about("Inspect game logs and crash reports")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("list")
+                .about("List the logs of an instance, newest first")
+                .arg(instance_arg()),
+        )
+        .subcommand(
+            Command::new("show")
+                .about("Print a log, optionally filtered, or export it redacted")
+                .arg(instance_arg())
+                .arg(
+                    Arg::new("file")
+                        .help("Log file name, e.g. debug or 2024-01-01-1 (default: latest)")
+                        .default_value("latest.log")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("since")
+                        .long("since")
+                        .help("Only records from this time on (12:30, 2024-01-01 12:30, 2h)")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("until")
+                        .long("until")
+                        .help("Only records up to this time")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("level")
+                        .short('l')
+                        .long("level")
+                        .help("Lowest level to show")
+                        .value_parser(["trace", "debug", "info", "warn", "error", "fatal"])
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("grep")
+                        .short('g')
+                        .long("grep")
+                        .help("Only records matching this regular expression (case-insensitive)")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("redact")
+                        .long("redact")
+                        .help("Hide player names, tokens, UUIDs, IP addresses and home paths")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("export")
+                        .short('o')
+                        .long("export")
+                        .help("Write the redacted log to this file for sharing instead of printing it")
+                        .action(ArgAction::Set),
+                ),
+        )
+        .subcommand(
+            Command::new("crash")
+                .about("Explain the latest crash of an instance")
+                .arg(instance_arg())
+                .arg(
+                    Arg::new("file")
+                        .long("file")
+                        .help("Crash report or hs_err_pid log to analyze instead of the latest")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("full")
+                        .long("full")
+                        .help("Print the whole stack trace, mod list and system details")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("list", sub)) => list(sub),
+        Some(("show", sub)) => show(sub),
+        Some(("crash", sub)) => crash(sub),
+        _ => Ok(()),
+    }
+}
+
+fn list(matches: &ArgMatches) -> Result<()> {
+    let instance = instance::find(matches.get_one::<String>("instance").unwrap())?;
+    let files = history::list(&instance.path);
+    if files.is_empty() {
+        println!("{} has no logs yet", instance.name);
+    }
+    for file in files {
+        let modified = file
+            .modified
+            .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        println!("{:<28} {:>10}  {}", file.name, human_size(file.size), modified);
+    }
+    Ok(())
+}
+
+fn human_size(bytes: u64) -> String {
+    match bytes {
+        0..1024 => format!("{} B", bytes),
+        1024..1_048_576 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MiB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+fn filter(matches: &ArgMatches, file: &history::LogFile) -> Result<Filter> {
+    let date = file.date();
+    Ok(Filter {
+        since: matches
+            .get_one::<String>("since")
+            .map(|value| history::parse_time(value, date))
+            .transpose()?,
+        until: matches
+            .get_one::<String>("until")
+            .map(|value| history::parse_time(value, date))
+            .transpose()?,
+        min_level: matches.get_one::<String>("level").and_then(|level| Level::parse(level)),
+        pattern: matches
+            .get_one::<String>("grep")
+            .map(|query| history::search(query))
+            .transpose()?,
+    })
+}
+
+fn colored(event: &Event) -> String {
+    let text = event.format();
+    match event.level {
+        Level::Fatal | Level::Error => text.red().to_string(),
+        Level::Warn => text.yellow().to_string(),
+        Level::Debug | Level::Trace => text.dimmed().to_string(),
+        Level::Info => text,
+    }
+}
+
+fn show(matches: &ArgMatches) -> Result<()> {
+    let instance = instance::find(matches.get_one::<String>("instance").unwrap())?;
+    let file = history::find(&instance.path, matches.get_one::<String>("file").unwrap())?;
+    let filter = filter(matches, &file)?;
+    let mut redactor = Redactor::default();
+
+    if let Some(export) = matches.get_one::<String>("export") {
+        let mut out = BufWriter::new(File::create(export)?);
+        writeln!(
+            out,
+            "# {} from a Minecraft {} instance, exported {} with personal details redacted",
+            file.name,
+            instance.game_version,
+            Local::now().format("%Y-%m-%d %H:%M")
+        )?;
+        let mut count = 0;
+        let mut result = Ok(());
+        // names are learned from filtered out lines too, like `Setting user`
+        // before --since
+        history::scan(&file, &filter, |event, passes| {
+            let text = event.format();
+            redactor.learn(&text);
+            if passes && result.is_ok() {
+                result = writeln!(out, "{}", redactor.redact(&text));
+                count += 1;
+            }
+        })?;
+        result?;
+        out.flush()?;
+        info!("Exported {} records of {} to {}", count, file.name, export);
+        return Ok(());
+    }
+
+    let redact = matches.get_flag("redact");
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    history::scan(&file, &filter, |event, passes| {
+        if redact {
+            redactor.learn(&event.format());
+        }
+        if !passes {
+            return;
+        }
+        let event = if redact {
+            Event {
+                message: redactor.redact(&event.message),
+                thread: redactor.redact(&event.thread),
+                throwable: event.throwable.as_ref().map(|throwable| redactor.redact(throwable)),
+                ..event
+            }
+        } else {
+            event
+        };
+        // a closed pipe, e.g. from `| head`, just ends the output
+        let _ = writeln!(out, "{}", colored(&event));
+    })
+}
+
+fn crash(matches: &ArgMatches) -> Result<()> {
+    let instance = instance::find(matches.get_one::<String>("instance").unwrap())?;
+    let report = match matches.get_one::<String>("file") {
+        Some(file) => crash::load(Path::new(file))?,
+        None => crash::latest(&instance.path)?
+            .ok_or_else(|| eyre!("{} has no crash reports", instance.name))?,
+    };
+    print_analysis(&crash::analyze(report), matches.get_flag("full"));
+    Ok(())
+}
+
+fn print_analysis(analysis: &Analysis, full: bool) {
+    let report = &analysis.report;
+    let modified = report
+        .modified
+        .map(|time| format!(", {}", time.format("%Y-%m-%d %H:%M")))
+        .unwrap_or_default();
+    println!("{}  ({}{})", report.path.display().bold(), report.kind.name(), modified);
+    for (i, line) in report.description.lines().enumerate() {
+        println!("  {}  {}", if i == 0 { "Description" } else { "           " }, line);
+    }
+    println!();
+
+    if analysis.diagnoses.is_empty() {
+        println!("No known problem recognized, the stack trace below is the best lead.");
+    }
+    for diagnosis in &analysis.diagnoses {
+        println!("{}", diagnosis.title.yellow().bold());
+        println!("  {}", diagnosis.advice);
+        println!("  {}", format!("> {}", diagnosis.evidence).dimmed());
+        println!();
+    }
+
+    if !report.stack_trace.is_empty() {
+        println!("{}", "Stack trace".bold());
+        let lines: Vec<&str> = report.stack_trace.lines().collect();
+        let shown = if full { lines.len() } else { lines.len().min(STACK_PREVIEW) };
+        for line in &lines[..shown] {
+            println!("  {}", line.trim_end());
+        }
+        if shown < lines.len() {
+            println!("  ... {} more lines, see --full", lines.len() - shown);
+        }
+        println!();
+    }
+
+    if !report.mods.is_empty() {
+        println!("{}  {}", "Mods".bold(), report.mods.len());
+        if full {
+            for name in &report.mods {
+                println!("  {}", name);
+            }
+        }
+    }
+    if full && !report.system.is_empty() {
+        println!("{}", "System".bold());
+        for (key, value) in &report.system {
+            println!("  {:<24} {}", key, value);
+        }
+    }
+}
@@ -0,0 +1,420 @@
+use std::io::{self, Write};
+use std::time::Duration;
+
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use color_eyre::eyre::{eyre, Result};
+use crate::{
+    debug, error, info,
+    instance,
+    config::SETTINGS,
+    launch::{self, LaunchOptions, QuickPlay},
+    logger::{Format, Logger},
+    tui,
+};
+
+mod config;
+mod datapacks;
+mod logs;
+mod mods;
+mod options;
+mod packwiz;
+mod servers;
+mod shaders;
+mod worlds;
+
+fn instance_arg() -> Arg {
+    Arg::new("instance")
+        .help("Instance id or name")
+        .required(true)
+        .action(ArgAction::Set)
+}
+
+fn world_arg() -> Arg {
+    Arg::new("world")
+        .help("World folder or name")
+        .required(true)
+        .action(ArgAction::Set)
+}
+
+fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    let _ = io::stdout().flush();
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Parses `--set key=value`
+fn parse_setting(value: &str) -> std::result::Result<(String, String), String> {
+    let (key, value) = value
+        .split_once('=')
+        .ok_or_else(|| format!("expected key=value, e.g. general.debug=true, got '{}'", value))?;
+    if key.trim().is_empty() {
+        return Err(format!("missing key in '{}'", value));
+    }
+    Ok((key.trim().to_string(), value.trim().to_string()))
+}
+
+fn exit_on_error(result: Result<()>) {
+    if let Err(err) = result {
+        error!("{:#}", err);
+        std::process::exit(1);
+    }
+}
+
+pub fn init() {
+    let matches = Command::new("mcl")
+        .about("Minecraft CLI Launcher")
+        .version("1.0.0")
+        .subcommand_required(false)
+        .arg_required_else_help(false)
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("path")
+                .help("Read settings from this file instead of config.toml")
+                .value_parser(clap::value_parser!(std::path::PathBuf))
+                .global(true)
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new("set")
+                .long("set")
+                .value_name("key=value")
+                .help("Override a setting, e.g. general.debug=true (repeatable)")
+                .value_parser(parse_setting)
+                .global(true)
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new("log-format")
+                .long("log-format")
+                .help("Format of log lines: human or json, one object per line on stderr")
+                .value_parser(["human", "json"])
+                .global(true)
+                .action(ArgAction::Set),
+        )
+        .subcommand(
+            Command::new("launch")
+                .about("Launch Minecraft with a specific profile")
+                .arg_required_else_help(true)
+                .arg(
+                    Arg::new("profile")
+                        .short('p')
+                        .long("profile")
+                        .help("Profile to launch (e.g., main)")
+                        .required(true)
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("offline")
+                        .short('o')
+                        .long("offline")
+                        .help("Launch Minecraft in offline mode")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("memory")
+                        .short('m')
+                        .long("memory")
+                        .help("Set memory allocation for Minecraft (e.g., 4G, 512M)")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("resolution")
+                        .short('r')
+                        .long("resolution")
+                        .help("Set screen resolution (e.g., 1920x1080)")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("jvm-args")
+                        .short('j')
+                        .long("jvm-args")
+                        .help("Custom JVM arguments for Minecraft (e.g., -Xmx4G)")
+                        .action(ArgAction::Set)
+                        .num_args(1..),
+                )
+                .arg(
+                    Arg::new("no-window")
+                        .short('n')
+                        .long("no-window")
+                        .help("Run Minecraft in headless mode (no graphical window)")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("world")
+                        .long("world")
+                        .help("Open a singleplayer world right away (folder or name)")
+                        .conflicts_with_all(["join", "realm"])
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("join")
+                        .long("join")
+                        .help("Join a server right away (host[:port])")
+                        .conflicts_with("realm")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("realm")
+                        .long("realm")
+                        .help("Join a realm right away (realm id, 1.20+)")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("no-sync")
+                        .long("no-sync")
+                        .help("Skip syncing packwiz instances before launching")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("no-check")
+                        .long("no-check")
+                        .help("Launch even if `mods check` finds errors")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Print the command line and logging setup instead of launching")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("profiles")
+                .about("Manage Minecraft profiles")
+                .arg_required_else_help(true)
+                .arg(
+                    Arg::new("list")
+                        .short('l')
+                        .long("list")
+                        .help("List all available profiles")
+                        .conflicts_with("delete") // Ensures list and delete are mutually exclusive
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("delete")
+                        .short('d')
+                        .long("delete")
+                        .help("Delete a specific profile")
+                        .action(ArgAction::Set),
+                ),
+        )
+        .subcommand(
+            Command::new("kill")
+                .about("Stop the running game of an instance")
+                .arg(instance_arg())
+                .arg(
+                    Arg::new("force")
+                        .short('f')
+                        .long("force")
+                        .help("Kill immediately instead of asking the game to exit")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(config::command())
+        .subcommand(logs::command())
+        .subcommand(mods::command())
+        .subcommand(datapacks::command())
+        .subcommand(options::command())
+        .subcommand(packwiz::command())
+        .subcommand(servers::command())
+        .subcommand(shaders::command())
+        .subcommand(worlds::command())
+        .get_matches();
+
+    crate::config::set_overrides(crate::config::Overrides {
+        file: matches.get_one::<std::path::PathBuf>("config").cloned(),
+        values: matches
+            .get_many::<(String, String)>("set")
+            .map(|values| values.cloned().collect())
+            .unwrap_or_default(),
+    });
+    Logger::init(&SETTINGS);
+    if let Some(format) = matches.get_one::<String>("log-format").and_then(|name| Format::parse(name)) {
+        Logger::set_format(format);
+    }
+    // config check prints them itself and the TUI shows them in its messages
+    if !matches!(matches.subcommand_name(), None | Some("config")) {
+        crate::config::report_problems();
+    }
+
+    if matches.subcommand().is_none() {
+        tui::show().unwrap()
+    }
+
+    match matches.subcommand() {
+        Some(("launch", launch_matches)) => exit_on_error(launch(launch_matches)),
+        Some(("profiles", profiles_matches)) => {
+            if profiles_matches.get_flag("list") {
+                debug!("Listing all profiles...");
+            } else if let Some(profile) = profiles_matches.get_one::<String>("delete") {
+                debug!("Deleting profile '{}'...", profile);
+            }
+        }
+        Some(("kill", kill_matches)) => exit_on_error(kill(kill_matches)),
+        Some(("config", config_matches)) => exit_on_error(config::run(config_matches)),
+        Some(("logs", logs_matches)) => exit_on_error(logs::run(logs_matches)),
+        Some(("mods", mods_matches)) => exit_on_error(mods::run(mods_matches)),
+        Some(("datapacks", datapacks_matches)) => exit_on_error(datapacks::run(datapacks_matches)),
+        Some(("options", options_matches)) => exit_on_error(options::run(options_matches)),
+        Some(("packwiz", packwiz_matches)) => exit_on_error(packwiz::run(packwiz_matches)),
+        Some(("servers", servers_matches)) => exit_on_error(servers::run(servers_matches)),
+        Some(("shaders", shaders_matches)) => exit_on_error(shaders::run(shaders_matches)),
+        Some(("worlds", worlds_matches)) => exit_on_error(worlds::run(worlds_matches)),
+        _ => {},
+    }
+
+}
+
+fn parse_resolution(value: &str) -> Result<(u32, u32)> {
+    let (width, height) = value
+        .split_once('x')
+        .ok_or_else(|| eyre!("invalid resolution '{}', expected e.g. 1920x1080", value))?;
+    Ok((width.trim().parse()?, height.trim().parse()?))
+}
+
+fn launch(launch_matches: &ArgMatches) -> Result<()> {
+    let profile = launch_matches
+        .get_one::<String>("profile")
+        .expect("Profile is required");
+
+    let memory = launch_matches.get_one::<String>("memory").cloned();
+    let resolution = launch_matches
+        .get_one::<String>("resolution")
+        .map(|value| parse_resolution(value))
+        .transpose()?;
+
+    let jvm_args: Vec<String> = launch_matches
+        .get_many::<String>("jvm-args")
+        .map(|args| args.flat_map(|s| s.split_whitespace()).map(String::from).collect())
+        .unwrap_or_default();
+
+    let offline = launch_matches.get_flag("offline");
+    if offline {
+        debug!(instance = profile; "Launching profile '{}' in offline mode...", profile);
+    } else {
+        debug!(instance = profile; "Launching profile '{}' in online mode...", profile);
+    }
+
+    debug!("Memory: {}", memory.as_deref().unwrap_or("Default"));
+    debug!("Resolution: {:?}", resolution);
+    debug!("JVM Args: {}", jvm_args.join(" "));
+
+    if launch_matches.get_flag("no-window") {
+        debug!("Running in headless mode...");
+    }
+
+    let instance = instance::find(profile)?;
+    let dry_run = launch_matches.get_flag("dry-run");
+    if !dry_run {
+        // syncing or backing up under a running game would fight over its files
+        if let Some(pid) = instance::process::running(&instance) {
+            return Err(eyre!("{} is already running (pid {})", instance.name, pid));
+        }
+        launch::before_launch(
+            &instance,
+            !launch_matches.get_flag("no-sync"),
+            !launch_matches.get_flag("no-check"),
+        )?;
+    }
+
+    let quick_play = if let Some(world) = launch_matches.get_one::<String>("world") {
+        Some(QuickPlay::World(crate::worlds::find(&instance, world)?.folder))
+    } else if let Some(address) = launch_matches.get_one::<String>("join") {
+        Some(QuickPlay::Server(address.clone()))
+    } else {
+        launch_matches
+            .get_one::<String>("realm")
+            .map(|realm| QuickPlay::Realm(realm.clone()))
+    };
+
+    let options = LaunchOptions {
+        offline,
+        memory,
+        resolution,
+        jvm_args,
+        quick_play,
+        dry_run,
+        ..Default::default()
+    };
+
+    let prepared = launch::prepare(&instance, &options)?;
+    if dry_run {
+        print_dry_run(&prepared);
+        return Ok(());
+    }
+    let started = std::time::Instant::now();
+    let status = prepared.run()?;
+    info!(
+        instance = instance.id,
+        exit_code = status.code(),
+        duration_ms = started.elapsed().as_millis() as u64;
+        "{} exited with {}", instance.name, status
+    );
+    Ok(())
+}
+
+/// Quotes an argument for copying into a POSIX shell
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=+,@%".contains(c)) {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+fn print_dry_run(prepared: &launch::Prepared) {
+    let logging = &prepared.logging;
+    println!("Version    {}", prepared.version.id);
+    println!("Directory  {}", prepared.game_dir.display());
+    match &logging.config {
+        Some(config) => println!(
+            "Logging    {}{}",
+            config.display(),
+            if logging.missing { " (downloaded on launch)" } else { "" }
+        ),
+        None => println!("Logging    built into the game jar"),
+    }
+    println!("Log4Shell  {}", logging.mitigation);
+    println!();
+    println!("{} \\", shell_quote(&prepared.java));
+    for (i, arg) in prepared.args.iter().enumerate() {
+        let end = if i + 1 < prepared.args.len() { " \\" } else { "" };
+        println!("    {}{}", shell_quote(arg), end);
+    }
+}
+
+/// How long a polite stop may take before suggesting --force
+const KILL_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn kill(matches: &ArgMatches) -> Result<()> {
+    let instance = instance::find(matches.get_one::<String>("instance").unwrap())?;
+    let force = matches.get_flag("force");
+    let pid = instance::process::kill(&instance, force)?;
+    if instance::process::wait_exit(pid, KILL_TIMEOUT) {
+        info!("Stopped {} (pid {})", instance.name, pid);
+        Ok(())
+    } else {
+        Err(eyre!("{} (pid {}) is still running, try --force", instance.name, pid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shell_quote_leaves_no_expansions() {
+        assert_eq!(shell_quote("-Xmx4G"), "-Xmx4G");
+        assert_eq!(shell_quote("/usr/lib/jvm/bin/java"), "/usr/lib/jvm/bin/java");
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("${natives_directory}"), "'${natives_directory}'");
+        assert_eq!(shell_quote("$HOME/.minecraft"), "'$HOME/.minecraft'");
+        assert_eq!(shell_quote("{a,b}"), "'{a,b}'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    }
+}
@@ -0,0 +1,402 @@
+use std::fs;
+
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+    info,
+    instance::{self, Channel, Instance},
+    launch::{self, LaunchOptions},
+    mods::{self, bisect, check::Severity, update},
+};
+
+use super::{confirm, instance_arg};
+
+fn channel_arg() -> Arg {
+    Arg::new("channel")
+        .short('c')
+        .long("channel")
+        .help("Override the release channel (release, beta, alpha)")
+        .value_parser(["release", "beta", "alpha"])
+        .action(ArgAction::Set)
+}
+
+pub fn command() -> Command {
+    Command::new("mods")
+        .about("Manage the mods of an instance")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("list")
+                .about("List installed mods with their embedded metadata")
+                .arg(instance_arg())
+                .arg(
+                    Arg::new("lookup")
+                        .short('l')
+                        .long("lookup")
+                        .help("Identify unknown jars on Modrinth by their SHA-1")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("info")
+                .about("Show the metadata of a single mod")
+                .arg(instance_arg())
+                .arg(Arg::new("mod").required(true).action(ArgAction::Set)),
+        )
+        .subcommand(
+            Command::new("check")
+                .about("Check mods for conflicts and missing dependencies, failing on errors")
+                .arg(instance_arg()),
+        )
+        .subcommand(
+            Command::new("outdated")
+                .about("List mods with newer versions on Modrinth")
+                .arg(instance_arg())
+                .arg(channel_arg()),
+        )
+        .subcommand(
+            Command::new("update")
+                .about("Update one or all outdated mods")
+                .arg(instance_arg())
+                .arg(
+                    Arg::new("mod")
+                        .help("Mod to update (slug, name or file name)")
+                        .required_unless_present("all")
+                        .conflicts_with("all")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("all")
+                        .short('a')
+                        .long("all")
+                        .help("Update every outdated mod")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(channel_arg())
+                .arg(
+                    Arg::new("yes")
+                        .short('y')
+                        .long("yes")
+                        .help("Apply without asking for confirmation")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("enable")
+                .about("Enable a disabled mod")
+                .arg(instance_arg())
+                .arg(Arg::new("mod").required(true).action(ArgAction::Set)),
+        )
+        .subcommand(
+            Command::new("disable")
+                .about("Disable a mod by renaming it to .jar.disabled")
+                .arg(instance_arg())
+                .arg(Arg::new("mod").required(true).action(ArgAction::Set))
+                .arg(
+                    Arg::new("yes")
+                        .short('y')
+                        .long("yes")
+                        .help("Disable even if other mods depend on it")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("bisect")
+                .about("Find the mod causing a problem by repeatedly disabling half of them")
+                .arg(instance_arg())
+                .arg(
+                    Arg::new("auto")
+                        .long("auto")
+                        .help("Treat a clean exit as 'problem gone' instead of asking")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("reset")
+                        .long("reset")
+                        .help("Restore the mods of an interrupted bisection")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("pin")
+                .about("Prevent a mod from being updated")
+                .arg(instance_arg())
+                .arg(Arg::new("mod").required(true).action(ArgAction::Set)),
+        )
+        .subcommand(
+            Command::new("unpin")
+                .about("Allow a pinned mod to be updated again")
+                .arg(instance_arg())
+                .arg(Arg::new("mod").required(true).action(ArgAction::Set)),
+        )
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("list", sub)) => list(sub),
+        Some(("info", sub)) => info(sub),
+        Some(("check", sub)) => check(sub),
+        Some(("outdated", sub)) => outdated(sub),
+        Some(("update", sub)) => update(sub),
+        Some(("enable", sub)) => toggle(sub, true),
+        Some(("disable", sub)) => toggle(sub, false),
+        Some(("bisect", sub)) => bisect(sub),
+        Some(("pin", sub)) => pin(sub, true),
+        Some(("unpin", sub)) => pin(sub, false),
+        _ => Ok(()),
+    }
+}
+
+fn channel(matches: &ArgMatches) -> Option<Channel> {
+    matches
+        .get_one::<String>("channel")
+        .and_then(|value| Channel::parse(value))
+}
+
+fn list(matches: &ArgMatches) -> Result<()> {
+    let instance = instance::find(matches.get_one::<String>("instance").unwrap())?;
+    if matches.get_flag("lookup") {
+        let linked = mods::identify(&instance)?;
+        info!("{} mods linked to Modrinth", linked);
+    }
+
+    for info in mods::load(&instance) {
+        println!(
+            "{} {:<30} {:<20} {:<24} {}",
+            if info.file.enabled { "+" } else { "-" },
+            info.name(),
+            info.version(),
+            info.id().unwrap_or("-"),
+            info.project.as_ref().map_or("-", |project| project.slug.as_str()),
+        );
+    }
+    Ok(())
+}
+
+fn info(matches: &ArgMatches) -> Result<()> {
+    let instance = instance::find(matches.get_one::<String>("instance").unwrap())?;
+    let query = matches.get_one::<String>("mod").unwrap();
+    let info = mods::load(&instance)
+        .into_iter()
+        .find(|info| info.matches(query))
+        .ok_or_else(|| eyre!("no mod matching '{}' in '{}'", query, instance.name))?;
+
+    println!("{} {}", info.name(), info.version());
+    println!("file: {}", info.file.file_name);
+    if let Some(project) = &info.project {
+        println!("modrinth: {} ({})", project.slug, project.project_id);
+    }
+
+    let Some(meta) = &info.metadata else {
+        println!("no embedded metadata");
+        return Ok(());
+    };
+
+    println!("id: {}", meta.id);
+    println!("format: {}", meta.source.name());
+    if !meta.authors.is_empty() {
+        println!("authors: {}", meta.authors.join(", "));
+    }
+    if !meta.description.is_empty() {
+        println!("description: {}", meta.description);
+    }
+    if !meta.provides.is_empty() {
+        println!("provides: {}", meta.provides.join(", "));
+    }
+    for dep in &meta.dependencies {
+        println!(
+            "{:?}: {} {}",
+            dep.kind,
+            dep.id,
+            if dep.versions.is_empty() { "*".to_string() } else { dep.versions.join(" || ") }
+        );
+    }
+    Ok(())
+}
+
+fn check(matches: &ArgMatches) -> Result<()> {
+    let instance = instance::find(matches.get_one::<String>("instance").unwrap())?;
+    let problems = mods::check::check(&instance, &mods::load(&instance));
+
+    for problem in &problems {
+        let label = match problem.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        println!("{}: {}", label, problem.message);
+    }
+
+    let errors = problems
+        .iter()
+        .filter(|problem| problem.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        return Err(eyre!("{} error(s) found in '{}'", errors, instance.name));
+    }
+
+    info!("No problems found in '{}'", instance.name);
+    Ok(())
+}
+
+fn outdated(matches: &ArgMatches) -> Result<()> {
+    let instance = instance::find(matches.get_one::<String>("instance").unwrap())?;
+    let updates = update::outdated(&instance, channel(matches))?;
+
+    if updates.is_empty() {
+        info!("All mods of '{}' are up to date", instance.name);
+        return Ok(());
+    }
+
+    for update in &updates {
+        println!(
+            "{:<30} {} -> {} ({})",
+            update.title,
+            update.current.version_number,
+            update.latest.version_number,
+            update.latest.version_type
+        );
+    }
+    Ok(())
+}
+
+fn update(matches: &ArgMatches) -> Result<()> {
+    let instance = instance::find(matches.get_one::<String>("instance").unwrap())?;
+    let mut updates = update::outdated(&instance, channel(matches))?;
+
+    if let Some(query) = matches.get_one::<String>("mod") {
+        updates.retain(|update| update.matches(query));
+        if updates.is_empty() {
+            return Err(eyre!("'{}' is not installed, pinned or already up to date", query));
+        }
+    }
+
+    if updates.is_empty() {
+        info!("All mods of '{}' are up to date", instance.name);
+        return Ok(());
+    }
+
+    for update in &updates {
+        println!(
+            "{}: {} -> {}",
+            update.title, update.current.version_number, update.latest.version_number
+        );
+        for line in update.changelog_summary(5) {
+            println!("  {}", line);
+        }
+    }
+
+    if !matches.get_flag("yes") && !confirm(&format!("Apply {} update(s)?", updates.len())) {
+        return Ok(());
+    }
+
+    for update in &updates {
+        update::apply(&instance, update)?;
+        info!("Updated {} to {}", update.title, update.latest.version_number);
+    }
+    Ok(())
+}
+
+fn toggle(matches: &ArgMatches, enabled: bool) -> Result<()> {
+    let instance = instance::find(matches.get_one::<String>("instance").unwrap())?;
+    let query = matches.get_one::<String>("mod").unwrap();
+    let mods = mods::load(&instance);
+    let mut target = mods
+        .iter()
+        .find(|info| info.matches(query))
+        .cloned()
+        .ok_or_else(|| eyre!("no mod matching '{}' in '{}'", query, instance.name))?;
+
+    if !enabled {
+        let dependents = mods::dependents(&mods, &target);
+        if !dependents.is_empty() {
+            let names: Vec<&str> = dependents.iter().map(|info| info.name()).collect();
+            println!("{} is required by: {}", target.name(), names.join(", "));
+            if !matches.get_flag("yes") && !confirm("Disable it anyway?") {
+                return Ok(());
+            }
+        }
+    }
+
+    target.file.set_enabled(enabled)?;
+    info!(
+        "{} {}",
+        if enabled { "Enabled" } else { "Disabled" },
+        target.name()
+    );
+    Ok(())
+}
+
+fn crash_reports(instance: &Instance) -> usize {
+    fs::read_dir(instance.path.join("crash-reports"))
+        .map(|entries| entries.count())
+        .unwrap_or(0)
+}
+
+/// Launches the game once and decides whether the problem occurred
+fn bisect_step(instance: &Instance, step: &bisect::Step, auto: bool) -> Result<bool> {
+    println!(
+        "Step {}: launching with {} mods ({} suspects left)",
+        step.number,
+        step.enabled.len(),
+        step.suspects
+    );
+
+    let reports = crash_reports(instance);
+    let prepared = launch::prepare(instance, &LaunchOptions::default())?;
+    let status = prepared.run()?;
+
+    if !status.success() || crash_reports(instance) > reports {
+        println!("The game crashed ({})", status);
+        return Ok(true);
+    }
+    if auto {
+        return Ok(false);
+    }
+    Ok(confirm("Did the problem occur?"))
+}
+
+fn bisect(matches: &ArgMatches) -> Result<()> {
+    let instance = instance::find(matches.get_one::<String>("instance").unwrap())?;
+
+    if matches.get_flag("reset") {
+        if bisect::restore(&instance)? {
+            info!("Restored the mods of '{}'", instance.name);
+        } else {
+            info!("No bisection of '{}' in progress", instance.name);
+        }
+        return Ok(());
+    }
+
+    let auto = matches.get_flag("auto");
+    let outcome = bisect::run(&instance, |step| bisect_step(&instance, step, auto))?;
+    info!("Original mods of '{}' restored", instance.name);
+
+    let (found, mods) = match outcome {
+        bisect::Outcome::Found(mods) => (true, mods),
+        bisect::Outcome::Inconclusive(mods) => (false, mods),
+    };
+    if found {
+        println!("The problem is caused by:");
+    } else {
+        println!("These mods depend on each other and could not be split further:");
+    }
+    for info in mods {
+        println!("  {} ({})", info.name(), info.file.file_name);
+    }
+    Ok(())
+}
+
+fn pin(matches: &ArgMatches, pinned: bool) -> Result<()> {
+    let mut instance = instance::find(matches.get_one::<String>("instance").unwrap())?;
+    let name = matches.get_one::<String>("mod").unwrap();
+
+    instance.mods.pinned.retain(|pin| pin != name);
+    if pinned {
+        instance.mods.pinned.push(name.clone());
+    }
+    instance.save()?;
+
+    info!("{} '{}'", if pinned { "Pinned" } else { "Unpinned" }, name);
+    Ok(())
+}
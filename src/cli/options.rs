@@ -0,0 +1,207 @@
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+    error, info,
+    instance::{
+        self,
+        options::{self, Options, COMMON},
+        template,
+    },
+};
+
+use super::instance_arg;
+
+fn keys_arg() -> Arg {
+    Arg::new("keys")
+        .short('k')
+        .long("keys")
+        .help("Groups (keys, sound, video, controls, all) or patterns like key_* to copy")
+        .default_value("keys")
+        .num_args(1..)
+        .action(ArgAction::Append)
+}
+
+pub fn command() -> Command {
+    Command::new("options")
+        .about("View and edit options.txt, and copy settings between instances")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("show")
+                .about("Show common settings")
+                .arg(instance_arg())
+                .arg(
+                    Arg::new("all")
+                        .short('a')
+                        .long("all")
+                        .help("Print every entry of options.txt")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("set")
+                .about("Change a single setting")
+                .arg(instance_arg())
+                .arg(Arg::new("key").required(true).action(ArgAction::Set))
+                .arg(
+                    Arg::new("value")
+                        .help("Value as shown by `show`, e.g. 90° for fov or auto for guiScale")
+                        .required(true)
+                        .action(ArgAction::Set),
+                ),
+        )
+        .subcommand(
+            Command::new("apply")
+                .about("Copy selected settings from an instance or preset to other instances")
+                .arg(
+                    Arg::new("from")
+                        .long("from")
+                        .help("Instance to copy from, or preset:<name>")
+                        .required(true)
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("targets")
+                        .help("Instances to update")
+                        .required(true)
+                        .num_args(1..)
+                        .action(ArgAction::Append),
+                )
+                .arg(keys_arg()),
+        )
+        .subcommand(
+            Command::new("preset")
+                .about("Manage saved settings presets")
+                .subcommand_required(true)
+                .subcommand(Command::new("list").about("List presets"))
+                .subcommand(
+                    Command::new("save")
+                        .about("Save selected settings of an instance as a preset")
+                        .arg(Arg::new("name").required(true).action(ArgAction::Set))
+                        .arg(
+                            Arg::new("from")
+                                .long("from")
+                                .help("Instance to copy from")
+                                .required(true)
+                                .action(ArgAction::Set),
+                        )
+                        .arg(keys_arg()),
+                )
+                .subcommand(
+                    Command::new("delete")
+                        .about("Delete a preset")
+                        .arg(Arg::new("name").required(true).action(ArgAction::Set)),
+                ),
+        )
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("show", sub)) => show(sub),
+        Some(("set", sub)) => set(sub),
+        Some(("apply", sub)) => apply(sub),
+        Some(("preset", sub)) => match sub.subcommand() {
+            Some(("list", _)) => {
+                template::list_presets().iter().for_each(|name| println!("{}", name));
+                Ok(())
+            }
+            Some(("save", save)) => save_preset(save),
+            Some(("delete", delete)) => {
+                let name = delete.get_one::<String>("name").unwrap();
+                template::delete_preset(name)?;
+                info!("Deleted preset {}", name);
+                Ok(())
+            }
+            _ => Ok(()),
+        },
+        _ => Ok(()),
+    }
+}
+
+fn selected_keys(matches: &ArgMatches) -> Vec<String> {
+    let selectors: Vec<String> = matches
+        .get_many::<String>("keys")
+        .map(|keys| keys.cloned().collect())
+        .unwrap_or_default();
+    template::expand(&selectors)
+}
+
+fn show(matches: &ArgMatches) -> Result<()> {
+    let instance = instance::find(matches.get_one::<String>("instance").unwrap())?;
+    let options = Options::load(&instance)?;
+    if !options.exists() {
+        println!("{} has no options.txt yet, it is created on first launch", instance.name);
+        return Ok(());
+    }
+
+    if matches.get_flag("all") {
+        for (key, value) in options.entries() {
+            println!("{}:{}", key, value);
+        }
+        return Ok(());
+    }
+    for setting in COMMON {
+        let value = options
+            .value(setting.key, setting.kind)
+            .map_or("-".to_string(), |value| setting.kind.display(&value));
+        println!("{:<22} {:<10} ({})", setting.label, value, setting.key);
+    }
+    Ok(())
+}
+
+fn set(matches: &ArgMatches) -> Result<()> {
+    let instance = instance::find(matches.get_one::<String>("instance").unwrap())?;
+    let key = matches.get_one::<String>("key").unwrap();
+    let input = matches.get_one::<String>("value").unwrap();
+
+    let mut options = Options::load(&instance)?;
+    match options::kind(key) {
+        Some(kind) => {
+            let value = kind
+                .parse_input(input)
+                .ok_or_else(|| eyre!("'{}' is not a valid value for {}", input, key))?;
+            options.set_value(key, &value);
+        }
+        // unknown keys are written verbatim
+        None => options.set(key, input.clone()),
+    }
+    options.save()?;
+    info!("Set {} to {} in {}", key, input, instance.name);
+    Ok(())
+}
+
+fn apply(matches: &ArgMatches) -> Result<()> {
+    let source = template::load_source(matches.get_one::<String>("from").unwrap())?;
+    let patterns = selected_keys(matches);
+
+    let mut failed = false;
+    for target in matches.get_many::<String>("targets").unwrap() {
+        let result = instance::find(target)
+            .and_then(|instance| template::apply_to(&source, &instance, &patterns));
+        match result {
+            Ok(applied) => {
+                info!("Updated {} settings in {}", applied.changed, target);
+                for key in applied.skipped {
+                    info!("Kept {} in {}, its format differs between versions", key, target);
+                }
+            }
+            Err(err) => {
+                error!("{}: {:#}", target, err);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        return Err(eyre!("some instances were not updated"));
+    }
+    Ok(())
+}
+
+fn save_preset(matches: &ArgMatches) -> Result<()> {
+    let name = matches.get_one::<String>("name").unwrap();
+    let instance = instance::find(matches.get_one::<String>("from").unwrap())?;
+    let count = template::save_preset(name, &Options::load(&instance)?, &selected_keys(matches))?;
+    info!("Saved {} settings of {} as preset {}", count, instance.name, name);
+    Ok(())
+}
@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+    info,
+    instance::{self, Instance},
+    packwiz,
+};
+
+use super::instance_arg;
+
+pub fn command() -> Command {
+    Command::new("packwiz")
+        .about("Install, sync and export packwiz modpacks")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("import")
+                .about("Create an instance from a pack.toml path or URL")
+                .arg(
+                    Arg::new("pack")
+                        .help("Path or URL of the pack.toml")
+                        .required(true)
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("id")
+                        .long("id")
+                        .help("Instance id, defaults to the pack name")
+                        .action(ArgAction::Set),
+                ),
+        )
+        .subcommand(
+            Command::new("sync")
+                .about("Update an instance to the latest state of its pack")
+                .arg(instance_arg()),
+        )
+        .subcommand(
+            Command::new("export")
+                .about("Write an instance out as a packwiz tree")
+                .arg(instance_arg())
+                .arg(
+                    Arg::new("dir")
+                        .help("Directory to write pack.toml and index.toml into")
+                        .required(true)
+                        .action(ArgAction::Set),
+                ),
+        )
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("import", sub)) => import(sub),
+        Some(("sync", sub)) => {
+            let instance = instance::find(sub.get_one::<String>("instance").unwrap())?;
+            let source = instance
+                .packwiz
+                .clone()
+                .ok_or_else(|| eyre!("'{}' was not imported from a packwiz pack", instance.name))?;
+            sync(&instance, &source)
+        }
+        Some(("export", sub)) => export(sub),
+        _ => Ok(()),
+    }
+}
+
+/// Syncs the instance, listing files the launcher had to leave out
+fn sync(instance: &Instance, source: &str) -> Result<()> {
+    let report = packwiz::sync::sync(instance, source)?;
+    for name in &report.skipped {
+        println!("warning: {} has no download URL and must be installed by hand", name);
+    }
+    Ok(())
+}
+
+fn import(matches: &ArgMatches) -> Result<()> {
+    let source = matches.get_one::<String>("pack").unwrap();
+    let id = matches.get_one::<String>("id").map(String::as_str);
+    let instance = packwiz::import(source, id)?;
+    if let Some(version) = &instance.launch.version {
+        info!(
+            "'{}' launches version {}, install the {} loader if it is missing",
+            instance.id,
+            version,
+            instance.loader.as_str()
+        );
+    }
+    Ok(())
+}
+
+fn export(matches: &ArgMatches) -> Result<()> {
+    let instance = instance::find(matches.get_one::<String>("instance").unwrap())?;
+    let dir = PathBuf::from(matches.get_one::<String>("dir").unwrap());
+    let count = packwiz::export::export(&instance, &dir)?;
+    info!("Exported {} files of {} to {}", count, instance.name, dir.display());
+    Ok(())
+}
@@ -0,0 +1,307 @@
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use color_eyre::eyre::{eyre, Result};
+use color_eyre::owo_colors::OwoColorize;
+
+use crate::{
+    error, info,
+    instance::{self, Instance},
+    servers::{self, ping::Status, Server, Textures},
+    tui::widgets::ascii,
+};
+
+use super::instance_arg;
+
+fn server_arg() -> Arg {
+    Arg::new("server")
+        .help("Position in the list (from 1), name or address")
+        .required(true)
+        .action(ArgAction::Set)
+}
+
+fn textures_arg() -> Arg {
+    Arg::new("textures")
+        .long("textures")
+        .help("Server resource pack: prompt, enabled or disabled")
+        .value_parser(["prompt", "enabled", "disabled"])
+        .action(ArgAction::Set)
+}
+
+pub fn command() -> Command {
+    Command::new("servers")
+        .about("Manage the multiplayer server list of an instance")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("list")
+                .about("List servers in the order the game shows them")
+                .arg(instance_arg()),
+        )
+        .subcommand(
+            Command::new("add")
+                .about("Add a server")
+                .arg(instance_arg())
+                .arg(Arg::new("name").required(true).action(ArgAction::Set))
+                .arg(
+                    Arg::new("address")
+                        .help("host or host:port")
+                        .required(true)
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("position")
+                        .long("position")
+                        .help("Insert at this position (from 1) instead of the end")
+                        .value_parser(clap::value_parser!(usize))
+                        .action(ArgAction::Set),
+                )
+                .arg(textures_arg()),
+        )
+        .subcommand(
+            Command::new("edit")
+                .about("Change the name, address or resource pack setting of a server")
+                .arg(instance_arg())
+                .arg(server_arg())
+                .arg(Arg::new("name").long("name").action(ArgAction::Set))
+                .arg(Arg::new("address").long("address").action(ArgAction::Set))
+                .arg(textures_arg()),
+        )
+        .subcommand(
+            Command::new("move")
+                .about("Move a server to another position")
+                .arg(instance_arg())
+                .arg(server_arg())
+                .arg(
+                    Arg::new("position")
+                        .help("New position, from 1")
+                        .required(true)
+                        .value_parser(clap::value_parser!(usize))
+                        .action(ArgAction::Set),
+                ),
+        )
+        .subcommand(
+            Command::new("remove")
+                .about("Remove a server")
+                .arg(instance_arg())
+                .arg(server_arg()),
+        )
+        .subcommand(
+            Command::new("ping")
+                .about("Show the MOTD, players, version and latency of a server")
+                .arg_required_else_help(true)
+                .arg(
+                    Arg::new("address")
+                        .help("host or host:port")
+                        .conflicts_with("instance")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("instance")
+                        .short('i')
+                        .long("instance")
+                        .help("Ping every saved server of this instance instead")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("icon")
+                        .long("icon")
+                        .help("Also print the server icon")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("copy")
+                .about("Copy the server list of one instance to others")
+                .arg(
+                    Arg::new("from")
+                        .long("from")
+                        .help("Instance to copy from")
+                        .required(true)
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("targets")
+                        .help("Instances to update")
+                        .required(true)
+                        .num_args(1..)
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("replace")
+                        .long("replace")
+                        .help("Replace the target lists instead of adding missing servers")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("list", sub)) => list(sub),
+        Some(("add", sub)) => add(sub),
+        Some(("edit", sub)) => edit(sub),
+        Some(("move", sub)) => move_server(sub),
+        Some(("remove", sub)) => {
+            let instance = find_instance(sub)?;
+            let server = servers::remove(&instance, sub.get_one::<String>("server").unwrap())?;
+            info!("Removed {} ({}) from {}", server.name, server.ip, instance.name);
+            Ok(())
+        }
+        Some(("copy", sub)) => copy(sub),
+        Some(("ping", sub)) => ping(sub),
+        _ => Ok(()),
+    }
+}
+
+fn find_instance(matches: &ArgMatches) -> Result<Instance> {
+    instance::find(matches.get_one::<String>("instance").unwrap())
+}
+
+fn textures(matches: &ArgMatches) -> Option<Textures> {
+    matches
+        .get_one::<String>("textures")
+        .and_then(|value| Textures::parse(value))
+}
+
+fn list(matches: &ArgMatches) -> Result<()> {
+    let instance = find_instance(matches)?;
+    let list = servers::load(&instance)?;
+    if list.is_empty() {
+        println!("{} has no saved servers", instance.name);
+    }
+    for (i, server) in list.iter().enumerate() {
+        println!(
+            "{:>3}  {:<28} {:<32} {}",
+            i + 1,
+            server.name,
+            server.ip,
+            server.textures.name()
+        );
+    }
+    Ok(())
+}
+
+fn add(matches: &ArgMatches) -> Result<()> {
+    let instance = find_instance(matches)?;
+    let name = matches.get_one::<String>("name").unwrap();
+    let address = matches.get_one::<String>("address").unwrap();
+    let mut server = Server::new(name, address);
+    if let Some(textures) = textures(matches) {
+        server.textures = textures;
+    }
+    let at = matches
+        .get_one::<usize>("position")
+        .map(|position| position.saturating_sub(1));
+
+    servers::add(&instance, server, at)?;
+    info!("Added {} ({}) to {}", name, address, instance.name);
+    Ok(())
+}
+
+fn edit(matches: &ArgMatches) -> Result<()> {
+    let instance = find_instance(matches)?;
+    let name = matches.get_one::<String>("name");
+    let address = matches.get_one::<String>("address");
+    let textures = textures(matches);
+    if name.is_none() && address.is_none() && textures.is_none() {
+        return Err(eyre!("nothing to change, pass --name, --address or --textures"));
+    }
+
+    let server = servers::edit(&instance, matches.get_one::<String>("server").unwrap(), |server| {
+        if let Some(name) = name {
+            server.name = name.clone();
+        }
+        if let Some(address) = address {
+            server.ip = address.clone();
+        }
+        if let Some(textures) = textures {
+            server.textures = textures;
+        }
+    })?;
+    info!("Updated {} ({}) in {}", server.name, server.ip, instance.name);
+    Ok(())
+}
+
+fn move_server(matches: &ArgMatches) -> Result<()> {
+    let instance = find_instance(matches)?;
+    let query = matches.get_one::<String>("server").unwrap();
+    let position = *matches.get_one::<usize>("position").unwrap();
+    servers::move_to(&instance, query, position.saturating_sub(1))?;
+    info!("Moved {} to position {}", query, position);
+    Ok(())
+}
+
+fn copy(matches: &ArgMatches) -> Result<()> {
+    let source = instance::find(matches.get_one::<String>("from").unwrap())?;
+    let replace = matches.get_flag("replace");
+
+    let mut failed = false;
+    for target in matches.get_many::<String>("targets").unwrap() {
+        let result = instance::find(target)
+            .and_then(|instance| servers::copy(&source, &instance, replace));
+        match result {
+            Ok(added) => {
+                info!("Added {} servers to {}", added, target);
+            }
+            Err(err) => {
+                error!("{}: {:#}", target, err);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        return Err(eyre!("some instances were not updated"));
+    }
+    Ok(())
+}
+
+fn ping(matches: &ArgMatches) -> Result<()> {
+    let icon = matches.get_flag("icon");
+    if let Some(address) = matches.get_one::<String>("address") {
+        let (host, port) = servers::split_address(address);
+        let status = servers::ping::ping(&host, port, servers::ping::TIMEOUT)?;
+        print_status(&format!("{}:{}", host, port), &status, icon);
+        return Ok(());
+    }
+
+    let instance = instance::find(matches.get_one::<String>("instance").unwrap())?;
+    let list = servers::load(&instance)?;
+    // one thread per server so slow ones don't add up
+    let handles: Vec<_> = list
+        .into_iter()
+        .map(|server| std::thread::spawn(move || (server.ping(), server)))
+        .collect();
+    for handle in handles {
+        let Ok((result, server)) = handle.join() else {
+            continue;
+        };
+        match result {
+            Ok(status) => print_status(&format!("{} ({})", server.name, server.ip), &status, icon),
+            Err(err) => println!("{} ({})  {}\n", server.name, server.ip, format!("{:#}", err).red()),
+        }
+    }
+    Ok(())
+}
+
+fn print_status(title: &str, status: &Status, icon: bool) {
+    println!("{}  {} ms", title.bold(), status.latency.as_millis());
+    if icon {
+        if let Some(art) = status.favicon.as_deref().and_then(|png| ascii::render(png, 32)) {
+            println!("{}", art);
+        }
+    }
+    let protocol = status
+        .protocol
+        .map(|protocol| format!(" (protocol {})", protocol))
+        .unwrap_or_default();
+    let legacy = if status.legacy { ", legacy ping" } else { "" };
+    println!("  Version  {}{}{}", status.version, protocol, legacy);
+    let mut players = format!("{}/{}", status.online, status.max);
+    if !status.sample.is_empty() {
+        players.push_str(&format!(": {}", status.sample.join(", ")));
+    }
+    println!("  Players  {}", players);
+    for (i, line) in status.motd.lines().enumerate() {
+        println!("  {}  {}", if i == 0 { "MOTD   " } else { "       " }, line.trim());
+    }
+    println!();
+}
@@ -0,0 +1,110 @@
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+    info,
+    instance::{self, Instance},
+    packs::shader,
+};
+
+use super::instance_arg;
+
+pub fn command() -> Command {
+    Command::new("shaders")
+        .about("Manage the shader packs of an instance")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("list")
+                .about("List shader packs and the active one")
+                .arg(instance_arg()),
+        )
+        .subcommand(
+            Command::new("install")
+                .about("Download a shader pack from Modrinth")
+                .arg(instance_arg())
+                .arg(
+                    Arg::new("project")
+                        .help("Modrinth project slug or id")
+                        .required(true)
+                        .action(ArgAction::Set),
+                ),
+        )
+        .subcommand(
+            Command::new("use")
+                .about("Select the active shader pack")
+                .arg(instance_arg())
+                .arg(
+                    Arg::new("pack")
+                        .help("File or folder name in shaderpacks/")
+                        .required_unless_present("off")
+                        .conflicts_with("off")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("off")
+                        .long("off")
+                        .help("Turn shaders off")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("list", sub)) => list(sub),
+        Some(("install", sub)) => install(sub),
+        Some(("use", sub)) => select(sub),
+        _ => Ok(()),
+    }
+}
+
+fn find_instance(matches: &ArgMatches) -> Result<Instance> {
+    instance::find(matches.get_one::<String>("instance").unwrap())
+}
+
+fn list(matches: &ArgMatches) -> Result<()> {
+    let instance = find_instance(matches)?;
+    let packs = shader::list(&instance);
+    let loader = shader::detect(&instance);
+    let active = loader.and_then(|loader| shader::active(&instance, loader));
+
+    match loader {
+        Some(loader) => println!("Shader loader: {}", loader.name()),
+        None => println!("Shader loader: none"),
+    }
+    for pack in &packs {
+        let marker = if active.as_deref() == Some(pack.name.as_str()) { "*" } else { " " };
+        println!("{} {}{}", marker, pack.name, if pack.folder { "/" } else { "" });
+    }
+    if let Some(warning) = shader::warning(&instance, &packs, loader) {
+        println!("warning: {}", warning);
+    }
+    Ok(())
+}
+
+fn install(matches: &ArgMatches) -> Result<()> {
+    let instance = find_instance(matches)?;
+    let project = matches.get_one::<String>("project").unwrap();
+    let loader = shader::detect(&instance);
+    let path = shader::install(&instance, project, loader)?;
+    info!("Installed {}", path.display());
+    if loader.is_none() {
+        println!("warning: install Iris or OptiFine to use shader packs");
+    }
+    Ok(())
+}
+
+fn select(matches: &ArgMatches) -> Result<()> {
+    let instance = find_instance(matches)?;
+    let loader = shader::detect(&instance)
+        .ok_or_else(|| eyre!("'{}' has neither Iris nor OptiFine installed", instance.name))?;
+    let pack = matches.get_one::<String>("pack").map(String::as_str);
+    shader::set_active(&instance, loader, pack)?;
+    if let Some(pack) = pack {
+        info!("{} will use {}", instance.name, pack);
+    } else {
+        info!("Shaders turned off for {}", instance.name);
+    }
+    Ok(())
+}
@@ -0,0 +1,225 @@
+use std::path::PathBuf;
+
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use color_eyre::eyre::{eyre, Result};
+use color_eyre::owo_colors::OwoColorize;
+
+use crate::{
+    info,
+    instance::{self, Instance},
+    worlds::{
+        self, backup,
+        map::{self, Area, Dimension},
+    },
+};
+
+use super::{confirm, instance_arg, world_arg};
+
+pub fn command() -> Command {
+    Command::new("worlds")
+        .about("Manage the worlds of an instance")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .subcommand(
+            Command::new("list")
+                .about("List worlds, most recently played first")
+                .arg(instance_arg()),
+        )
+        .subcommand(
+            Command::new("backup")
+                .about("Back up a world to a compressed archive")
+                .arg(instance_arg())
+                .arg(world_arg()),
+        )
+        .subcommand(
+            Command::new("backups")
+                .about("List the backups of a world")
+                .arg(instance_arg())
+                .arg(world_arg()),
+        )
+        .subcommand(
+            Command::new("map")
+                .about("Render a top-down map of a world")
+                .arg(instance_arg())
+                .arg(world_arg())
+                .arg(
+                    Arg::new("dimension")
+                        .short('d')
+                        .long("dimension")
+                        .help("overworld, nether, end or a datapack dimension id")
+                        .default_value("overworld")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("radius")
+                        .short('r')
+                        .long("radius")
+                        .help("Blocks around the world spawn, everything generated if omitted")
+                        .value_parser(clap::value_parser!(u16))
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .help("PNG file to write, defaults to <world>-<dimension>.png")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("ascii")
+                        .long("ascii")
+                        .help("Print a text map this many columns wide instead of a PNG")
+                        .value_parser(clap::value_parser!(u16).range(8..))
+                        .action(ArgAction::Set),
+                ),
+        )
+        .subcommand(
+            Command::new("restore")
+                .about("Replace a world with one of its backups")
+                .arg(instance_arg())
+                .arg(world_arg())
+                .arg(
+                    Arg::new("backup")
+                        .help("Backup timestamp as shown by `mcl worlds backups`")
+                        .default_value("latest")
+                        .action(ArgAction::Set),
+                )
+                .arg(
+                    Arg::new("yes")
+                        .short('y')
+                        .long("yes")
+                        .help("Don't ask for confirmation")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    match matches.subcommand() {
+        Some(("list", sub)) => list(sub),
+        Some(("backup", sub)) => create_backup(sub),
+        Some(("backups", sub)) => list_backups(sub),
+        Some(("map", sub)) => render_map(sub),
+        Some(("restore", sub)) => restore(sub),
+        _ => Ok(()),
+    }
+}
+
+fn find_instance(matches: &ArgMatches) -> Result<Instance> {
+    instance::find(matches.get_one::<String>("instance").unwrap())
+}
+
+/// Folder of the world, which may have been deleted since it was backed up
+fn world_folder(instance: &Instance, query: &str) -> String {
+    worlds::find(instance, query)
+        .map(|world| world.folder)
+        .unwrap_or_else(|_| query.to_string())
+}
+
+fn list(matches: &ArgMatches) -> Result<()> {
+    let instance = find_instance(matches)?;
+    for world in worlds::list(&instance) {
+        println!(
+            "{:<24} {:<10} {:<10} {:>10}  saves/{}",
+            world.name,
+            world.mode_label(),
+            world.version.as_deref().unwrap_or("?"),
+            worlds::format_size(world.size),
+            world.folder
+        );
+    }
+    Ok(())
+}
+
+fn create_backup(matches: &ArgMatches) -> Result<()> {
+    let instance = find_instance(matches)?;
+    let world = worlds::find(&instance, matches.get_one::<String>("world").unwrap())?;
+    let backup = backup::create(&instance, &world)?;
+    info!(
+        "Backed up {} to {} ({})",
+        world.name,
+        backup.path.display(),
+        worlds::format_size(backup.size)
+    );
+    Ok(())
+}
+
+fn list_backups(matches: &ArgMatches) -> Result<()> {
+    let instance = find_instance(matches)?;
+    let folder = world_folder(&instance, matches.get_one::<String>("world").unwrap());
+    let backups = backup::list(&instance, &folder);
+    if backups.is_empty() {
+        println!("No backups of {}", folder);
+    }
+    for backup in backups {
+        println!("{}  {:>10}", backup.name(), worlds::format_size(backup.size));
+    }
+    Ok(())
+}
+
+fn render_map(matches: &ArgMatches) -> Result<()> {
+    let instance = find_instance(matches)?;
+    let world = worlds::find(&instance, matches.get_one::<String>("world").unwrap())?;
+    let dimension = Dimension::parse(matches.get_one::<String>("dimension").unwrap())?;
+    let area = matches
+        .get_one::<u16>("radius")
+        .map(|radius| Area::around(world.spawn.0, world.spawn.1, *radius as i32));
+
+    let map = map::build(&world, &dimension, area)?;
+    if let Some(&width) = matches.get_one::<u16>("ascii") {
+        // terminal cells are about twice as tall as wide
+        let width = width as usize;
+        let height = (width * map.area.height() / map.area.width().max(1) / 2).max(1);
+        for row in map.ascii(width, height) {
+            let line: String = row
+                .into_iter()
+                .map(|cell| match cell {
+                    Some((glyph, [r, g, b])) => glyph.to_string().truecolor(r, g, b).to_string(),
+                    None => " ".to_string(),
+                })
+                .collect();
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+
+    let output = matches
+        .get_one::<String>("output")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            let name = dimension.name().replace(':', "_");
+            PathBuf::from(format!("{}-{}.png", world.folder, name))
+        });
+    map.save_png(&output)?;
+    info!(
+        "Wrote {}x{} map of {} to {}",
+        map.area.width(),
+        map.area.height(),
+        world.name,
+        output.display()
+    );
+    Ok(())
+}
+
+fn restore(matches: &ArgMatches) -> Result<()> {
+    let instance = find_instance(matches)?;
+    let folder = world_folder(&instance, matches.get_one::<String>("world").unwrap());
+    let wanted = matches.get_one::<String>("backup").unwrap();
+
+    let backups = backup::list(&instance, &folder);
+    let backup = if wanted == "latest" {
+        backups.first()
+    } else {
+        backups.iter().find(|backup| backup.name() == *wanted)
+    }
+    .ok_or_else(|| eyre!("no backup '{}' of {}", wanted, folder))?;
+
+    if !matches.get_flag("yes")
+        && !confirm(&format!("Replace saves/{} with the backup from {}?", folder, backup.name()))
+    {
+        return Ok(());
+    }
+    backup::restore(&instance, &folder, backup)?;
+    info!("Restored {} from {}", folder, backup.name());
+    Ok(())
+}
@@ -1,42 +1,303 @@
-use config::{Config as ConfigLoader, ConfigError, File};
-use dirs_next::config_dir;
-use once_cell::sync::Lazy;
+use config::{Config as ConfigLoader, Environment};
+use dirs_next::{config_dir, data_dir, home_dir};
+use once_cell::sync::{Lazy, OnceCell};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use types::Config;
+use validate::{Problem, Severity};
 
-use crate::debug;
+use crate::{debug, error, warn};
 
 pub mod types;
+pub mod validate;
+
+/// Built-in defaults, the lowest layer. Also written to `config.toml` on
+/// first start so there is something to edit.
+pub const DEFAULTS: &str = include_str!("../../assets/default.toml");
+
+/// Environment variables like `MCL_GENERAL__DEBUG=true` set `general.debug`
+const ENV_PREFIX: &str = "MCL";
+const ENV_SEPARATOR: &str = "__";
 
 pub fn get_config_path() -> PathBuf {
     let base_dir = config_dir().unwrap();
     base_dir.join("mcl/")
 }
 
-fn ensure_config_exists(default_path: &str) -> PathBuf {
+pub fn get_data_path() -> PathBuf {
+    let base_dir = data_dir().unwrap();
+    base_dir.join("mcl/")
+}
+
+/// `$XDG_STATE_HOME/mcl/`, for the launcher's own logs
+pub fn get_state_path() -> PathBuf {
+    let base_dir = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .unwrap_or_else(|| home_dir().unwrap().join(".local/state"));
+    base_dir.join("mcl/")
+}
+
+/// Settings given on the command line, layered over the file and environment
+#[derive(Debug, Clone, Default)]
+pub struct Overrides {
+    /// Read instead of `config.toml`, from `--config`
+    pub file: Option<PathBuf>,
+    /// `key=value` pairs from `--set`
+    pub values: Vec<(String, String)>,
+}
+
+static OVERRIDES: OnceCell<Overrides> = OnceCell::new();
+
+/// Sets the command line layer. Has no effect once [`SETTINGS`] is loaded.
+pub fn set_overrides(overrides: Overrides) {
+    let _ = OVERRIDES.set(overrides);
+}
+
+pub fn overrides() -> Overrides {
+    OVERRIDES.get().cloned().unwrap_or_default()
+}
+
+/// Where a setting came from, in order of precedence from low to high
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Layer {
+    Default,
+    File(PathBuf),
+    Env,
+    Cli,
+}
+
+impl Layer {
+    /// Describes where `key` was set in this layer
+    pub fn origin(&self, key: &str) -> String {
+        match self {
+            Layer::Default => "default".to_string(),
+            Layer::File(path) => path.display().to_string(),
+            Layer::Env => format!(
+                "env {}_{}",
+                ENV_PREFIX,
+                key.replace('.', ENV_SEPARATOR).to_uppercase()
+            ),
+            Layer::Cli => "--set".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Layer::Default => f.write_str("defaults"),
+            Layer::File(path) => write!(f, "{}", path.display()),
+            Layer::Env => write!(f, "{}_* environment variables", ENV_PREFIX),
+            Layer::Cli => f.write_str("--set"),
+        }
+    }
+}
+
+/// The file layer: `--config` if given, `config.toml` otherwise
+pub fn config_file(overrides: &Overrides) -> PathBuf {
+    overrides
+        .file
+        .clone()
+        .unwrap_or_else(|| get_config_path().join("config.toml"))
+}
+
+/// Writes the defaults to `config.toml` if there is none yet
+fn ensure_config_exists() {
     let config_path = get_config_path().join("config.toml");
+    if config_path.exists() {
+        return;
+    }
+    let written = config_path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&config_path, DEFAULTS));
+    match written {
+        Ok(()) => {
+            debug!("Default configuration written to '{}'", config_path.display());
+        }
+        // the defaults are built in, so this only costs the template
+        Err(err) => {
+            debug!("Could not write '{}': {}", config_path.display(), err);
+        }
+    }
+}
+
+/// Parses a `--set` pair as a TOML line, so `true` and `3` keep their type
+/// and keys can be quoted like `logging.modules."launch::log4j"`. Values
+/// that aren't valid TOML are taken as strings.
+fn parse_override(key: &str, value: &str) -> Result<toml::Table, String> {
+    toml::from_str(&format!("{} = {}", key, value))
+        .or_else(|_| toml::from_str(&format!("{} = {}", key, toml::Value::String(value.to_string()))))
+        .map_err(|_| "invalid key, quote parts like logging.modules.\"launch::log4j\"".to_string())
+}
 
-    if !config_path.exists() {
-        if let Some(parent_dir) = config_path.parent() {
-            fs::create_dir_all(parent_dir).expect("Failed to create configuration directory");
+pub(crate) fn merge(into: &mut toml::Table, from: toml::Table) {
+    for (key, value) in from {
+        match (into.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(table)) => merge(existing, table),
+            (_, value) => {
+                into.insert(key, value);
+            }
         }
+    }
+}
+
+/// Settings of one layer, with the text they were read from for file layers
+#[derive(Debug, Clone)]
+pub struct LayerValues {
+    pub layer: Layer,
+    pub values: toml::Table,
+    pub text: Option<String>,
+}
 
-        fs::copy(default_path, &config_path).expect("Failed to copy default configuration file");
-        debug!("Default configuration copied to '{}'", config_path.display());
+/// Reads a TOML file, problems leave the layer empty
+fn file_layer(path: &Path, required: bool, problems: &mut Vec<Problem>) -> LayerValues {
+    let layer = Layer::File(path.to_path_buf());
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) => {
+            if required || path.exists() {
+                let mut problem = Problem::error(&layer, None, None, format!("cannot read file: {}", err));
+                problem.fallback = Some("using the defaults");
+                problems.push(problem);
+            }
+            return LayerValues {
+                layer,
+                values: toml::Table::new(),
+                text: None,
+            };
+        }
+    };
+    let values = match toml::from_str::<toml::Table>(&text) {
+        Ok(values) => values,
+        Err(err) => {
+            let line = err.span().map(|span| text[..span.start].lines().count().max(1));
+            let message = err.message().lines().collect::<Vec<_>>().join(", ");
+            let mut problem = Problem::error(&layer, line, None, message);
+            problem.fallback = Some("ignoring the file");
+            problems.push(problem);
+            toml::Table::new()
+        }
+    };
+    LayerValues {
+        layer,
+        values,
+        text: Some(text),
     }
+}
 
-    config_path
+fn env_layer(problems: &mut Vec<Problem>) -> LayerValues {
+    let values = ConfigLoader::builder()
+        .add_source(
+            Environment::with_prefix(ENV_PREFIX)
+                .prefix_separator("_")
+                .separator(ENV_SEPARATOR)
+                .try_parsing(true),
+        )
+        .build()
+        .and_then(|config| config.try_deserialize::<toml::Table>())
+        .unwrap_or_else(|err| {
+            problems.push(Problem::error(&Layer::Env, None, None, err.to_string()));
+            toml::Table::new()
+        });
+    LayerValues {
+        layer: Layer::Env,
+        values,
+        text: None,
+    }
 }
 
-pub fn load_config(config_path: &PathBuf) -> Result<Config, ConfigError> {
-    ConfigLoader::builder()
-        .add_source(File::from(config_path.clone()))
-        .build()?
-        .try_deserialize()
+/// Each layer on its own, lowest precedence first, and what was wrong with
+/// reading them
+pub fn layers(overrides: &Overrides) -> (Vec<LayerValues>, Vec<Problem>) {
+    let mut problems = Vec::new();
+    let defaults = toml::from_str(DEFAULTS).expect("built-in defaults are valid TOML");
+    let file = file_layer(&config_file(overrides), overrides.file.is_some(), &mut problems);
+    let env = env_layer(&mut problems);
+    let mut cli = toml::Table::new();
+    for (key, value) in &overrides.values {
+        match parse_override(key, value) {
+            Ok(table) => merge(&mut cli, table),
+            Err(err) => problems.push(Problem::error(&Layer::Cli, None, Some(key), err)),
+        }
+    }
+
+    let layers = vec![
+        LayerValues {
+            layer: Layer::Default,
+            values: defaults,
+            text: Some(DEFAULTS.to_string()),
+        },
+        file,
+        env,
+        LayerValues {
+            layer: Layer::Cli,
+            values: cli,
+            text: None,
+        },
+    ];
+    (layers, problems)
 }
 
+fn flatten(prefix: &str, value: toml::Value, into: &mut BTreeMap<String, toml::Value>) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                let key = if prefix.is_empty() { key } else { format!("{}.{}", prefix, key) };
+                flatten(&key, value, into);
+            }
+        }
+        value => {
+            into.insert(prefix.to_string(), value);
+        }
+    }
+}
+
+/// Every setting by dotted key, as set before validation, with the layer
+/// that set it
+pub fn origins(overrides: &Overrides) -> BTreeMap<String, (toml::Value, Layer)> {
+    let mut settings = BTreeMap::new();
+    for layer in layers(overrides).0 {
+        let mut values = BTreeMap::new();
+        flatten("", toml::Value::Table(layer.values), &mut values);
+        for (key, value) in values {
+            settings.insert(key, (value, layer.layer.clone()));
+        }
+    }
+    settings
+}
+
+static PROBLEMS: OnceCell<Vec<Problem>> = OnceCell::new();
+
 pub static SETTINGS: Lazy<Config> = Lazy::new(|| {
-    let path: PathBuf = ensure_config_exists("assets/default.toml");
-    load_config(&path).expect("Failed to load configuration")
+    let overrides = overrides();
+    if overrides.file.is_none() {
+        ensure_config_exists();
+    }
+    let (config, problems) = validate::load(&overrides);
+    let _ = PROBLEMS.set(problems);
+    config
 });
+
+/// What was wrong with the configuration [`SETTINGS`] was loaded from
+pub fn problems() -> &'static [Problem] {
+    Lazy::force(&SETTINGS);
+    PROBLEMS.get().map_or(&[], Vec::as_slice)
+}
+
+/// Logs the configuration problems, once the logger is set up
+pub fn report_problems() {
+    for problem in problems() {
+        match problem.severity {
+            Severity::Error => {
+                error!("{}", problem);
+            }
+            Severity::Warning => {
+                warn!("{}", problem);
+            }
+        }
+    }
+}
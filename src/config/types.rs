@@ -1,6 +1,7 @@
+use std::collections::HashMap;
 use serde::{Deserialize, Deserializer};
 use ratatui::style::Color;
-use crate::error;
+use crate::logger::{Format, Level};
 
 #[derive(Debug, Deserialize)]
 pub struct General {
@@ -31,15 +32,65 @@ pub struct Colors {
     pub row_alternate_bg: Color,  // Alternate row background color
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Logging {
+    pub level: Option<Level>,     // Defaults to info, or debug with general.debug
+    pub format: Format,           // "human" or "json" for the terminal
+    pub file: bool,               // Also write to ~/.local/state/mcl/mcl.log
+    pub max_size: u64,            // KiB before the file is rotated
+    pub max_files: usize,         // Rotated files kept
+    pub modules: HashMap<String, Level>, // Levels for modules, e.g. "launch" = "trace"
+}
+
+impl Default for Logging {
+    fn default() -> Self {
+        Logging {
+            level: None,
+            format: Format::Human,
+            file: true,
+            max_size: 1024,
+            max_files: 3,
+            modules: HashMap::new(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Config {
     pub general: General,
     pub colors: Colors,
+    #[serde(default)]
+    pub logging: Logging,
 }
 
 
-pub fn parse_color(color: &str) -> Color {
-    match color.to_lowercase().as_str() {
+/// Color names accepted besides `#rrggbb`
+pub const COLOR_NAMES: &[&str] = &[
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "white",
+    "gray",
+    "grey",
+    "darkgray",
+    "darkgrey",
+    "lightred",
+    "lightgreen",
+    "lightyellow",
+    "lightblue",
+    "lightmagenta",
+    "lightcyan",
+    "reset",
+];
+
+pub fn parse_color(color: &str) -> Result<Color, String> {
+    let color = color.to_lowercase();
+    Ok(match color.as_str() {
         "black" => Color::Black,
         "red" => Color::Red,
         "green" => Color::Green,
@@ -57,34 +108,21 @@ pub fn parse_color(color: &str) -> Color {
         "lightmagenta" => Color::LightMagenta,
         "lightcyan" => Color::LightCyan,
         "reset" => Color::Reset,
-        hex if hex.starts_with('#') && hex.len() == 7 => {
-            let r = u8::from_str_radix(&hex[1..3], 16).unwrap_or_else(|_| {
-                error!("Invalid hex color format: {}", color);
-                255
-            });
-            let g = u8::from_str_radix(&hex[3..5], 16).unwrap_or_else(|_| {
-                error!("Invalid hex color format: {}", color);
-                255
-            });
-            let b = u8::from_str_radix(&hex[5..7], 16).unwrap_or_else(|_| {
-                error!("Invalid hex color format: {}", color);
-                255
-            });
-            Color::Rgb(r, g, b)
-        }
-        _ => {
-            error!("Invalid color name or hex value: {}", color);
-            panic!("Invalid config");
+        hex if hex.starts_with('#') => {
+            let channel = |range| hex.get(range).and_then(|digits| u8::from_str_radix(digits, 16).ok());
+            match (hex.len(), channel(1..3), channel(3..5), channel(5..7)) {
+                (7, Some(r), Some(g), Some(b)) => Color::Rgb(r, g, b),
+                _ => return Err(format!("invalid hex color '{}', expected #rrggbb", color)),
+            }
         }
-    }
+        _ => return Err(format!("unknown color '{}'", color)),
+    })
 }
 
-
-
 fn deserialize_color<'de, D>(deserializer: D) -> Result<Color, D::Error>
 where
     D: Deserializer<'de>,
 {
     let color_str: String = Deserialize::deserialize(deserializer)?;
-    Ok(parse_color(&color_str))
+    parse_color(&color_str).map_err(serde::de::Error::custom)
 }
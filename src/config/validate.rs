@@ -0,0 +1,409 @@
+use std::fmt;
+
+use super::{
+    layers, merge,
+    types::{parse_color, Config, COLOR_NAMES},
+    Layer, LayerValues, Overrides,
+};
+
+/// Every setting there is and what it holds, besides `logging.modules.*`
+const KEYS: &[(&str, Kind)] = &[
+    ("general.debug", Kind::Bool),
+    ("colors.background", Kind::Color),
+    ("colors.foreground", Kind::Color),
+    ("colors.border_focused", Kind::Color),
+    ("colors.border_unfocused", Kind::Color),
+    ("colors.row_highlight", Kind::Color),
+    ("colors.row_background", Kind::Color),
+    ("colors.row_alternate_bg", Kind::Color),
+    ("logging.level", Kind::Level),
+    ("logging.format", Kind::Format),
+    ("logging.file", Kind::Bool),
+    ("logging.max_size", Kind::Count),
+    ("logging.max_files", Kind::Count),
+];
+
+/// Tables of user-chosen keys
+const MODULES: &str = "logging.modules";
+
+const LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+const FORMATS: &[&str] = &["human", "json"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Bool,
+    /// Whole number of at least 0
+    Count,
+    Color,
+    Level,
+    Format,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Ignored, like an unknown key
+    Warning,
+    /// Replaced by the default
+    Error,
+}
+
+/// Something wrong with a setting and where it was set
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub severity: Severity,
+    /// File, environment variable or `--set`
+    pub source: String,
+    pub line: Option<usize>,
+    pub key: Option<String>,
+    pub message: String,
+    /// What was probably meant
+    pub suggestion: Option<String>,
+    /// What the launcher does instead, like "using the default"
+    pub fallback: Option<&'static str>,
+}
+
+impl Problem {
+    fn new(severity: Severity, layer: &Layer, line: Option<usize>, key: Option<&str>, message: String) -> Problem {
+        Problem {
+            severity,
+            source: key.map_or_else(|| layer.to_string(), |key| layer.origin(key)),
+            line,
+            key: key.map(str::to_string),
+            message,
+            suggestion: None,
+            fallback: None,
+        }
+    }
+
+    pub(super) fn error(layer: &Layer, line: Option<usize>, key: Option<&str>, message: String) -> Problem {
+        Problem::new(Severity::Error, layer, line, key, message)
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        if let Some(key) = &self.key {
+            write!(f, ": {}", key)?;
+        }
+        write!(f, ": {}", self.message)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, ", did you mean {}?", suggestion)?;
+        }
+        if let Some(fallback) = self.fallback {
+            write!(f, " ({})", fallback)?;
+        }
+        Ok(())
+    }
+}
+
+fn kind(key: &str) -> Option<Kind> {
+    if key.strip_prefix(MODULES).is_some_and(|rest| rest.starts_with('.')) {
+        return Some(Kind::Level);
+    }
+    KEYS.iter().find(|(known, _)| *known == key).map(|(_, kind)| *kind)
+}
+
+/// Whether settings live below `key`, like `colors` or `logging.modules`
+fn is_table(key: &str) -> bool {
+    key == MODULES
+        || KEYS
+            .iter()
+            .any(|(known, _)| known.strip_prefix(key).is_some_and(|rest| rest.starts_with('.')))
+}
+
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let cost = usize::from(a != *b);
+            current.push((previous[j] + cost).min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// The candidate closest to a misspelt `input`, if any is close enough
+fn closest<'a>(input: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let input = input.to_lowercase();
+    candidates
+        .into_iter()
+        .map(|candidate| (distance(&input, candidate), candidate))
+        .filter(|(distance, _)| *distance <= (input.chars().count() / 3).max(1))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+fn describe(value: &toml::Value) -> String {
+    match value {
+        toml::Value::Table(_) => "a table".to_string(),
+        toml::Value::Array(_) => "a list".to_string(),
+        value => value.to_string(),
+    }
+}
+
+/// Checks a value, giving the problem and a suggestion if it's wrong
+fn check(kind: Kind, value: &toml::Value) -> Result<(), (String, Option<String>)> {
+    let text = value.as_str();
+    match kind {
+        Kind::Bool if value.is_bool() => Ok(()),
+        Kind::Bool => Err((format!("expected true or false, got {}", describe(value)), None)),
+        Kind::Count if value.as_integer().is_some_and(|count| count >= 0) => Ok(()),
+        Kind::Count => Err((format!("expected a whole number, got {}", describe(value)), None)),
+        Kind::Color => match text {
+            Some(color) => parse_color(color)
+                .map(|_| ())
+                .map_err(|err| (err, closest(color, COLOR_NAMES.iter().copied()))),
+            None => Err((format!("expected a color name or #rrggbb, got {}", describe(value)), None)),
+        },
+        Kind::Level | Kind::Format => {
+            let (names, what) = if kind == Kind::Level {
+                (LEVELS, "level")
+            } else {
+                (FORMATS, "log format")
+            };
+            match text {
+                Some(name) if names.contains(&name) => Ok(()),
+                Some(name) => Err((
+                    format!("unknown {} '{}', expected one of {}", what, name, names.join(", ")),
+                    closest(name, names.iter().copied()),
+                )),
+                None => Err((format!("expected a {} name, got {}", what, describe(value)), None)),
+            }
+        }
+    }
+}
+
+fn split_key(key: &str) -> Vec<String> {
+    key.split('.')
+        .map(|part| part.trim().trim_matches('"').trim_matches('\'').to_string())
+        .collect()
+}
+
+/// Line of a key or table header in a TOML file, 1-based
+fn find_line(text: &str, path: &[String]) -> Option<usize> {
+    let mut table = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[') {
+            table = split_key(header.trim_start_matches('[').split(']').next().unwrap_or_default());
+            if table == path {
+                return Some(i + 1);
+            }
+        } else if let Some((key, _)) = line.split_once('=') {
+            let mut full = table.clone();
+            full.extend(split_key(key));
+            if full == path {
+                return Some(i + 1);
+            }
+        }
+    }
+    None
+}
+
+fn lookup<'a>(table: &'a toml::Table, path: &[String]) -> Option<&'a toml::Value> {
+    let (first, rest) = path.split_first()?;
+    let value = table.get(first)?;
+    match (rest.is_empty(), value) {
+        (true, value) => Some(value),
+        (false, toml::Value::Table(table)) => lookup(table, rest),
+        (false, _) => None,
+    }
+}
+
+struct Checker<'a> {
+    layers: &'a [LayerValues],
+    problems: Vec<Problem>,
+}
+
+impl Checker<'_> {
+    /// Records a problem with the setting at `path`, located in the highest
+    /// layer that sets it
+    fn report(&mut self, severity: Severity, path: &[String], message: String, suggestion: Option<String>) {
+        let fallback = match severity {
+            Severity::Error => "using the default",
+            Severity::Warning => "ignoring it",
+        };
+        let key = path.join(".");
+        let Some(layer) = self
+            .layers
+            .iter()
+            .rev()
+            .find(|layer| lookup(&layer.values, path).is_some())
+        else {
+            return;
+        };
+        let line = match (&layer.layer, &layer.text) {
+            (Layer::File(_), Some(text)) => find_line(text, path),
+            _ => None,
+        };
+        let mut problem = Problem::new(severity, &layer.layer, line, Some(&key), message);
+        problem.suggestion = suggestion;
+        problem.fallback = Some(fallback);
+        self.problems.push(problem);
+    }
+
+    /// The valid settings of a table, reporting the rest
+    fn clean(&mut self, prefix: &[String], table: toml::Table) -> toml::Table {
+        let mut valid = toml::Table::new();
+        for (name, value) in table {
+            let mut path = prefix.to_vec();
+            path.push(name.clone());
+            let key = path.join(".");
+
+            match (kind(&key), value) {
+                (Some(kind), value) => match check(kind, &value) {
+                    Ok(()) => {
+                        valid.insert(name, value);
+                    }
+                    Err((message, suggestion)) => self.report(Severity::Error, &path, message, suggestion),
+                },
+                (None, toml::Value::Table(table)) if is_table(&key) => {
+                    valid.insert(name, toml::Value::Table(self.clean(&path, table)));
+                }
+                (None, value) if is_table(&key) => self.report(
+                    Severity::Error,
+                    &path,
+                    format!("expected a table, got {}", describe(&value)),
+                    None,
+                ),
+                (None, _) => {
+                    let known = KEYS
+                        .iter()
+                        .flat_map(|(known, _)| [*known, known.split('.').next().unwrap_or_default()]);
+                    self.report(Severity::Warning, &path, "unknown setting".to_string(), closest(&key, known))
+                }
+            }
+        }
+        valid
+    }
+}
+
+/// Loads the layered configuration. Invalid values fall back to their
+/// defaults and unknown ones are ignored, all of it reported as problems.
+pub fn load(overrides: &Overrides) -> (Config, Vec<Problem>) {
+    let (layers, problems) = layers(overrides);
+    let mut merged = toml::Table::new();
+    for layer in &layers {
+        merge(&mut merged, layer.values.clone());
+    }
+
+    let mut checker = Checker {
+        layers: &layers,
+        problems,
+    };
+    let valid = checker.clean(&[], merged);
+    let mut problems = checker.problems;
+    problems.sort_by(|a, b| a.source.cmp(&b.source).then(a.line.cmp(&b.line)));
+
+    // what was dropped comes back from the defaults
+    let defaults = layers[0].values.clone();
+    let mut settings = defaults.clone();
+    merge(&mut settings, valid);
+    let config = toml::Value::Table(settings).try_into::<Config>().unwrap_or_else(|err| {
+        let mut problem = Problem::error(&Layer::Default, None, None, err.to_string());
+        problem.fallback = Some("using the defaults");
+        problems.push(problem);
+        toml::Value::Table(defaults)
+            .try_into()
+            .expect("built-in defaults are a valid configuration")
+    });
+    (config, problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::config::DEFAULTS;
+
+    fn path(key: &str) -> Vec<String> {
+        split_key(key)
+    }
+
+    /// Problems with a config file layered over the defaults
+    fn problems(text: &str) -> Vec<Problem> {
+        let layers = [
+            LayerValues {
+                layer: Layer::Default,
+                values: toml::from_str(DEFAULTS).unwrap(),
+                text: Some(DEFAULTS.to_string()),
+            },
+            LayerValues {
+                layer: Layer::File(PathBuf::from("config.toml")),
+                values: toml::from_str(text).unwrap(),
+                text: Some(text.to_string()),
+            },
+        ];
+        let mut merged = toml::Table::new();
+        for layer in &layers {
+            merge(&mut merged, layer.values.clone());
+        }
+        let mut checker = Checker {
+            layers: &layers,
+            problems: Vec::new(),
+        };
+        checker.clean(&[], merged);
+        checker.problems
+    }
+
+    #[test]
+    fn finds_lines_of_keys_and_tables() {
+        let text = "# comment\n[general]\ndebug = true\n\n[logging]\nlevel = \"info\"\n\n[logging.modules]\n\"launch::log4j\" = \"trace\"\n";
+        assert_eq!(find_line(text, &path("general")), Some(2));
+        assert_eq!(find_line(text, &path("general.debug")), Some(3));
+        assert_eq!(find_line(text, &path("logging.level")), Some(6));
+        assert_eq!(find_line(text, &path("logging.modules")), Some(8));
+        assert_eq!(find_line(text, &["logging".into(), "modules".into(), "launch::log4j".into()]), Some(9));
+        assert_eq!(find_line(text, &path("colors.foreground")), None);
+        assert_eq!(find_line("logging.level = \"info\"\n", &path("logging.level")), Some(1));
+    }
+
+    #[test]
+    fn suggests_close_names() {
+        assert_eq!(closest("debg", ["debug", "info"]), Some("debug".to_string()));
+        assert_eq!(closest("TRCE", LEVELS.iter().copied()), Some("trace".to_string()));
+        // two edits are too many for a five letter word
+        assert_eq!(closest("degub", ["debug", "info"]), None);
+        assert_eq!(closest("verbose", LEVELS.iter().copied()), None);
+        assert_eq!(distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn reports_located_problems() {
+        let problems = problems("[general]\ndebug = \"yes\"\n\n[logging]\nlevel = \"inf\"\nmax_szie = 10\n");
+        assert_eq!(problems.len(), 3);
+
+        let debug = problems.iter().find(|p| p.key.as_deref() == Some("general.debug")).unwrap();
+        assert_eq!((debug.severity, debug.line), (Severity::Error, Some(2)));
+
+        let level = problems.iter().find(|p| p.key.as_deref() == Some("logging.level")).unwrap();
+        assert_eq!(level.line, Some(5));
+        assert_eq!(level.suggestion.as_deref(), Some("info"));
+        assert_eq!(
+            level.to_string(),
+            "config.toml:5: logging.level: unknown level 'inf', expected one of error, warn, info, debug, trace, \
+             did you mean info? (using the default)"
+        );
+
+        let unknown = problems.iter().find(|p| p.key.as_deref() == Some("logging.max_szie")).unwrap();
+        assert_eq!((unknown.severity, unknown.line), (Severity::Warning, Some(6)));
+        assert_eq!(unknown.suggestion.as_deref(), Some("logging.max_size"));
+    }
+
+    #[test]
+    fn defaults_are_valid() {
+        assert!(problems("").is_empty());
+        assert!(problems("[colors]\nforeground = \"#ff00ff\"\n[logging.modules]\nlaunch = \"trace\"\n").is_empty());
+    }
+}
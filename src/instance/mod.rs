@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::get_data_path, debug};
+
+pub mod options;
+pub mod process;
+pub mod template;
+
+pub fn get_instances_path() -> PathBuf {
+    get_data_path().join("instances")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Loader {
+    #[default]
+    Vanilla,
+    Fabric,
+    Quilt,
+    Forge,
+    NeoForge,
+}
+
+impl Loader {
+    /// Loader name as used by the Modrinth API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Loader::Vanilla => "minecraft",
+            Loader::Fabric => "fabric",
+            Loader::Quilt => "quilt",
+            Loader::Forge => "forge",
+            Loader::NeoForge => "neoforge",
+        }
+    }
+}
+
+/// Release channel a mod is allowed to update to. Each channel includes the
+/// more stable ones, so `Beta` accepts releases and betas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    #[default]
+    Release,
+    Beta,
+    Alpha,
+}
+
+impl Channel {
+    pub fn parse(value: &str) -> Option<Channel> {
+        match value.to_lowercase().as_str() {
+            "release" => Some(Channel::Release),
+            "beta" => Some(Channel::Beta),
+            "alpha" => Some(Channel::Alpha),
+            _ => None,
+        }
+    }
+
+    /// Whether a Modrinth `version_type` is allowed on this channel
+    pub fn allows(&self, version_type: &str) -> bool {
+        Channel::parse(version_type).is_some_and(|channel| channel <= *self)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModSettings {
+    #[serde(default)]
+    pub channel: Channel,
+
+    /// Per-mod channel overrides, keyed by project slug
+    #[serde(default)]
+    pub channels: HashMap<String, Channel>,
+
+    /// Project slugs or file names that must never be updated
+    #[serde(default)]
+    pub pinned: Vec<String>,
+}
+
+impl ModSettings {
+    pub fn channel_for(&self, slug: &str) -> Channel {
+        self.channels.get(slug).copied().unwrap_or(self.channel)
+    }
+
+    pub fn is_pinned(&self, keys: &[&str]) -> bool {
+        self.pinned.iter().any(|pin| keys.contains(&pin.as_str()))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LaunchSettings {
+    /// Version id in the shared versions directory, defaults to `game_version`
+    pub version: Option<String>,
+    pub java: Option<String>,
+    pub memory: Option<String>,
+    #[serde(default)]
+    pub jvm_args: Vec<String>,
+}
+
+/// Automatic world backups, see [`crate::worlds::backup`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupSettings {
+    /// Back up every world before each launch
+    #[serde(default)]
+    pub auto: bool,
+
+    /// Most recent backups that are always kept
+    #[serde(default = "BackupSettings::default_keep_last")]
+    pub keep_last: usize,
+
+    /// Days for which the newest backup of the day is kept
+    #[serde(default = "BackupSettings::default_keep_daily")]
+    pub keep_daily: usize,
+
+    /// Weeks for which the newest backup of the week is kept
+    #[serde(default = "BackupSettings::default_keep_weekly")]
+    pub keep_weekly: usize,
+}
+
+impl BackupSettings {
+    fn default_keep_last() -> usize {
+        5
+    }
+
+    fn default_keep_daily() -> usize {
+        7
+    }
+
+    fn default_keep_weekly() -> usize {
+        4
+    }
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        BackupSettings {
+            auto: false,
+            keep_last: Self::default_keep_last(),
+            keep_daily: Self::default_keep_daily(),
+            keep_weekly: Self::default_keep_weekly(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Instance {
+    #[serde(skip)]
+    pub id: String,
+
+    #[serde(skip)]
+    pub path: PathBuf,
+
+    pub name: String,
+    pub game_version: String,
+
+    #[serde(default)]
+    pub loader: Loader,
+
+    #[serde(default)]
+    pub launch: LaunchSettings,
+
+    #[serde(default)]
+    pub mods: ModSettings,
+
+    #[serde(default)]
+    pub backups: BackupSettings,
+
+    /// `pack.toml` path or URL the instance is synced from on launch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub packwiz: Option<String>,
+}
+
+impl Instance {
+    fn config_file(path: &Path) -> PathBuf {
+        path.join("instance.toml")
+    }
+
+    pub fn load(id: &str) -> Result<Instance> {
+        let path = get_instances_path().join(id);
+        let file = Self::config_file(&path);
+        let raw = fs::read_to_string(&file)
+            .wrap_err_with(|| format!("instance '{}' not found at {}", id, file.display()))?;
+
+        let mut instance: Instance = toml::from_str(&raw)
+            .wrap_err_with(|| format!("invalid instance file {}", file.display()))?;
+        instance.id = id.to_string();
+        instance.path = path;
+        Ok(instance)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        fs::create_dir_all(&self.path)?;
+        let raw = toml::to_string_pretty(self)?;
+        fs::write(Self::config_file(&self.path), raw)?;
+        Ok(())
+    }
+
+    pub fn mods_dir(&self) -> PathBuf {
+        self.path.join("mods")
+    }
+}
+
+/// Lists every instance in the data directory, skipping broken ones
+pub fn list() -> Vec<Instance> {
+    let Ok(entries) = fs::read_dir(get_instances_path()) else {
+        return Vec::new();
+    };
+
+    let mut instances: Vec<Instance> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let id = entry.file_name().to_string_lossy().to_string();
+            match Instance::load(&id) {
+                Ok(instance) => Some(instance),
+                Err(err) => {
+                    debug!("Skipping instance '{}': {}", id, err);
+                    None
+                }
+            }
+        })
+        .collect();
+
+    instances.sort_by_key(|instance| instance.name.to_lowercase());
+    instances
+}
+
+pub fn find(id: &str) -> Result<Instance> {
+    Instance::load(id).or_else(|err| {
+        list()
+            .into_iter()
+            .find(|instance| instance.name.eq_ignore_ascii_case(id))
+            .ok_or_else(|| eyre!("{}", err))
+    })
+}
@@ -0,0 +1,447 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{Result, WrapErr};
+
+use super::Instance;
+
+/// The game's `options.txt`. Entries keep their order and every key the
+/// launcher does not know about is written back untouched.
+#[derive(Debug, Clone)]
+pub struct Options {
+    path: PathBuf,
+    entries: Vec<Entry>,
+}
+
+/// A line of `options.txt`
+#[derive(Debug, Clone, PartialEq)]
+enum Entry {
+    KeyValue(String, String),
+    /// A line without `:`, such as a blank line, written back as it was
+    Raw(String),
+}
+
+impl Options {
+    /// Loads `options.txt`, which does not exist before the first launch
+    pub fn load(instance: &Instance) -> Result<Options> {
+        Options::from_path(&instance.path.join("options.txt"))
+    }
+
+    /// Loads an `options.txt`-style file, empty if it does not exist
+    pub fn from_path(path: &Path) -> Result<Options> {
+        let path = path.to_path_buf();
+        let raw = match fs::read_to_string(&path) {
+            Ok(raw) => raw,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err).wrap_err_with(|| format!("failed to read {}", path.display())),
+        };
+
+        let entries = raw
+            .lines()
+            .map(|line| match line.split_once(':') {
+                Some((key, value)) => Entry::KeyValue(key.to_string(), value.to_string()),
+                None => Entry::Raw(line.to_string()),
+            })
+            .collect();
+        Ok(Options { path, entries })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries().find(|(name, _)| *name == key).map(|(_, value)| value)
+    }
+
+    /// Keys and values in file order, lines that aren't `key:value` left out
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().filter_map(|entry| match entry {
+            Entry::KeyValue(key, value) => Some((key.as_str(), value.as_str())),
+            Entry::Raw(_) => None,
+        })
+    }
+
+    pub fn exists(&self) -> bool {
+        self.path.exists()
+    }
+
+    pub fn set(&mut self, key: &str, value: String) {
+        let existing = self.entries.iter_mut().find_map(|entry| match entry {
+            Entry::KeyValue(name, current) if name == key => Some(current),
+            _ => None,
+        });
+        match existing {
+            Some(current) => *current = value,
+            None => self.entries.push(Entry::KeyValue(key.to_string(), value)),
+        }
+    }
+
+    /// JSON string lists such as `resourcePacks:["vanilla","file/x.zip"]`
+    pub fn get_list(&self, key: &str) -> Vec<String> {
+        self.get(key)
+            .and_then(|value| serde_json::from_str(value).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn set_list(&mut self, key: &str, values: &[String]) {
+        self.set(key, serde_json::to_string(values).unwrap_or_else(|_| "[]".to_string()));
+    }
+
+    /// Reads a typed value, `None` if it is missing or malformed
+    pub fn value(&self, key: &str, kind: Kind) -> Option<Value> {
+        kind.parse(self.get(key)?)
+    }
+
+    pub fn set_value(&mut self, key: &str, value: &Value) {
+        self.set(key, value.to_raw());
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let mut raw = String::new();
+        for entry in &self.entries {
+            match entry {
+                Entry::KeyValue(key, value) => {
+                    raw.push_str(key);
+                    raw.push(':');
+                    raw.push_str(value);
+                }
+                Entry::Raw(line) => raw.push_str(line),
+            }
+            raw.push('\n');
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, raw).wrap_err_with(|| format!("failed to write {}", self.path.display()))
+    }
+}
+
+/// How a setting is stored in `options.txt`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Bool,
+    Int { min: i64, max: i64 },
+    /// Integer up to `max` where 0 picks automatically, like `guiScale`
+    Scale { max: i64 },
+    /// Stored as a float, shown as `offset + value * scale` with a unit
+    Float {
+        min: f64,
+        max: f64,
+        step: f64,
+        scale: f64,
+        offset: f64,
+        unit: &'static str,
+    },
+    /// Numbered options like `graphicsMode`, labels by index
+    Choice(&'static [&'static str]),
+    /// Free text such as `lang`, JSON quotes are kept as they are
+    Text,
+    /// Key binding like `key.keyboard.w` or `key.mouse.left`
+    Key,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+    Key(String),
+}
+
+impl Kind {
+    pub fn parse(&self, raw: &str) -> Option<Value> {
+        let raw = raw.trim();
+        match self {
+            Kind::Bool => raw.parse().ok().map(Value::Bool),
+            Kind::Int { .. } | Kind::Scale { .. } | Kind::Choice(_) => raw.parse().ok().map(Value::Int),
+            Kind::Float { .. } => raw.parse().ok().map(Value::Float),
+            Kind::Text => Some(Value::Text(raw.to_string())),
+            Kind::Key => Some(Value::Key(raw.to_string())),
+        }
+    }
+
+    /// Parses user input in the units the value is displayed in
+    pub fn parse_input(&self, input: &str) -> Option<Value> {
+        let input = input.trim();
+        match self {
+            Kind::Bool => match input.to_lowercase().as_str() {
+                "true" | "on" | "yes" => Some(Value::Bool(true)),
+                "false" | "off" | "no" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            Kind::Int { min, max } => input
+                .parse()
+                .ok()
+                .filter(|value| (min..=max).contains(&value))
+                .map(Value::Int),
+            Kind::Scale { max } if input.eq_ignore_ascii_case("auto") => Some(Value::Int(0)).filter(|_| *max >= 0),
+            Kind::Scale { max } => input
+                .parse()
+                .ok()
+                .filter(|value| (0..=*max).contains(value))
+                .map(Value::Int),
+            Kind::Float {
+                min,
+                max,
+                scale,
+                offset,
+                unit,
+                ..
+            } => {
+                let shown: f64 = input.trim_end_matches(unit).trim().parse().ok()?;
+                let value = (shown - offset) / scale;
+                (*min..=*max).contains(&value).then_some(Value::Float(value))
+            }
+            Kind::Choice(labels) => labels
+                .iter()
+                .position(|label| label.eq_ignore_ascii_case(input))
+                .map(|index| Value::Int(index as i64))
+                .or_else(|| {
+                    let index: usize = input.parse().ok()?;
+                    (index < labels.len()).then_some(Value::Int(index as i64))
+                }),
+            Kind::Text => Some(Value::Text(input.to_string())),
+            Kind::Key => input
+                .starts_with("key.")
+                .then(|| Value::Key(input.to_string())),
+        }
+    }
+
+    pub fn display(&self, value: &Value) -> String {
+        match (self, value) {
+            (Kind::Bool, Value::Bool(on)) => if *on { "on" } else { "off" }.to_string(),
+            (Kind::Choice(labels), Value::Int(index)) => labels
+                .get(*index as usize)
+                .map_or_else(|| index.to_string(), |label| label.to_string()),
+            (Kind::Scale { .. }, Value::Int(0)) => "auto".to_string(),
+            (
+                Kind::Float {
+                    scale, offset, unit, ..
+                },
+                Value::Float(value),
+            ) => format!("{}{}", (offset + value * scale).round(), unit),
+            (_, Value::Key(key)) => key
+                .trim_start_matches("key.keyboard.")
+                .trim_start_matches("key.")
+                .to_string(),
+            (_, value) => value.to_raw(),
+        }
+    }
+
+    /// Steps a value for left/right in the editor, wrapping choices
+    pub fn step(&self, value: &Value, forward: bool) -> Option<Value> {
+        let sign = if forward { 1 } else { -1 };
+        match (self, value) {
+            (Kind::Bool, Value::Bool(on)) => Some(Value::Bool(!on)),
+            (Kind::Int { min, max }, Value::Int(n)) => Some(Value::Int((n + sign).clamp(*min, *max))),
+            (Kind::Scale { max }, Value::Int(n)) => Some(Value::Int((n + sign).clamp(0, *max))),
+            (Kind::Choice(labels), Value::Int(n)) => {
+                let len = labels.len() as i64;
+                Some(Value::Int((n + sign).rem_euclid(len)))
+            }
+            (Kind::Float { min, max, step, .. }, Value::Float(value)) => {
+                let next = value + step * sign as f64;
+                // round away float noise so the file stays readable
+                Some(Value::Float(((next * 1000.0).round() / 1000.0).clamp(*min, *max)))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Value {
+    pub fn to_raw(&self) -> String {
+        match self {
+            Value::Bool(on) => on.to_string(),
+            Value::Int(n) => n.to_string(),
+            Value::Float(value) => {
+                let raw = value.to_string();
+                if raw.contains('.') {
+                    raw
+                } else {
+                    format!("{}.0", raw)
+                }
+            }
+            Value::Text(text) | Value::Key(text) => text.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Setting {
+    pub key: &'static str,
+    pub label: &'static str,
+    pub kind: Kind,
+}
+
+const PERCENT: Kind = Kind::Float {
+    min: 0.0,
+    max: 1.0,
+    step: 0.05,
+    scale: 100.0,
+    offset: 0.0,
+    unit: "%",
+};
+
+/// Settings shown in the editor. Anything else is still kept in the file.
+pub const COMMON: &[Setting] = &[
+    Setting {
+        key: "fov",
+        label: "FOV",
+        // stored as -1..1 around the default of 70 degrees
+        kind: Kind::Float {
+            min: -1.0,
+            max: 1.0,
+            step: 0.025,
+            scale: 40.0,
+            offset: 70.0,
+            unit: "°",
+        },
+    },
+    Setting {
+        key: "guiScale",
+        label: "GUI scale",
+        kind: Kind::Scale { max: 6 },
+    },
+    Setting {
+        key: "renderDistance",
+        label: "Render distance",
+        kind: Kind::Int { min: 2, max: 32 },
+    },
+    Setting {
+        key: "simulationDistance",
+        label: "Simulation distance",
+        kind: Kind::Int { min: 5, max: 32 },
+    },
+    Setting {
+        key: "maxFps",
+        label: "Max framerate",
+        kind: Kind::Int { min: 10, max: 260 },
+    },
+    Setting {
+        key: "enableVsync",
+        label: "VSync",
+        kind: Kind::Bool,
+    },
+    Setting {
+        key: "fullscreen",
+        label: "Fullscreen",
+        kind: Kind::Bool,
+    },
+    Setting {
+        key: "graphicsMode",
+        label: "Graphics",
+        kind: Kind::Choice(&["Fast", "Fancy", "Fabulous"]),
+    },
+    Setting {
+        key: "gamma",
+        label: "Brightness",
+        kind: PERCENT,
+    },
+    Setting {
+        key: "mouseSensitivity",
+        label: "Mouse sensitivity",
+        kind: Kind::Float {
+            min: 0.0,
+            max: 1.0,
+            step: 0.01,
+            scale: 200.0,
+            offset: 0.0,
+            unit: "%",
+        },
+    },
+    Setting {
+        key: "autoJump",
+        label: "Auto-jump",
+        kind: Kind::Bool,
+    },
+    Setting {
+        key: "lang",
+        label: "Language",
+        kind: Kind::Text,
+    },
+    Setting {
+        key: "soundCategory_master",
+        label: "Master volume",
+        kind: PERCENT,
+    },
+    Setting {
+        key: "soundCategory_music",
+        label: "Music",
+        kind: PERCENT,
+    },
+    Setting {
+        key: "soundCategory_record",
+        label: "Jukebox/Note blocks",
+        kind: PERCENT,
+    },
+    Setting {
+        key: "soundCategory_weather",
+        label: "Weather",
+        kind: PERCENT,
+    },
+    Setting {
+        key: "soundCategory_block",
+        label: "Blocks",
+        kind: PERCENT,
+    },
+    Setting {
+        key: "soundCategory_hostile",
+        label: "Hostile creatures",
+        kind: PERCENT,
+    },
+    Setting {
+        key: "soundCategory_neutral",
+        label: "Friendly creatures",
+        kind: PERCENT,
+    },
+    Setting {
+        key: "soundCategory_player",
+        label: "Players",
+        kind: PERCENT,
+    },
+    Setting {
+        key: "soundCategory_ambient",
+        label: "Ambient/Environment",
+        kind: PERCENT,
+    },
+    Setting {
+        key: "soundCategory_voice",
+        label: "Voice/Speech",
+        kind: PERCENT,
+    },
+];
+
+/// How a key is stored, `None` for keys the editor does not know
+pub fn kind(key: &str) -> Option<Kind> {
+    match COMMON.iter().find(|setting| setting.key == key) {
+        Some(setting) => Some(setting.kind),
+        None => key.starts_with("key_").then_some(Kind::Key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unparsed_lines_are_written_back() {
+        let dir = std::env::temp_dir().join(format!("mcl-options-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("options.txt");
+        let raw = "version:3465\n# added by a mod\n\nfov:0.0\nkey_key.jump:key.keyboard.space\n";
+        fs::write(&path, raw).unwrap();
+
+        let mut options = Options::from_path(&path).unwrap();
+        assert_eq!(options.get("fov"), Some("0.0"));
+        assert_eq!(options.entries().count(), 3);
+        options.set("fov", "0.5".to_string());
+        options.set("gamma", "1.0".to_string());
+        options.save().unwrap();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            saved,
+            "version:3465\n# added by a mod\n\nfov:0.5\nkey_key.jump:key.keyboard.space\ngamma:1.0\n"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
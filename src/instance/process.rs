@@ -0,0 +1,270 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+#[cfg(not(target_os = "linux"))]
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local, TimeZone};
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+use super::Instance;
+
+fn pid_file(game_dir: &Path) -> PathBuf {
+    game_dir.join(".mcl").join("game.pid")
+}
+
+fn session_file(game_dir: &Path) -> PathBuf {
+    game_dir.join(".mcl").join("session.toml")
+}
+
+/// The latest run of an instance. The pid file says whether it is still
+/// going, this keeps when it started and how it ended.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub pid: u32,
+    /// Unix timestamps in seconds
+    pub started: i64,
+    pub ended: Option<i64>,
+    pub exit_code: Option<i32>,
+    /// Unix signal that terminated the game, if it didn't exit by itself
+    pub signal: Option<i32>,
+}
+
+impl Session {
+    pub fn started(&self) -> Option<DateTime<Local>> {
+        Local.timestamp_opt(self.started, 0).single()
+    }
+
+    pub fn ended(&self) -> Option<DateTime<Local>> {
+        self.ended.and_then(|ended| Local.timestamp_opt(ended, 0).single())
+    }
+
+    /// How the run ended, e.g. `exit 0` or `killed (signal 9)`
+    pub fn outcome(&self) -> String {
+        match (self.exit_code, self.signal) {
+            (Some(code), _) => format!("exit {}", code),
+            (None, Some(signal)) => format!("killed (signal {})", signal),
+            (None, None) => "unknown exit".to_string(),
+        }
+    }
+
+    /// Whether the run ended badly on its own rather than being stopped
+    pub fn crashed(&self) -> bool {
+        match (self.exit_code, self.signal) {
+            // 130 and 143 are how the JVM reports SIGINT and SIGTERM
+            (Some(code), _) => ![0, 130, 143].contains(&code),
+            (None, Some(signal)) => ![2, 9, 15].contains(&signal),
+            (None, None) => false,
+        }
+    }
+}
+
+/// Latest recorded run of the game in this directory
+pub fn session_at(game_dir: &Path) -> Option<Session> {
+    toml::from_str(&fs::read_to_string(session_file(game_dir)).ok()?).ok()
+}
+
+fn write_session(game_dir: &Path, session: &Session) -> Result<()> {
+    fs::write(session_file(game_dir), toml::to_string(session)?)?;
+    Ok(())
+}
+
+/// Remembers the game process of an instance directory and when it started
+pub fn write_pid(game_dir: &Path, pid: u32) -> Result<()> {
+    let path = pid_file(game_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, pid.to_string())?;
+    write_session(
+        game_dir,
+        &Session {
+            pid,
+            started: Local::now().timestamp(),
+            ended: None,
+            exit_code: None,
+            signal: None,
+        },
+    )
+}
+
+pub fn clear_pid(game_dir: &Path) {
+    let _ = fs::remove_file(pid_file(game_dir));
+}
+
+/// Clears the pid file and records how the game ended
+pub fn record_exit(game_dir: &Path, status: Option<ExitStatus>) {
+    clear_pid(game_dir);
+    let Some(mut session) = session_at(game_dir) else {
+        return;
+    };
+    session.ended = Some(Local::now().timestamp());
+    session.exit_code = status.and_then(|status| status.code());
+    session.signal = status.and_then(signal);
+    let _ = write_session(game_dir, &session);
+}
+
+#[cfg(unix)]
+fn signal(status: ExitStatus) -> Option<i32> {
+    std::os::unix::process::ExitStatusExt::signal(&status)
+}
+
+#[cfg(windows)]
+fn signal(_status: ExitStatus) -> Option<i32> {
+    None
+}
+
+/// Whether a process with this id exists
+#[cfg(unix)]
+pub fn alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // SAFETY: signal 0 only checks that the process exists
+    let found = unsafe { libc::kill(pid, 0) } == 0;
+    found || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Whether a process with this id exists
+#[cfg(windows)]
+pub fn alive(pid: u32) -> bool {
+    Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+        .stderr(Stdio::null())
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+        .unwrap_or(true)
+}
+
+/// Whether `pid` is a game running in `game_dir`, rather than whatever got
+/// the pid after the game exited. The game runs in its directory and gets
+/// it as `--gameDir`.
+#[cfg(target_os = "linux")]
+fn is_game(pid: u32, game_dir: &Path) -> bool {
+    let proc_dir = PathBuf::from(format!("/proc/{}", pid));
+    let in_dir = match (fs::read_link(proc_dir.join("cwd")), game_dir.canonicalize()) {
+        (Ok(cwd), Ok(game_dir)) => cwd == game_dir,
+        _ => false,
+    };
+    in_dir
+        || fs::read(proc_dir.join("cmdline")).is_ok_and(|cmdline| {
+            String::from_utf8_lossy(&cmdline).contains(game_dir.to_string_lossy().as_ref())
+        })
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn is_game(pid: u32, game_dir: &Path) -> bool {
+    Command::new("ps")
+        .args(["-p", &pid.to_string(), "-o", "command="])
+        .stderr(Stdio::null())
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains(game_dir.to_string_lossy().as_ref()))
+}
+
+#[cfg(windows)]
+fn is_game(_pid: u32, _game_dir: &Path) -> bool {
+    true
+}
+
+/// Game process of the instance, if it is running. A pid file left behind
+/// by a crashed launcher is removed once the process is gone or its pid
+/// belongs to something else.
+pub fn running(instance: &Instance) -> Option<u32> {
+    running_at(&instance.path)
+}
+
+pub fn running_at(game_dir: &Path) -> Option<u32> {
+    let pid = fs::read_to_string(pid_file(game_dir))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    if alive(pid) && is_game(pid, game_dir) {
+        Some(pid)
+    } else {
+        clear_pid(game_dir);
+        None
+    }
+}
+
+/// Whether the instance is running, and its latest session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Running(Session),
+    Stopped(Option<Session>),
+}
+
+pub fn status(instance: &Instance) -> Status {
+    status_at(&instance.path)
+}
+
+pub fn status_at(game_dir: &Path) -> Status {
+    let session = session_at(game_dir);
+    match (running_at(game_dir), session) {
+        (Some(pid), Some(session)) if session.pid == pid => Status::Running(session),
+        // started by something that didn't record a session
+        (Some(pid), _) => Status::Running(Session {
+            pid,
+            started: Local::now().timestamp(),
+            ended: None,
+            exit_code: None,
+            signal: None,
+        }),
+        // a session that never recorded its end belongs to a launcher that
+        // went away with the game
+        (None, session) => Status::Stopped(session),
+    }
+}
+
+/// Sends a stop request, or kills right away with `force`. Get `pid` from
+/// [`running`], which checks it is still the game.
+#[cfg(unix)]
+pub fn send_kill(pid: u32, force: bool) -> Result<()> {
+    let signal = if force { libc::SIGKILL } else { libc::SIGTERM };
+    let target = libc::pid_t::try_from(pid).map_err(|_| eyre!("invalid pid {}", pid))?;
+    // SAFETY: plain kill(2) on a pid we checked belongs to the game
+    if unsafe { libc::kill(target, signal) } != 0 {
+        return Err(eyre!(
+            "failed to signal process {}: {}",
+            pid,
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+pub fn send_kill(pid: u32, force: bool) -> Result<()> {
+    let mut command = Command::new("taskkill");
+    command.args(["/PID", &pid.to_string()]);
+    if force {
+        command.arg("/F");
+    }
+    let status = command.stdout(Stdio::null()).stderr(Stdio::null()).status()?;
+    if !status.success() {
+        return Err(eyre!("failed to stop process {}", pid));
+    }
+    Ok(())
+}
+
+/// Asks the game to stop, or kills it outright with `force`. Returns the pid
+pub fn kill(instance: &Instance, force: bool) -> Result<u32> {
+    let pid = running(instance).ok_or_else(|| eyre!("{} is not running", instance.name))?;
+    send_kill(pid, force)?;
+    Ok(pid)
+}
+
+/// Waits up to `timeout` for a process to exit, returning whether it did
+pub fn wait_exit(pid: u32, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if !alive(pid) {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+    !alive(pid)
+}
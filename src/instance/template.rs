@@ -0,0 +1,251 @@
+use std::fs;
+use std::path::PathBuf;
+
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{config::get_data_path, mods::version};
+
+use super::{
+    options::{self, Kind, Options},
+    Instance,
+};
+
+/// Keys that describe the game version or state of one instance and are
+/// never copied, whatever the selection
+const VERSION_SPECIFIC: &[&str] = &[
+    "version",
+    "resourcePacks",
+    "incompatibleResourcePacks",
+    "lastServer",
+    "tutorialStep",
+    "joinedFirstServer",
+    "onboardAccessibility",
+    "skipMultiplayerWarning",
+    "skipRealms32bitWarning",
+    "hideBundleTutorial",
+    "syncChunkWrites",
+];
+
+/// Named selections accepted wherever key patterns are
+pub const GROUPS: &[(&str, &[&str])] = &[
+    ("keys", &["key_*"]),
+    ("sound", &["soundCategory_*", "soundDevice", "showSubtitles", "directionalAudio"]),
+    (
+        "video",
+        &[
+            "fov",
+            "fovEffectScale",
+            "guiScale",
+            "renderDistance",
+            "simulationDistance",
+            "maxFps",
+            "enableVsync",
+            "fullscreen",
+            "graphicsMode",
+            "gamma",
+            "ao",
+            "renderClouds",
+            "particles",
+            "entityShadows",
+            "biomeBlendRadius",
+            "mipmapLevels",
+            "bobView",
+        ],
+    ),
+    (
+        "controls",
+        &[
+            "mouseSensitivity",
+            "invertYMouse",
+            "rawMouseInput",
+            "mouseWheelSensitivity",
+            "discrete_mouse_scroll",
+            "autoJump",
+            "toggleCrouch",
+            "toggleSprint",
+        ],
+    ),
+    ("all", &["*"]),
+];
+
+/// Turns group names and patterns like `key_*` into patterns
+pub fn expand(selectors: &[String]) -> Vec<String> {
+    selectors
+        .iter()
+        .flat_map(|selector| match GROUPS.iter().find(|(name, _)| name == selector) {
+            Some((_, patterns)) => patterns.iter().map(|p| p.to_string()).collect(),
+            None => vec![selector.clone()],
+        })
+        .collect()
+}
+
+fn matches(pattern: &str, key: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => key.starts_with(prefix),
+        None => pattern == key,
+    }
+}
+
+pub fn presets_dir() -> PathBuf {
+    get_data_path().join("presets")
+}
+
+fn preset_path(name: &str) -> Result<PathBuf> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(eyre!("invalid preset name '{}'", name));
+    }
+    Ok(presets_dir().join(format!("{}.txt", name)))
+}
+
+/// Names of the saved presets, sorted
+pub fn list_presets() -> Vec<String> {
+    let Ok(entries) = fs::read_dir(presets_dir()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.strip_suffix(".txt").map(String::from)
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+pub fn load_preset(name: &str) -> Result<Options> {
+    let path = preset_path(name)?;
+    if !path.exists() {
+        return Err(eyre!("no preset '{}'", name));
+    }
+    Options::from_path(&path)
+}
+
+/// Stores the selected keys of `source` as a preset, returns how many
+pub fn save_preset(name: &str, source: &Options, patterns: &[String]) -> Result<usize> {
+    let mut preset = Options::from_path(&preset_path(name)?)?;
+    let applied = apply(source, &mut preset, patterns, None);
+    preset.save()?;
+    Ok(applied.changed)
+}
+
+pub fn delete_preset(name: &str) -> Result<()> {
+    let path = preset_path(name)?;
+    fs::remove_file(&path).map_err(|_| eyre!("no preset '{}'", name))
+}
+
+/// An instance id or name, or `preset:<name>`
+pub fn load_source(source: &str) -> Result<Options> {
+    match source.strip_prefix("preset:") {
+        Some(name) => load_preset(name),
+        None => Options::load(&super::find(source)?),
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Applied {
+    pub changed: usize,
+    /// Key bindings whose format differs between the two game versions
+    pub skipped: Vec<String>,
+}
+
+/// Pre-1.13 bindings are numeric LWJGL 2 key codes
+fn is_legacy_binding(value: &str) -> bool {
+    value.parse::<i64>().is_ok()
+}
+
+/// Whether a release still stores numeric bindings, `None` for snapshots
+fn legacy_version(game_version: &str) -> Option<bool> {
+    if game_version.starts_with(['a', 'b']) && version::comparable(&game_version[1..]) {
+        // alpha and beta releases like b1.7.3
+        return Some(true);
+    }
+    version::comparable(game_version).then(|| version::compare(game_version, "1.13").is_lt())
+}
+
+/// Copies the keys matching `patterns` from `source` into `target`.
+/// `game_version` is the version `target` belongs to, used to tell the
+/// binding format when the target has no bindings yet.
+pub fn apply(source: &Options, target: &mut Options, patterns: &[String], game_version: Option<&str>) -> Applied {
+    // bindings only carry over between versions using the same format
+    let target_legacy = target
+        .entries()
+        .find(|(key, _)| options::kind(key) == Some(Kind::Key))
+        .map(|(_, value)| is_legacy_binding(value))
+        .or_else(|| game_version.and_then(legacy_version));
+
+    let mut applied = Applied::default();
+    for (key, value) in source.entries() {
+        if VERSION_SPECIFIC.contains(&key)
+            || !patterns.iter().any(|pattern| matches(pattern, key))
+            || target.get(key) == Some(value)
+        {
+            continue;
+        }
+        if options::kind(key) == Some(Kind::Key)
+            && target_legacy.is_some_and(|legacy| legacy != is_legacy_binding(value))
+        {
+            applied.skipped.push(key.to_string());
+            continue;
+        }
+        target.set(key, value.to_string());
+        applied.changed += 1;
+    }
+    applied
+}
+
+/// Applies a template to an instance's `options.txt`
+pub fn apply_to(source: &Options, instance: &Instance, patterns: &[String]) -> Result<Applied> {
+    if let Some(pid) = super::process::running(instance) {
+        return Err(eyre!(
+            "{} is running (pid {}) and would overwrite options.txt on exit",
+            instance.name,
+            pid
+        ));
+    }
+    let mut target = Options::load(instance)?;
+    let applied = apply(source, &mut target, patterns, Some(&instance.game_version));
+    if applied.changed > 0 {
+        target.save()?;
+    }
+    Ok(applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(entries: &[(&str, &str)]) -> Options {
+        let mut options = Options::from_path(&std::env::temp_dir().join("mcl-missing/options.txt")).unwrap();
+        for (key, value) in entries {
+            options.set(key, value.to_string());
+        }
+        options
+    }
+
+    #[test]
+    fn bindings_follow_the_target_version_without_options() {
+        let patterns = vec!["key_*".to_string()];
+        let legacy = options(&[("key_key.jump", "57")]);
+        let modern = options(&[("key_key.jump", "key.keyboard.space")]);
+
+        let mut target = options(&[]);
+        let applied = apply(&legacy, &mut target, &patterns, Some("1.20.1"));
+        assert_eq!((applied.changed, applied.skipped), (0, vec!["key_key.jump".to_string()]));
+
+        let applied = apply(&modern, &mut target, &patterns, Some("1.20.1"));
+        assert_eq!(applied.changed, 1);
+
+        let mut target = options(&[]);
+        let applied = apply(&modern, &mut target, &patterns, Some("1.12.2"));
+        assert_eq!(applied.skipped.len(), 1);
+        let applied = apply(&legacy, &mut target, &patterns, Some("b1.7.3"));
+        assert_eq!(applied.changed, 1);
+
+        // snapshots and presets don't say, so everything is copied
+        let mut target = options(&[]);
+        assert_eq!(apply(&legacy, &mut target, &patterns, Some("23w31a")).changed, 1);
+        let mut target = options(&[]);
+        assert_eq!(apply(&legacy, &mut target, &patterns, None).changed, 1);
+    }
+}
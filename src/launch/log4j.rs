@@ -0,0 +1,257 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{eyre, Result};
+use sha1::{Digest, Sha1};
+
+use crate::{debug, error, net};
+
+use super::{get_assets_path, version::VersionJson};
+
+/// Config Mojang published for 1.7 to 1.11.2, which ship log4j 2.0-beta9
+/// where the lookup flag doesn't exist
+const PATCHED_17_111: PatchedConfig = PatchedConfig {
+    id: "log4j2_17-111.xml",
+    sha1: "4bb89a97a66f350bc9f73b3ca8509632682aea2e",
+};
+
+/// Config Mojang published for 1.12 to 1.16.5, which ship log4j 2.8.1
+const PATCHED_112_116: PatchedConfig = PatchedConfig {
+    id: "log4j2_112-116.xml",
+    sha1: "02937d122c86ce73319ef9975b58896fc1b491d1",
+};
+
+/// How version JSONs pass the logging config
+const CONFIG_ARGUMENT: &str = "-Dlog4j.configurationFile=${path}";
+
+/// Disables message lookups in log4j 2.10 and later (1.17 to 1.18.0)
+pub const NO_LOOKUPS: &str = "-Dlog4j2.formatMsgNoLookups=true";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatchedConfig {
+    pub id: &'static str,
+    pub sha1: &'static str,
+}
+
+impl PatchedConfig {
+    fn url(&self) -> String {
+        format!("https://launcher.mojang.com/v1/objects/{}/{}", self.sha1, self.id)
+    }
+}
+
+/// What protects a version against Log4Shell (CVE-2021-44228)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mitigation {
+    /// Before 1.7 there is no log4j, from 1.18.1 on it is fixed
+    NotAffected,
+    /// The logging config of the version JSON is replaced by a patched one
+    PatchedConfig(PatchedConfig),
+    /// [`NO_LOOKUPS`] is added to the JVM arguments
+    NoLookups,
+}
+
+impl fmt::Display for Mitigation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mitigation::NotAffected => f.write_str("not affected"),
+            Mitigation::PatchedConfig(config) => write!(f, "patched logging config {}", config.id),
+            Mitigation::NoLookups => write!(f, "JVM flag {}", NO_LOOKUPS),
+        }
+    }
+}
+
+/// Logging config and JVM arguments for a launch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Logging {
+    /// Config passed to log4j, if the version has one
+    pub config: Option<PathBuf>,
+    /// Whether [`Logging::config`] still has to be downloaded, only on dry runs
+    pub missing: bool,
+    pub args: Vec<String>,
+    pub mitigation: Mitigation,
+}
+
+/// Where the launcher keeps logging configs, as the official one does
+pub fn configs_dir() -> PathBuf {
+    get_assets_path().join("log_configs")
+}
+
+/// `1.16.5` as `(1, 16, 5)`, pre-releases count as their release
+fn release(id: &str) -> Option<(u32, u32, u32)> {
+    let id = id.split(['-', ' ']).next()?;
+    let mut parts = id.split('.').map(|part| part.parse::<u32>().ok());
+    let major = parts.next()??;
+    let minor = parts.next()??;
+    let patch = parts.next().unwrap_or(Some(0))?;
+    Some((major, minor, patch))
+}
+
+/// Weekly snapshot like `21w37a` as `(21, 37)`
+fn snapshot(id: &str) -> Option<(u32, u32)> {
+    let (year, rest) = id.split_once('w')?;
+    let week = rest.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    Some((year.parse().ok()?, week.parse().ok()?))
+}
+
+/// Mitigation for a game version, by its release number or, for snapshots,
+/// the release it led up to
+pub fn mitigation(game_version: &str) -> Mitigation {
+    if let Some(version) = release(game_version) {
+        return match version {
+            (1, 7..=11, _) => Mitigation::PatchedConfig(PATCHED_17_111),
+            (1, 12..=16, _) => Mitigation::PatchedConfig(PATCHED_112_116),
+            (1, 17, _) | (1, 18, 0) => Mitigation::NoLookups,
+            _ => Mitigation::NotAffected,
+        };
+    }
+    match snapshot(game_version) {
+        // 13w36a started 1.7, 17w06a 1.12, 20w45a 1.17 and 21w44a was the
+        // last one before 1.18
+        Some(week) if ((13, 36)..(17, 6)).contains(&week) => Mitigation::PatchedConfig(PATCHED_17_111),
+        Some(week) if ((17, 6)..(20, 45)).contains(&week) => Mitigation::PatchedConfig(PATCHED_112_116),
+        Some(week) if ((20, 45)..=(21, 44)).contains(&week) => Mitigation::NoLookups,
+        _ => Mitigation::NotAffected,
+    }
+}
+
+fn sha1_matches(path: &Path, sha1: &str) -> bool {
+    fs::read(path).is_ok_and(|bytes| format!("{:x}", Sha1::digest(&bytes)) == sha1)
+}
+
+/// Downloads a config unless a copy with the right hash is already there
+fn fetch(url: &str, sha1: Option<&str>, path: &Path) -> Result<()> {
+    let valid = match sha1 {
+        Some(sha1) => sha1_matches(path, sha1),
+        None => path.exists(),
+    };
+    if valid {
+        return Ok(());
+    }
+    let body = net::get_bytes(url)?;
+    if let Some(sha1) = sha1 {
+        let hash = format!("{:x}", Sha1::digest(&body));
+        if hash != sha1 {
+            return Err(eyre!("{} has sha1 {}, expected {}", url, hash, sha1));
+        }
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, body)?;
+    debug!("Saved logging config {}", path.display());
+    Ok(())
+}
+
+/// Resolves the logging config of a version from `logging.client` and
+/// applies the Log4Shell mitigation it needs. Configs are downloaded unless
+/// `download` is false.
+pub fn setup(version: &VersionJson, download: bool) -> Result<Logging> {
+    // the jar of an inherited version is the game version it builds on
+    let game_version = version.jar.as_deref().unwrap_or(&version.id);
+    let mitigation = mitigation(game_version);
+    let client = version.logging.as_ref().and_then(|logging| logging.client.as_ref());
+    let argument = client.map_or(CONFIG_ARGUMENT, |client| client.argument.as_str());
+
+    // without a config of its own the game uses the one in its jar, which
+    // has lookups enabled, so the patched one is passed either way
+    let source = match (mitigation, client) {
+        (Mitigation::PatchedConfig(patched), _) => {
+            Some((patched.id.to_string(), patched.url(), Some(patched.sha1.to_string())))
+        }
+        (_, Some(client)) => Some((client.file.id.clone(), client.file.url.clone(), client.file.sha1.clone())),
+        (_, None) => None,
+    };
+
+    let mut logging = Logging {
+        config: None,
+        missing: false,
+        args: Vec::new(),
+        mitigation,
+    };
+    if let Some((id, url, sha1)) = source {
+        let path = configs_dir().join(&id);
+        if download {
+            match fetch(&url, sha1.as_deref(), &path) {
+                Ok(()) => {}
+                Err(err) if matches!(mitigation, Mitigation::PatchedConfig(_)) => {
+                    return Err(err.wrap_err(format!(
+                        "failed to get {}, which {} needs to be safe from Log4Shell",
+                        id, game_version
+                    )));
+                }
+                // the config in the jar is safe here, or made safe by the flag
+                Err(err) if !path.exists() => {
+                    error!("Failed to get logging config {}, using the built-in one: {}", id, err);
+                    if mitigation == Mitigation::NoLookups {
+                        logging.args.push(NO_LOOKUPS.to_string());
+                    }
+                    return Ok(logging);
+                }
+                Err(err) => {
+                    debug!("Failed to update logging config {}: {}", id, err);
+                }
+            }
+        } else {
+            logging.missing = !path.exists();
+        }
+        logging.args.push(argument.replace("${path}", &path.to_string_lossy()));
+        logging.config = Some(path);
+    }
+    if mitigation == Mitigation::NoLookups {
+        logging.args.push(NO_LOOKUPS.to_string());
+    }
+    Ok(logging)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_version_ids() {
+        assert_eq!(release("1.16.5"), Some((1, 16, 5)));
+        assert_eq!(release("1.17"), Some((1, 17, 0)));
+        assert_eq!(release("1.18-pre1"), Some((1, 18, 0)));
+        assert_eq!(release("1.14.4 Pre-Release 2"), Some((1, 14, 4)));
+        assert_eq!(release("21w37a"), None);
+        assert_eq!(snapshot("21w37a"), Some((21, 37)));
+        assert_eq!(snapshot("1.16.5"), None);
+    }
+
+    #[test]
+    fn release_ranges() {
+        for (version, expected) in [
+            ("1.6.4", Mitigation::NotAffected),
+            ("1.7", Mitigation::PatchedConfig(PATCHED_17_111)),
+            ("1.11.2", Mitigation::PatchedConfig(PATCHED_17_111)),
+            ("1.12", Mitigation::PatchedConfig(PATCHED_112_116)),
+            ("1.16.5", Mitigation::PatchedConfig(PATCHED_112_116)),
+            ("1.17", Mitigation::NoLookups),
+            ("1.17.1", Mitigation::NoLookups),
+            ("1.18", Mitigation::NoLookups),
+            ("1.18-rc3", Mitigation::NoLookups),
+            ("1.18.1", Mitigation::NotAffected),
+            ("1.20.1", Mitigation::NotAffected),
+        ] {
+            assert_eq!(mitigation(version), expected, "{}", version);
+        }
+    }
+
+    #[test]
+    fn snapshot_ranges() {
+        for (version, expected) in [
+            ("13w26a", Mitigation::NotAffected),
+            ("13w36a", Mitigation::PatchedConfig(PATCHED_17_111)),
+            ("17w05a", Mitigation::PatchedConfig(PATCHED_17_111)),
+            ("17w06a", Mitigation::PatchedConfig(PATCHED_112_116)),
+            ("20w22a", Mitigation::PatchedConfig(PATCHED_112_116)),
+            ("20w45a", Mitigation::NoLookups),
+            ("21w44a", Mitigation::NoLookups),
+            ("22w03a", Mitigation::NotAffected),
+            ("rd-132211", Mitigation::NotAffected),
+        ] {
+            assert_eq!(mitigation(version), expected, "{}", version);
+        }
+    }
+}
@@ -0,0 +1,414 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use sha1::{Digest, Sha1};
+use zip::ZipArchive;
+
+use crate::{
+    config::get_data_path,
+    debug, error,
+    instance::{process, Instance},
+    logs,
+    mods::{self, check::Severity},
+    packwiz, servers, warn, worlds,
+};
+
+use version::{Features, VersionJson};
+
+pub mod log4j;
+pub mod version;
+
+pub fn get_versions_path() -> PathBuf {
+    get_data_path().join("versions")
+}
+
+pub fn get_libraries_path() -> PathBuf {
+    get_data_path().join("libraries")
+}
+
+pub fn get_assets_path() -> PathBuf {
+    get_data_path().join("assets")
+}
+
+/// Where the game goes right after starting, skipping the title screen
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuickPlay {
+    /// World folder in `saves/`
+    World(String),
+    /// `host[:port]`
+    Server(String),
+    /// Realm id
+    Realm(String),
+}
+
+impl QuickPlay {
+    /// Feature the 1.20+ version JSON guards the argument with
+    fn feature(&self) -> &'static str {
+        match self {
+            QuickPlay::World(_) => "is_quick_play_singleplayer",
+            QuickPlay::Server(_) => "is_quick_play_multiplayer",
+            QuickPlay::Realm(_) => "is_quick_play_realms",
+        }
+    }
+
+    fn variable(&self) -> (&'static str, String) {
+        match self {
+            QuickPlay::World(folder) => ("quick_play_singleplayer", folder.clone()),
+            QuickPlay::Server(address) => ("quick_play_multiplayer", address.clone()),
+            QuickPlay::Realm(id) => ("quick_play_realms", id.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LaunchOptions {
+    pub username: String,
+    pub offline: bool,
+    pub memory: Option<String>,
+    pub resolution: Option<(u32, u32)>,
+    pub jvm_args: Vec<String>,
+    pub features: Features,
+    pub quick_play: Option<QuickPlay>,
+    /// Only resolve the command line, without downloading anything
+    pub dry_run: bool,
+}
+
+impl Default for LaunchOptions {
+    fn default() -> Self {
+        LaunchOptions {
+            username: "Player".to_string(),
+            offline: true,
+            memory: None,
+            resolution: None,
+            jvm_args: Vec::new(),
+            features: Features::new(),
+            quick_play: None,
+            dry_run: false,
+        }
+    }
+}
+
+/// Fully resolved command line, kept separate from spawning for dry runs
+#[derive(Debug, Clone)]
+pub struct Prepared {
+    pub java: String,
+    pub args: Vec<String>,
+    pub game_dir: PathBuf,
+    pub version: VersionJson,
+    pub logging: log4j::Logging,
+}
+
+impl Prepared {
+    pub fn command(&self) -> Command {
+        let mut command = Command::new(&self.java);
+        command.args(&self.args).current_dir(&self.game_dir);
+        command
+    }
+
+    /// Spawns the game with its output piped, see [`logs::capture`]
+    pub fn spawn(&self) -> Result<Child> {
+        debug!("Running {} {}", self.java, self.args.join(" "));
+        let child = self
+            .command()
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .wrap_err_with(|| format!("failed to start {}", self.java))?;
+        process::write_pid(&self.game_dir, child.id())?;
+        Ok(child)
+    }
+
+    /// Spawns the game in the background and returns its pid, for callers
+    /// like the TUI that can't hand over the terminal
+    pub fn start(&self) -> Result<u32> {
+        let mut child = self.spawn()?;
+        let pid = child.id();
+        let output = logs::capture::capture(&mut child, &self.game_dir, false)?;
+
+        let game_dir = self.game_dir.clone();
+        thread::spawn(move || {
+            let status = child.wait().ok();
+            for handle in output {
+                let _ = handle.join();
+            }
+            process::record_exit(&game_dir, status);
+        });
+        Ok(pid)
+    }
+
+    /// Spawns the game, printing its output, and waits for it to exit
+    pub fn run(&self) -> Result<ExitStatus> {
+        let mut child = self.spawn()?;
+        let output = logs::capture::capture(&mut child, &self.game_dir, true)?;
+        let status = child.wait();
+        for handle in output {
+            let _ = handle.join();
+        }
+        process::record_exit(&self.game_dir, status.as_ref().ok().copied());
+        Ok(status?)
+    }
+}
+
+/// Stable offline UUID derived from the player name
+fn offline_uuid(username: &str) -> String {
+    let hash = Sha1::digest(format!("OfflinePlayer:{}", username).as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hash[..16]);
+    bytes[6] = (bytes[6] & 0x0f) | 0x30;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn java_path(instance: &Instance) -> String {
+    if let Some(java) = &instance.launch.java {
+        return java.clone();
+    }
+    match std::env::var_os("JAVA_HOME") {
+        Some(home) => Path::new(&home).join("bin").join("java").to_string_lossy().to_string(),
+        None => "java".to_string(),
+    }
+}
+
+/// Unpacks legacy natives jars so LWJGL 2 can find them
+fn extract_natives(version: &VersionJson, libraries: &Path, target: &Path) -> Result<()> {
+    for library in &version.libraries {
+        if !version::rules_allow(&library.rules, &Features::new()) {
+            continue;
+        }
+        let Some(path) = library.natives_path() else {
+            continue;
+        };
+
+        let jar = libraries.join(&path);
+        let mut archive = ZipArchive::new(File::open(&jar).wrap_err_with(|| {
+            format!("missing natives {}", jar.display())
+        })?)?;
+        fs::create_dir_all(target)?;
+
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i)?;
+            let Some(name) = entry.enclosed_name() else {
+                continue;
+            };
+            if entry.is_dir() || name.starts_with("META-INF") {
+                continue;
+            }
+            let mut out = File::create(target.join(name))?;
+            io::copy(&mut entry, &mut out)?;
+        }
+    }
+    Ok(())
+}
+
+fn substitute(arg: &str, vars: &HashMap<&str, String>) -> String {
+    let mut result = arg.to_string();
+    for (key, value) in vars {
+        result = result.replace(&format!("${{{}}}", key), value);
+    }
+    result
+}
+
+/// Builds the java command line for an instance from its version JSON
+pub fn prepare(instance: &Instance, options: &LaunchOptions) -> Result<Prepared> {
+    if !options.offline {
+        return Err(eyre!("no account is signed in, launch with --offline"));
+    }
+
+    let versions = get_versions_path();
+    let libraries = get_libraries_path();
+    let version_id = instance
+        .launch
+        .version
+        .clone()
+        .unwrap_or_else(|| instance.game_version.clone());
+    let version = VersionJson::load(&versions, &version_id)?;
+    let main_class = version
+        .main_class
+        .clone()
+        .ok_or_else(|| eyre!("version '{}' has no main class", version_id))?;
+
+    let natives = instance.path.join(".mcl").join("natives");
+    if !options.dry_run {
+        extract_natives(&version, &libraries, &natives)?;
+    }
+    let logging = log4j::setup(&version, !options.dry_run)?;
+
+    let mut features = options.features.clone();
+    if options.resolution.is_some() {
+        features.insert("has_custom_resolution".to_string(), true);
+    }
+
+    // Quick Play arrived in 1.20, older versions can only join servers
+    let quick_play = options.quick_play.as_ref().filter(|quick_play| {
+        version.arguments.as_ref().is_some_and(|arguments| {
+            arguments.game.iter().any(|arg| arg.uses_feature(quick_play.feature()))
+        })
+    });
+    let legacy_server = match (&options.quick_play, quick_play) {
+        (Some(QuickPlay::Server(address)), None) => Some(servers::split_address(address)),
+        (Some(QuickPlay::World(_)), None) => {
+            return Err(eyre!("{} has no Quick Play support (1.20+), it can't open a world directly", version.id))
+        }
+        (Some(QuickPlay::Realm(_)), None) => {
+            return Err(eyre!("{} has no Quick Play support (1.20+), it can't join a realm directly", version.id))
+        }
+        _ => None,
+    };
+    if let Some(quick_play) = quick_play {
+        features.insert(quick_play.feature().to_string(), true);
+    }
+
+    let mut classpath: Vec<String> = Vec::new();
+    for library in &version.libraries {
+        if !version::rules_allow(&library.rules, &features) {
+            continue;
+        }
+        // natives-only entries of legacy versions have no main artifact
+        if !library.natives.is_empty() && library.downloads.artifact.is_none() {
+            continue;
+        }
+        if let Some(path) = library.artifact_path() {
+            let path = libraries.join(path).to_string_lossy().to_string();
+            if !classpath.contains(&path) {
+                classpath.push(path);
+            }
+        }
+    }
+    classpath.push(version.jar_path(&versions).to_string_lossy().to_string());
+
+    let separator = if cfg!(windows) { ";" } else { ":" };
+    let (width, height) = options.resolution.unwrap_or((854, 480));
+    let assets = get_assets_path();
+    let mut vars: HashMap<&str, String> = HashMap::from([
+        ("auth_player_name", options.username.clone()),
+        ("auth_uuid", offline_uuid(&options.username)),
+        ("auth_access_token", "0".to_string()),
+        ("auth_session", "0".to_string()),
+        ("auth_xuid", "0".to_string()),
+        ("clientid", "0".to_string()),
+        ("user_type", "legacy".to_string()),
+        ("user_properties", "{}".to_string()),
+        ("version_name", version.id.clone()),
+        ("version_type", version.kind.clone().unwrap_or_else(|| "release".to_string())),
+        ("game_directory", instance.path.to_string_lossy().to_string()),
+        ("assets_root", assets.to_string_lossy().to_string()),
+        ("game_assets", assets.join("virtual").join("legacy").to_string_lossy().to_string()),
+        ("assets_index_name", version.asset_index_name()),
+        ("natives_directory", natives.to_string_lossy().to_string()),
+        ("library_directory", libraries.to_string_lossy().to_string()),
+        ("classpath_separator", separator.to_string()),
+        ("classpath", classpath.join(separator)),
+        ("launcher_name", "mcl".to_string()),
+        ("launcher_version", env!("CARGO_PKG_VERSION").to_string()),
+        ("resolution_width", width.to_string()),
+        ("resolution_height", height.to_string()),
+    ]);
+    if let Some(quick_play) = quick_play {
+        let (name, value) = quick_play.variable();
+        vars.insert(name, value);
+    }
+
+    let mut args = Vec::new();
+    if let Some(memory) = options.memory.as_ref().or(instance.launch.memory.as_ref()) {
+        args.push(format!("-Xmx{}", memory));
+    }
+    args.extend(instance.launch.jvm_args.iter().cloned());
+    args.extend(options.jvm_args.iter().cloned());
+
+    match &version.arguments {
+        Some(arguments) => {
+            for arg in &arguments.jvm {
+                args.extend(arg.values(&features).iter().map(|value| substitute(value, &vars)));
+            }
+            args.extend(logging.args.iter().cloned());
+            args.push(main_class);
+            for arg in &arguments.game {
+                args.extend(arg.values(&features).iter().map(|value| substitute(value, &vars)));
+            }
+        }
+        None => {
+            args.push(substitute("-Djava.library.path=${natives_directory}", &vars));
+            args.push("-cp".to_string());
+            args.push(vars["classpath"].clone());
+            args.extend(logging.args.iter().cloned());
+            args.push(main_class);
+            let legacy = version.minecraft_arguments.clone().unwrap_or_default();
+            args.extend(legacy.split_whitespace().map(|value| substitute(value, &vars)));
+            if options.resolution.is_some() {
+                args.extend(["--width".to_string(), width.to_string()]);
+                args.extend(["--height".to_string(), height.to_string()]);
+            }
+        }
+    }
+    if let Some((host, port)) = legacy_server {
+        args.extend(["--server".to_string(), host, "--port".to_string(), port.to_string()]);
+    }
+
+    Ok(Prepared {
+        java: java_path(instance),
+        args,
+        game_dir: instance.path.clone(),
+        version,
+        logging,
+    })
+}
+
+/// What every real launch does first: syncs the packwiz pack unless `sync`
+/// is off, refuses to go on if the mods have errors unless `check` is off,
+/// then backs up the worlds if the instance asks for it
+pub fn before_launch(instance: &Instance, sync: bool, check: bool) -> Result<()> {
+    if let Some(source) = &instance.packwiz {
+        if sync {
+            let report = packwiz::sync::sync(instance, source)?;
+            for name in &report.skipped {
+                warn!("{} has no download URL and must be installed by hand", name);
+            }
+        } else {
+            debug!("Skipping packwiz sync of '{}'", instance.name);
+        }
+    }
+    if check {
+        let problems = mods::check::check(instance, &mods::load(instance));
+        let mut errors = 0;
+        for problem in &problems {
+            match problem.severity {
+                Severity::Error => {
+                    errors += 1;
+                    error!(instance = instance.id; "{}", problem.message);
+                }
+                Severity::Warning => {
+                    warn!(instance = instance.id; "{}", problem.message);
+                }
+            }
+        }
+        if errors > 0 {
+            return Err(eyre!(
+                "{} mod error(s) in '{}', see `mcl mods check` or launch with --no-check",
+                errors,
+                instance.name
+            ));
+        }
+    }
+    if instance.backups.auto {
+        let count = worlds::backup::create_all(instance)?;
+        debug!("Backed up {} worlds of '{}'", count, instance.name);
+    }
+    Ok(())
+}
+
+/// Prepares and starts an instance in the background, refusing to start a
+/// second copy of it. Syncing and downloading can take a while, so the TUI
+/// calls this off its thread.
+pub fn start(instance: &Instance, options: &LaunchOptions) -> Result<u32> {
+    if let Some(pid) = process::running(instance) {
+        return Err(eyre!("{} is already running (pid {})", instance.name, pid));
+    }
+    before_launch(instance, true, true)?;
+    prepare(instance, options)?.start()
+}
@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleAction {
+    Allow,
+    Disallow,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OsRule {
+    pub name: Option<String>,
+    pub arch: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Rule {
+    pub action: RuleAction,
+    pub os: Option<OsRule>,
+    pub features: Option<HashMap<String, bool>>,
+}
+
+/// Launcher features referenced by argument rules, e.g. `has_custom_resolution`
+pub type Features = HashMap<String, bool>;
+
+pub fn os_name() -> &'static str {
+    match std::env::consts::OS {
+        "macos" => "osx",
+        os => os,
+    }
+}
+
+impl Rule {
+    fn matches(&self, features: &Features) -> bool {
+        if let Some(os) = &self.os {
+            if os.name.as_deref().is_some_and(|name| name != os_name()) {
+                return false;
+            }
+            if os.arch.as_deref() == Some("x86") && std::env::consts::ARCH != "x86" {
+                return false;
+            }
+        }
+
+        if let Some(required) = &self.features {
+            return required
+                .iter()
+                .all(|(name, value)| features.get(name).copied().unwrap_or(false) == *value);
+        }
+
+        true
+    }
+}
+
+/// Evaluates a rule list the way the vanilla launcher does: the last
+/// matching rule wins and an empty list allows everything.
+pub fn rules_allow(rules: &[Rule], features: &Features) -> bool {
+    if rules.is_empty() {
+        return true;
+    }
+
+    rules
+        .iter()
+        .rev()
+        .find(|rule| rule.matches(features))
+        .is_some_and(|rule| rule.action == RuleAction::Allow)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum ArgumentValue {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Argument {
+    Plain(String),
+    Conditional {
+        #[serde(default)]
+        rules: Vec<Rule>,
+        value: ArgumentValue,
+    },
+}
+
+impl Argument {
+    /// The argument's values, if its rules allow it for these features
+    pub fn values(&self, features: &Features) -> Vec<String> {
+        match self {
+            Argument::Plain(value) => vec![value.clone()],
+            Argument::Conditional { rules, value } if rules_allow(rules, features) => match value {
+                ArgumentValue::One(value) => vec![value.clone()],
+                ArgumentValue::Many(values) => values.clone(),
+            },
+            Argument::Conditional { .. } => Vec::new(),
+        }
+    }
+
+    /// Whether one of the argument's rules checks this launcher feature
+    pub fn uses_feature(&self, feature: &str) -> bool {
+        match self {
+            Argument::Plain(_) => false,
+            Argument::Conditional { rules, .. } => rules.iter().any(|rule| {
+                rule.features
+                    .as_ref()
+                    .is_some_and(|features| features.contains_key(feature))
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Arguments {
+    #[serde(default)]
+    pub game: Vec<Argument>,
+    #[serde(default)]
+    pub jvm: Vec<Argument>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Artifact {
+    pub path: Option<String>,
+    #[serde(default)]
+    pub url: String,
+    pub sha1: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LibraryDownloads {
+    pub artifact: Option<Artifact>,
+    #[serde(default)]
+    pub classifiers: HashMap<String, Artifact>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Library {
+    pub name: String,
+    #[serde(default)]
+    pub downloads: LibraryDownloads,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    /// Legacy natives, mapping an OS name to a classifier
+    #[serde(default)]
+    pub natives: HashMap<String, String>,
+}
+
+/// Converts `group:artifact:version[:classifier]` into a repository path
+pub fn maven_path(name: &str) -> Option<String> {
+    let (name, extension) = name.split_once('@').unwrap_or((name, "jar"));
+    let mut parts = name.split(':');
+    let group = parts.next()?.replace('.', "/");
+    let artifact = parts.next()?;
+    let version = parts.next()?;
+    let file = match parts.next() {
+        Some(classifier) => format!("{}-{}-{}.{}", artifact, version, classifier, extension),
+        None => format!("{}-{}.{}", artifact, version, extension),
+    };
+    Some(format!("{}/{}/{}/{}", group, artifact, version, file))
+}
+
+impl Library {
+    pub fn artifact_path(&self) -> Option<String> {
+        self.downloads
+            .artifact
+            .as_ref()
+            .and_then(|artifact| artifact.path.clone())
+            .or_else(|| maven_path(&self.name))
+    }
+
+    /// Path of the natives jar for this OS, for pre-1.19 versions
+    pub fn natives_path(&self) -> Option<String> {
+        let classifier = self
+            .natives
+            .get(os_name())?
+            .replace("${arch}", if cfg!(target_pointer_width = "64") { "64" } else { "32" });
+        self.downloads
+            .classifiers
+            .get(&classifier)
+            .and_then(|artifact| artifact.path.clone())
+            .or_else(|| maven_path(&format!("{}:{}", self.name, classifier)))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AssetIndex {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LoggingFile {
+    pub id: String,
+    pub url: String,
+    pub sha1: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LoggingClient {
+    pub argument: String,
+    pub file: LoggingFile,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Logging {
+    pub client: Option<LoggingClient>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JavaVersion {
+    pub major_version: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionJson {
+    pub id: String,
+    pub inherits_from: Option<String>,
+    pub main_class: Option<String>,
+    /// Space separated game arguments used before 1.13
+    pub minecraft_arguments: Option<String>,
+    pub arguments: Option<Arguments>,
+    #[serde(default)]
+    pub libraries: Vec<Library>,
+    pub asset_index: Option<AssetIndex>,
+    pub assets: Option<String>,
+    /// Id of the version whose jar is used, for inherited versions
+    pub jar: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+    pub logging: Option<Logging>,
+    pub java_version: Option<JavaVersion>,
+}
+
+impl VersionJson {
+    /// Reads `versions/<id>/<id>.json`, resolving `inheritsFrom` chains
+    pub fn load(versions_dir: &Path, id: &str) -> Result<VersionJson> {
+        let path = versions_dir.join(id).join(format!("{}.json", id));
+        let raw = fs::read_to_string(&path)
+            .wrap_err_with(|| format!("version '{}' is not installed ({})", id, path.display()))?;
+        let version: VersionJson = serde_json::from_str(&raw)
+            .wrap_err_with(|| format!("invalid version file {}", path.display()))?;
+
+        match version.inherits_from.clone() {
+            Some(parent) if parent != id => {
+                let parent = VersionJson::load(versions_dir, &parent)?;
+                Ok(version.merge(parent))
+            }
+            Some(_) => Err(eyre!("version '{}' inherits from itself", id)),
+            None => Ok(version),
+        }
+    }
+
+    fn merge(mut self, parent: VersionJson) -> VersionJson {
+        let mut libraries = self.libraries;
+        libraries.extend(parent.libraries);
+        self.libraries = libraries;
+
+        self.arguments = match (parent.arguments, self.arguments) {
+            (Some(mut base), Some(child)) => {
+                base.game.extend(child.game);
+                base.jvm.extend(child.jvm);
+                Some(base)
+            }
+            (base, child) => child.or(base),
+        };
+
+        self.jar = self.jar.or(parent.jar).or(Some(parent.id));
+        self.main_class = self.main_class.or(parent.main_class);
+        self.minecraft_arguments = self.minecraft_arguments.or(parent.minecraft_arguments);
+        self.asset_index = self.asset_index.or(parent.asset_index);
+        self.assets = self.assets.or(parent.assets);
+        self.kind = self.kind.or(parent.kind);
+        self.logging = self.logging.or(parent.logging);
+        self.java_version = self.java_version.or(parent.java_version);
+        self.inherits_from = None;
+        self
+    }
+
+    pub fn jar_path(&self, versions_dir: &Path) -> PathBuf {
+        let id = self.jar.as_deref().unwrap_or(&self.id);
+        versions_dir.join(id).join(format!("{}.jar", id))
+    }
+
+    pub fn asset_index_name(&self) -> String {
+        self.asset_index
+            .as_ref()
+            .map(|index| index.id.clone())
+            .or_else(|| self.assets.clone())
+            .unwrap_or_else(|| "legacy".to_string())
+    }
+}
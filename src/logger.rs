@@ -1,56 +1,337 @@
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
-use chrono::Local;
+use chrono::{DateTime, Local, SecondsFormat};
 use color_eyre::owo_colors::OwoColorize;
 use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::config::{get_state_path, types::Config};
+
+/// Messages kept for the TUI's messages pane
+const MAX_MESSAGES: usize = 500;
+
+/// Severity of a launcher log line, from most to least severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+}
+
+/// How lines are written to the terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// Colored lines for people
+    #[default]
+    Human,
+    /// One JSON object per line for scripts
+    Json,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Format> {
+        match name {
+            "human" => Some(Format::Human),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Named values attached to a line, like `instance` or `url`
+pub type Fields = Vec<(&'static str, Value)>;
+
+/// Turns a field given to the logging macros into JSON, numbers stay numbers
+pub fn field<T: Serialize + ?Sized>(value: &T) -> Value {
+    serde_json::to_value(value).unwrap_or(Value::Null)
+}
+
+/// A line logged by the launcher
+#[derive(Debug, Clone)]
+pub struct Record {
+    pub time: DateTime<Local>,
+    pub level: Level,
+    /// Module that logged it, without the crate name, e.g. `launch::log4j`
+    pub module: String,
+    pub message: String,
+    pub fields: Fields,
+}
+
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    timestamp: String,
+    level: String,
+    module: &'a str,
+    message: &'a str,
+    fields: Map<String, Value>,
+}
+
+impl Record {
+    fn timestamp(&self) -> String {
+        self.time.format("%Y-%m-%d %H:%M:%S").to_string()
+    }
+
+    /// Fields as ` key=value`, strings unquoted
+    fn fields_text(&self) -> String {
+        self.fields
+            .iter()
+            .map(|(key, value)| match value {
+                Value::String(text) => format!(" {}={}", key, text),
+                value => format!(" {}={}", key, value),
+            })
+            .collect()
+    }
+
+    fn colored(&self) -> String {
+        let tag = format!("[{}]", self.level.name());
+        let tag = match self.level {
+            Level::Error => tag.red().bold().to_string(),
+            Level::Warn => tag.yellow().to_string(),
+            Level::Info => tag.green().to_string(),
+            Level::Debug => tag.cyan().to_string(),
+            Level::Trace => tag.magenta().to_string(),
+        };
+        let fields = self.fields_text();
+        if fields.is_empty() {
+            format!("{} {} {}", self.timestamp().dimmed(), tag, self.message)
+        } else {
+            format!("{} {} {}{}", self.timestamp().dimmed(), tag, self.message, fields.dimmed())
+        }
+    }
+
+    fn plain(&self) -> String {
+        format!(
+            "{} {:<5} {}: {}{}",
+            self.timestamp(),
+            self.level.name(),
+            self.module,
+            self.message,
+            self.fields_text()
+        )
+    }
+
+    fn json(&self) -> String {
+        let record = JsonRecord {
+            timestamp: self.time.to_rfc3339_opts(SecondsFormat::Millis, false),
+            level: self.level.name().to_lowercase(),
+            module: &self.module,
+            message: &self.message,
+            fields: self
+                .fields
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect(),
+        };
+        serde_json::to_string(&record).unwrap_or_default()
+    }
+}
+
+/// `mcl.log` in the state dir, rotated to `mcl.log.1`, `mcl.log.2`... once
+/// it outgrows `max_size`
+struct FileSink {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: u64,
+    max_files: usize,
+}
+
+impl FileSink {
+    fn open(path: PathBuf, max_size: u64, max_files: usize) -> std::io::Result<FileSink> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(FileSink {
+            path,
+            file,
+            size,
+            max_size,
+            max_files,
+        })
+    }
+
+    fn rotated(path: &Path, n: usize) -> PathBuf {
+        let mut name = path.as_os_str().to_os_string();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        if self.max_files == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            let _ = fs::remove_file(Self::rotated(&self.path, self.max_files));
+            for n in (1..self.max_files).rev() {
+                let from = Self::rotated(&self.path, n);
+                if from.exists() {
+                    fs::rename(&from, Self::rotated(&self.path, n + 1))?;
+                }
+            }
+            fs::rename(&self.path, Self::rotated(&self.path, 1))?;
+            self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+
+    fn write(&mut self, line: &str) {
+        if self.size > 0 && self.size + line.len() as u64 + 1 > self.max_size {
+            // a failed rotation keeps appending rather than losing lines
+            let _ = self.rotate();
+        }
+        if writeln!(self.file, "{}", line).is_ok() {
+            self.size += line.len() as u64 + 1;
+        }
+    }
+}
 
 pub struct Logger {
-    debug: bool,
+    level: Level,
+    /// Levels for modules and their children, most specific first
+    modules: Vec<(String, Level)>,
+    file: Option<FileSink>,
+    format: Format,
+    /// Set while the TUI owns the terminal, lines then go to `messages`
+    tui: bool,
+    messages: VecDeque<Record>,
 }
 
 impl Logger {
-    pub fn init(debug: bool) {
-        let mut logger = LOGGER.lock().unwrap();
-        logger.debug = debug;
+    pub fn init(config: &Config) {
+        let logging = &config.logging;
+        let level = logging
+            .level
+            .unwrap_or(if config.general.debug { Level::Debug } else { Level::Info });
+        let mut modules: Vec<(String, Level)> = logging
+            .modules
+            .iter()
+            .map(|(module, level)| (module.trim_start_matches("mcl::").to_string(), *level))
+            .collect();
+        modules.sort_by_key(|(module, _)| std::cmp::Reverse(module.len()));
+
+        let file = if logging.file {
+            match FileSink::open(log_path(), logging.max_size * 1024, logging.max_files) {
+                Ok(sink) => Some(sink),
+                Err(err) => {
+                    eprintln!("failed to open {}: {}", log_path().display(), err);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let mut logger = get_logger();
+        logger.level = level;
+        logger.modules = modules;
+        logger.file = file;
+        logger.format = logging.format;
     }
 
-    fn timestamp() -> String {
-        Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+    /// Overrides the format from the config, for `--log-format`
+    pub fn set_format(format: Format) {
+        get_logger().format = format;
     }
 
-    pub fn debug(&self, message: &str) {
-        if self.debug {
-            println!(
-                "{} {} {}",
-                Self::timestamp().dimmed(),
-                "[DEBUG]".cyan(),
-                message
-            );
-        }
+    /// Routes lines into the messages pane instead of the terminal while
+    /// `active`
+    pub fn set_tui(active: bool) {
+        get_logger().tui = active;
     }
 
-    pub fn info(&self, message: &str) {
-        println!(
-            "{} {} {}",
-            Self::timestamp().dimmed(),
-            "[INFO]".green(),
-            message
-        );
+    /// Lines logged while the TUI was active, oldest first
+    pub fn messages() -> Vec<Record> {
+        get_logger().messages.iter().cloned().collect()
     }
 
-    pub fn error(&self, message: &str) {
-        eprintln!(
-            "{} {} {}",
-            Self::timestamp().dimmed(),
-            "[ERROR]".red().bold(),
-            message
-        );
+    fn enabled(&self, level: Level, module: &str) -> bool {
+        let max = self
+            .modules
+            .iter()
+            .find(|(prefix, _)| {
+                module == prefix || module.strip_prefix(prefix.as_str()).is_some_and(|rest| rest.starts_with("::"))
+            })
+            .map_or(self.level, |(_, level)| *level);
+        level <= max
+    }
+
+    pub fn log(&mut self, level: Level, module: &str, message: &str, fields: Fields) {
+        let module = module.strip_prefix("mcl::").unwrap_or(module);
+        if !self.enabled(level, module) {
+            return;
+        }
+        let record = Record {
+            time: Local::now(),
+            level,
+            module: module.to_string(),
+            message: message.to_string(),
+            fields,
+        };
+
+        if let Some(file) = &mut self.file {
+            file.write(&record.plain());
+        }
+        if self.tui {
+            if self.messages.len() == MAX_MESSAGES {
+                self.messages.pop_front();
+            }
+            self.messages.push_back(record);
+            return;
+        }
+        match self.format {
+            Format::Human if level <= Level::Warn => eprintln!("{}", record.colored()),
+            Format::Human => println!("{}", record.colored()),
+            // one stream for scripts, stdout stays command output
+            Format::Json => eprintln!("{}", record.json()),
+        }
     }
 }
 
+/// The launcher's own log, `~/.local/state/mcl/mcl.log`
+pub fn log_path() -> PathBuf {
+    get_state_path().join("mcl.log")
+}
+
 lazy_static! {
-    pub static ref LOGGER: Mutex<Logger> = Mutex::new(Logger { debug: false });
+    pub static ref LOGGER: Mutex<Logger> = Mutex::new(Logger {
+        level: Level::Info,
+        modules: Vec::new(),
+        file: None,
+        format: Format::Human,
+        tui: false,
+        messages: VecDeque::new(),
+    });
+}
+
+/// What the logging macros call, after formatting so nothing they format
+/// can log while the logger is locked
+pub fn log(level: Level, module: &str, message: &str, fields: Fields) {
+    get_logger().log(level, module, message, fields);
 }
 
 pub fn get_logger() -> std::sync::MutexGuard<'static, Logger> {
-    LOGGER.lock().unwrap()
+    // a panic while logging must not take the logger down with it
+    LOGGER.lock().unwrap_or_else(|err| err.into_inner())
 }
@@ -0,0 +1,95 @@
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::Child;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use color_eyre::eyre::Result;
+
+use super::{output_log, Parsed, Parser};
+
+/// Where a stream goes besides the output log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Echo {
+    None,
+    Stdout,
+    Stderr,
+}
+
+/// Copies the game's stdout and stderr into [`output_log`], starting it
+/// over for each run. With `echo` the output is also printed, with XML
+/// events turned back into readable lines. Join the handles after the game
+/// exits to be sure everything was written.
+pub fn capture(child: &mut Child, game_dir: &Path, echo: bool) -> Result<Vec<JoinHandle<()>>> {
+    let path = output_log(game_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = Arc::new(Mutex::new(File::create(&path)?));
+
+    let mut handles = Vec::new();
+    if let Some(stdout) = child.stdout.take() {
+        let target = if echo { Echo::Stdout } else { Echo::None };
+        handles.push(spawn(stdout, file.clone(), target));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        let target = if echo { Echo::Stderr } else { Echo::None };
+        handles.push(spawn(stderr, file, target));
+    }
+    Ok(handles)
+}
+
+fn spawn(stream: impl Read + Send + 'static, file: Arc<Mutex<File>>, echo: Echo) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let mut parser = Parser::default();
+        // XML events span several lines and are written as one block so
+        // the other stream can't end up in the middle of them
+        let mut block = String::new();
+        let mut reader = BufReader::new(stream);
+        let mut bytes = Vec::new();
+        loop {
+            bytes.clear();
+            // read bytes rather than lines: output in another encoding must
+            // not stop the draining, or the game blocks once the pipe is full
+            match reader.read_until(b'\n', &mut bytes) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            }
+            let line = String::from_utf8_lossy(&bytes);
+            let line = line.trim_end_matches(['\n', '\r']).to_string();
+            block.push_str(&line);
+            block.push('\n');
+
+            let parsed = parser.push(&line);
+            if parsed.is_none() && !line.trim().is_empty() {
+                continue;
+            }
+            if let Ok(mut file) = file.lock() {
+                let _ = file.write_all(block.as_bytes());
+                let _ = file.flush();
+            }
+            block.clear();
+
+            let text = match parsed {
+                Some(Parsed::Event(event)) if line.contains("</log4j:Event>") => event.format(),
+                _ => line,
+            };
+            match echo {
+                // the XML layout separates events with blank lines
+                _ if text.trim().is_empty() => {}
+                Echo::None => {}
+                Echo::Stdout => println!("{}", text),
+                Echo::Stderr => eprintln!("{}", text),
+            }
+        }
+        if !block.is_empty() {
+            if let Ok(mut file) = file.lock() {
+                let _ = file.write_all(block.as_bytes());
+            }
+        }
+        let _ = io::stdout().flush();
+    })
+}
@@ -0,0 +1,313 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Local};
+use color_eyre::eyre::{Result, WrapErr};
+
+use crate::instance::process;
+
+use super::{output_log, rules, Level, Parser};
+
+/// What wrote a crash report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// `crash-reports/crash-*.txt` written by the game
+    Game,
+    /// `hs_err_pid*.log` written when the JVM itself dies
+    Jvm,
+    /// Output of a run that failed before it could write a report
+    Output,
+}
+
+impl Kind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::Game => "game",
+            Kind::Jvm => "jvm",
+            Kind::Output => "output",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub title: String,
+    pub lines: Vec<String>,
+}
+
+impl Section {
+    fn new(title: &str) -> Section {
+        Section {
+            title: title.to_string(),
+            lines: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub path: PathBuf,
+    pub kind: Kind,
+    pub modified: Option<DateTime<Local>>,
+    /// What went wrong in a line or two
+    pub description: String,
+    pub stack_trace: String,
+    /// Entries of the mod lists in the system details
+    pub mods: Vec<String>,
+    pub system: Vec<(String, String)>,
+    pub sections: Vec<Section>,
+    pub text: String,
+}
+
+/// A report with what the rules made of it
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    pub report: Report,
+    pub diagnoses: Vec<rules::Diagnosis>,
+}
+
+pub fn analyze(report: Report) -> Analysis {
+    let diagnoses = rules::diagnose(&report, &rules::load());
+    Analysis { report, diagnoses }
+}
+
+pub fn crash_reports_dir(game_dir: &Path) -> PathBuf {
+    game_dir.join("crash-reports")
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// Game crash reports and JVM error logs of an instance, newest first
+pub fn list(game_dir: &Path) -> Vec<(PathBuf, SystemTime)> {
+    let mut reports = Vec::new();
+    let reports_dir = crash_reports_dir(game_dir);
+    for (dir, is_report) in [
+        (reports_dir.as_path(), (|name: &str| name.ends_with(".txt")) as fn(&str) -> bool),
+        (game_dir, |name: &str| name.starts_with("hs_err_pid") && name.ends_with(".log")),
+    ] {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !path.is_file() || !is_report(&name) {
+                continue;
+            }
+            if let Some(time) = modified(&path) {
+                reports.push((path, time));
+            }
+        }
+    }
+    reports.sort_by_key(|(_, time)| std::cmp::Reverse(*time));
+    reports
+}
+
+/// Newest crash report of an instance. When the last run failed without
+/// writing one, e.g. because of the wrong Java, its output stands in.
+pub fn latest(game_dir: &Path) -> Result<Option<Report>> {
+    let newest = list(game_dir).into_iter().next();
+    if let Some(session) = process::session_at(game_dir).filter(|session| session.crashed()) {
+        let started = UNIX_EPOCH + Duration::from_secs(session.started.max(0) as u64);
+        let output = output_log(game_dir);
+        if newest.as_ref().is_none_or(|(_, time)| *time < started) && output.exists() {
+            return load(&output).map(Some);
+        }
+    }
+    newest.map(|(path, _)| load(&path)).transpose()
+}
+
+pub fn load(path: &Path) -> Result<Report> {
+    let bytes = fs::read(path).wrap_err_with(|| format!("failed to read {}", path.display()))?;
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let kind = if name.starts_with("hs_err_pid") {
+        Kind::Jvm
+    } else if path.parent().and_then(Path::file_name).is_some_and(|parent| parent == ".mcl") {
+        Kind::Output
+    } else {
+        Kind::Game
+    };
+    Ok(parse(path, kind, String::from_utf8_lossy(&bytes).to_string()))
+}
+
+pub fn parse(path: &Path, kind: Kind, text: String) -> Report {
+    let mut report = Report {
+        path: path.to_path_buf(),
+        kind,
+        modified: modified(path).map(DateTime::<Local>::from),
+        description: String::new(),
+        stack_trace: String::new(),
+        mods: Vec::new(),
+        system: Vec::new(),
+        sections: Vec::new(),
+        text,
+    };
+    match kind {
+        Kind::Game => parse_game(&mut report),
+        Kind::Jvm => parse_jvm(&mut report),
+        Kind::Output => parse_output(&mut report),
+    }
+    report
+}
+
+/// `---- Minecraft Crash Report ----` with a description, the stack trace
+/// and `-- Title --` sections, the last one being the system details
+fn parse_game(report: &mut Report) {
+    let mut stack = Vec::new();
+    let mut walkthrough = false;
+    let mut current: Option<Section> = None;
+    for line in report.text.lines() {
+        if let Some(title) = line.strip_prefix("-- ").and_then(|rest| rest.strip_suffix(" --")) {
+            report.sections.extend(current.take());
+            current = Some(Section::new(title));
+            continue;
+        }
+        if let Some(section) = &mut current {
+            section.lines.push(line.to_string());
+            continue;
+        }
+        if line.starts_with("A detailed walkthrough") {
+            walkthrough = true;
+        } else if let Some(description) = line.strip_prefix("Description: ") {
+            if report.description.is_empty() {
+                report.description = description.trim().to_string();
+            }
+        } else if !walkthrough && !report.description.is_empty() {
+            stack.push(line);
+        }
+    }
+    report.sections.extend(current);
+    report.stack_trace = stack.join("\n").trim().to_string();
+
+    let Some(details) = report.sections.iter().find(|section| section.title == "System Details") else {
+        return;
+    };
+    // `\tKey: value`, with lists like the mods nested one level deeper
+    let mut key = String::new();
+    for line in &details.lines {
+        if line.starts_with("\t\t") || line.starts_with("        ") {
+            if key.to_lowercase().contains("mod") && !line.trim().is_empty() {
+                report.mods.push(line.trim().to_string());
+            }
+            continue;
+        }
+        let Some((name, value)) = line.trim().split_once(':') else {
+            continue;
+        };
+        key = name.trim().to_string();
+        if !value.trim().is_empty() {
+            report.system.push((key.clone(), value.trim().to_string()));
+        }
+    }
+}
+
+/// `T H R E A D` to `THREAD`
+fn spaced_title(title: &str) -> String {
+    title
+        .split("  ")
+        .map(|word| word.replace(' ', ""))
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Lines that say where a JVM error log starts talking about the JVM
+/// instead of the error
+const JVM_HEADER_END: [&str; 4] = ["JRE version", "Possible reasons", "If you would like", "No core dump"];
+
+/// `hs_err_pid*.log`: a `#` commented header, then sections between
+/// `---------------  T H R E A D  ---------------` lines
+fn parse_jvm(report: &mut Report) {
+    let mut header = Vec::new();
+    let mut current: Option<Section> = None;
+    for line in report.text.lines() {
+        if line.starts_with("---------------") {
+            report.sections.extend(current.take());
+            current = Some(Section::new(&spaced_title(line.trim_matches(['-', ' ']))));
+            continue;
+        }
+        match &mut current {
+            Some(section) => section.lines.push(line.to_string()),
+            None => header.push(line.trim_start_matches('#').trim().to_string()),
+        }
+    }
+    report.sections.extend(current);
+
+    let mut description = Vec::new();
+    for line in &header {
+        if JVM_HEADER_END.iter().any(|end| line.starts_with(end)) {
+            break;
+        }
+        if !line.is_empty() && !line.starts_with("A fatal error has been detected") {
+            description.push(line.clone());
+        }
+    }
+    if let Some(i) = header.iter().position(|line| line.starts_with("Problematic frame:")) {
+        if let Some(frame) = header.get(i + 1) {
+            description.push(format!("Problematic frame: {}", frame));
+        }
+    }
+    report.description = description.join("\n");
+
+    for line in &header {
+        if let Some((key, value)) = line.split_once(": ") {
+            if key == "JRE version" || key == "Java VM" {
+                report.system.push((key.to_string(), value.trim().to_string()));
+            }
+        }
+    }
+    for section in &report.sections {
+        match section.title.as_str() {
+            "THREAD" => {
+                // the native and java frames lists end at a blank line
+                let mut in_frames = false;
+                let mut frames = Vec::new();
+                for line in &section.lines {
+                    if line.contains("frames:") {
+                        in_frames = true;
+                    } else if line.trim().is_empty() {
+                        in_frames = false;
+                        continue;
+                    }
+                    if in_frames {
+                        frames.push(line.as_str());
+                    }
+                }
+                report.stack_trace = frames.join("\n");
+            }
+            "SYSTEM" => {
+                for line in &section.lines {
+                    let Some((key, value)) = line.split_once(':') else {
+                        continue;
+                    };
+                    if ["OS", "CPU", "Memory", "vm_info", "Host"].contains(&key) && !value.trim().is_empty() {
+                        report.system.push((key.to_string(), value.trim().to_string()));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Captured output of a failed run: the last error is the description
+fn parse_output(report: &mut Report) {
+    let events = super::parse(&report.text, &mut Parser::default());
+    let error = events
+        .iter()
+        .rev()
+        .find(|event| event.level >= Level::Error || event.throwable.is_some());
+    if let Some(event) = error {
+        report.description = event.message.clone();
+        report.stack_trace = event.throwable.clone().unwrap_or_default();
+    }
+    let mut section = Section::new("Output");
+    section.lines = report.text.lines().map(String::from).collect();
+    report.sections.push(section);
+}
@@ -0,0 +1,226 @@
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use flate2::read::MultiGzDecoder;
+use regex::{Regex, RegexBuilder};
+
+use super::{Event, Level, Parsed, Parser};
+
+/// A log the game wrote to `logs/`: `latest.log`, `debug.log` or a rotated
+/// `2024-01-01-1.log.gz`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFile {
+    pub path: PathBuf,
+    pub name: String,
+    /// Size on disk, compressed for `.gz` files
+    pub size: u64,
+    pub modified: Option<DateTime<Local>>,
+}
+
+impl LogFile {
+    fn at(path: &Path) -> Option<LogFile> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(LogFile {
+            path: path.to_path_buf(),
+            name: path.file_name()?.to_string_lossy().to_string(),
+            size: metadata.len(),
+            modified: metadata.modified().ok().map(DateTime::<Local>::from),
+        })
+    }
+
+    pub fn compressed(&self) -> bool {
+        self.name.ends_with(".gz")
+    }
+
+    /// Day the log was written, for records that only carry the time. Rotated
+    /// logs are named after it, the others go by their modification time.
+    pub fn date(&self) -> NaiveDate {
+        self.name
+            .get(..10)
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+            .or_else(|| self.modified.map(|modified| modified.date_naive()))
+            .unwrap_or_else(|| Local::now().date_naive())
+    }
+
+    /// Reader over the text of the log, decompressing as it goes so big
+    /// logs never sit in memory whole
+    pub fn open(&self) -> Result<Box<dyn BufRead + Send>> {
+        let file = File::open(&self.path).wrap_err_with(|| format!("failed to open {}", self.path.display()))?;
+        Ok(if self.compressed() {
+            Box::new(BufReader::new(MultiGzDecoder::new(file)))
+        } else {
+            Box::new(BufReader::new(file))
+        })
+    }
+}
+
+pub fn logs_dir(game_dir: &Path) -> PathBuf {
+    game_dir.join("logs")
+}
+
+/// Logs of an instance, newest first
+pub fn list(game_dir: &Path) -> Vec<LogFile> {
+    let Ok(entries) = fs::read_dir(logs_dir(game_dir)) else {
+        return Vec::new();
+    };
+    let mut files: Vec<LogFile> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            path.is_file() && (name.ends_with(".log") || name.ends_with(".log.gz"))
+        })
+        .filter_map(|path| LogFile::at(&path))
+        .collect();
+    files.sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| b.name.cmp(&a.name)));
+    files
+}
+
+/// A log by file name, with or without `.log`/`.log.gz`
+pub fn find(game_dir: &Path, name: &str) -> Result<LogFile> {
+    list(game_dir)
+        .into_iter()
+        .find(|file| {
+            file.name == name
+                || file.name.strip_suffix(".log") == Some(name)
+                || file.name.strip_suffix(".log.gz") == Some(name)
+        })
+        .ok_or_else(|| eyre!("no log named '{}', see mcl logs list", name))
+}
+
+/// Which events of a log to show
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    pub since: Option<NaiveDateTime>,
+    pub until: Option<NaiveDateTime>,
+    pub min_level: Option<Level>,
+    pub pattern: Option<Regex>,
+}
+
+impl Filter {
+    /// Whether the event passes. `time` is the event's own time or, for
+    /// records without one, that of the record before it.
+    pub fn matches(&self, event: &Event, time: Option<NaiveDateTime>) -> bool {
+        if self.min_level.is_some_and(|level| event.level < level) {
+            return false;
+        }
+        if self.since.is_some() || self.until.is_some() {
+            let Some(time) = time else {
+                return false;
+            };
+            if self.since.is_some_and(|since| time < since) || self.until.is_some_and(|until| time > until) {
+                return false;
+            }
+        }
+        match &self.pattern {
+            Some(pattern) => {
+                [&event.message, &event.logger, &event.thread]
+                    .iter()
+                    .any(|text| pattern.is_match(text))
+                    || event.throwable.as_ref().is_some_and(|throwable| pattern.is_match(throwable))
+            }
+            None => true,
+        }
+    }
+}
+
+/// Case-insensitive regex for searching logs
+pub fn search(query: &str) -> Result<Regex> {
+    RegexBuilder::new(query)
+        .case_insensitive(true)
+        .build()
+        .wrap_err_with(|| format!("invalid search pattern '{}'", query))
+}
+
+/// Parses a time bound: `12:30`, `12:30:15`, `2024-01-01`, `2024-01-01 12:30`
+/// or relative to now like `30m`, `2h` and `1d`. Times without a date are on
+/// `date`.
+pub fn parse_time(value: &str, date: NaiveDate) -> Result<NaiveDateTime> {
+    let value = value.trim();
+    if let Some(unit) = value.chars().last().filter(|unit| "smhd".contains(*unit)) {
+        if let Ok(amount) = value[..value.len() - 1].parse::<i64>() {
+            let ago = match unit {
+                's' => Duration::seconds(amount),
+                'm' => Duration::minutes(amount),
+                'h' => Duration::hours(amount),
+                _ => Duration::days(amount),
+            };
+            return Ok(Local::now().naive_local() - ago);
+        }
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(time);
+        }
+    }
+    if let Ok(day) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(day.and_time(NaiveTime::MIN));
+    }
+    for format in ["%H:%M:%S", "%H:%M"] {
+        if let Ok(time) = NaiveTime::parse_from_str(value, format) {
+            return Ok(date.and_time(time));
+        }
+    }
+    Err(eyre!("invalid time '{}', expected e.g. 12:30, 2024-01-01 12:30 or 2h", value))
+}
+
+/// Streams the events of a log that pass the filter to `f`, one record at a
+/// time
+pub fn read(file: &LogFile, filter: &Filter, mut f: impl FnMut(Event)) -> Result<()> {
+    scan(file, filter, |event, passes| {
+        if passes {
+            f(event);
+        }
+    })
+}
+
+/// Streams every event of a log to `f` along with whether it passes the
+/// filter, for callers that need to see what the filter leaves out
+pub fn scan(file: &LogFile, filter: &Filter, mut f: impl FnMut(Event, bool)) -> Result<()> {
+    let mut reader = file.open()?;
+    let mut parser = Parser::with_date(file.date());
+    let mut pending: Option<Event> = None;
+    let mut last_time = None;
+    let mut flush = |event: Option<Event>| {
+        let Some(event) = event else {
+            return;
+        };
+        last_time = event.time.or(last_time);
+        let passes = filter.matches(&event, last_time);
+        f(event, passes);
+    };
+
+    let mut bytes = Vec::new();
+    loop {
+        bytes.clear();
+        if reader
+            .read_until(b'\n', &mut bytes)
+            .wrap_err_with(|| format!("failed to read {}", file.path.display()))?
+            == 0
+        {
+            break;
+        }
+        let line = String::from_utf8_lossy(&bytes);
+        match parser.push(&line) {
+            Some(Parsed::Event(event)) => flush(pending.replace(event)),
+            Some(Parsed::Continuation(line)) => match &mut pending {
+                Some(event) => event.extend(&line),
+                None => pending = Some(Event::plain(&line)),
+            },
+            None => {}
+        }
+    }
+    match parser.finish() {
+        Some(Parsed::Continuation(line)) => match &mut pending {
+            Some(event) => event.extend(&line),
+            None => pending = Some(Event::plain(&line)),
+        },
+        Some(Parsed::Event(event)) => flush(pending.replace(event)),
+        None => {}
+    }
+    flush(pending);
+    Ok(())
+}
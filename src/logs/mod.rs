@@ -0,0 +1,480 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime};
+
+pub mod capture;
+pub mod crash;
+pub mod history;
+pub mod redact;
+pub mod rules;
+
+/// Raw stdout and stderr of the latest run, written by [`capture`]
+pub fn output_log(game_dir: &Path) -> PathBuf {
+    game_dir.join(".mcl").join("output.log")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl Level {
+    pub const ALL: [Level; 6] = [
+        Level::Trace,
+        Level::Debug,
+        Level::Info,
+        Level::Warn,
+        Level::Error,
+        Level::Fatal,
+    ];
+
+    pub fn parse(value: &str) -> Option<Level> {
+        match value.trim().to_uppercase().as_str() {
+            "TRACE" | "FINEST" | "FINER" => Some(Level::Trace),
+            "DEBUG" | "FINE" | "CONFIG" => Some(Level::Debug),
+            "INFO" => Some(Level::Info),
+            "WARN" | "WARNING" => Some(Level::Warn),
+            "ERROR" | "SEVERE" => Some(Level::Error),
+            "FATAL" => Some(Level::Fatal),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Trace => "TRACE",
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+            Level::Fatal => "FATAL",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// One log record, from a log4j XML event or a line of plain text
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub time: Option<NaiveDateTime>,
+    pub level: Level,
+    pub thread: String,
+    pub logger: String,
+    pub message: String,
+    /// Stack trace, if the record carries one
+    pub throwable: Option<String>,
+}
+
+impl Event {
+    fn plain(message: &str) -> Event {
+        Event {
+            time: None,
+            level: Level::Info,
+            thread: String::new(),
+            logger: String::new(),
+            message: message.to_string(),
+            throwable: None,
+        }
+    }
+
+    /// Same layout as the game's own console output
+    pub fn format(&self) -> String {
+        let mut line = String::new();
+        if let Some(time) = self.time {
+            line.push_str(&format!("[{}] ", time.format("%H:%M:%S")));
+        }
+        if !self.thread.is_empty() {
+            line.push_str(&format!("[{}/{}] ", self.thread, self.level));
+        }
+        if !self.logger.is_empty() {
+            line.push_str(&format!("({}) ", short_logger(&self.logger)));
+        }
+        line.push_str(&self.message);
+        if let Some(throwable) = &self.throwable {
+            line.push('\n');
+            line.push_str(throwable.trim_end());
+        }
+        line
+    }
+
+    /// Whether `query` (lowercase) appears anywhere in the record
+    pub fn contains(&self, query: &str) -> bool {
+        [&self.message, &self.logger, &self.thread]
+            .iter()
+            .any(|text| text.to_lowercase().contains(query))
+            || self
+                .throwable
+                .as_ref()
+                .is_some_and(|throwable| throwable.to_lowercase().contains(query))
+    }
+
+    /// Adds a line that belongs to this record, such as a stack frame
+    fn extend(&mut self, line: &str) {
+        if self.throwable.is_some() || is_stack_line(line) {
+            let throwable = self.throwable.get_or_insert_with(String::new);
+            if !throwable.is_empty() {
+                throwable.push('\n');
+            }
+            throwable.push_str(line);
+        } else {
+            self.message.push('\n');
+            self.message.push_str(line);
+        }
+    }
+}
+
+/// `net.minecraft.client.Minecraft` as `Minecraft`
+pub fn short_logger(logger: &str) -> &str {
+    logger.rsplit('.').next().unwrap_or(logger)
+}
+
+/// Stack frames and exception headers like `java.io.IOException: ...`
+fn is_stack_line(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    let class = trimmed.split([':', ' ']).next().unwrap_or_default();
+    trimmed.starts_with("at ")
+        || trimmed.starts_with("Caused by")
+        || trimmed.starts_with("Suppressed:")
+        || (trimmed.starts_with("...") && trimmed.ends_with("more"))
+        || (class.contains('.') && (class.ends_with("Exception") || class.ends_with("Error")))
+}
+
+/// What a line of output turned into
+#[derive(Debug, Clone, PartialEq)]
+pub enum Parsed {
+    Event(Event),
+    /// Belongs to the previous event, e.g. a stack frame
+    Continuation(String),
+}
+
+/// Adds a parsed line to a list of events
+pub fn append(events: &mut Vec<Event>, parsed: Parsed) {
+    match parsed {
+        Parsed::Event(event) => events.push(event),
+        Parsed::Continuation(line) => match events.last_mut() {
+            Some(event) => event.extend(&line),
+            None => events.push(Event::plain(&line)),
+        },
+    }
+}
+
+/// Longest log4j event kept in memory while waiting for its end tag
+const MAX_EVENT: usize = 1 << 20;
+
+/// Turns game output into events line by line. Understands the log4j XML
+/// layout modern versions print with the client logging config and the
+/// plain formats of the game, Forge, Fabric and pre-1.7 versions.
+#[derive(Debug, Clone, Default)]
+pub struct Parser {
+    /// Lines of an XML event still waiting for `</log4j:Event>`
+    xml: Option<String>,
+    /// Date of plain records, which only carry the time of day
+    date: Option<NaiveDate>,
+}
+
+impl Parser {
+    /// Parser for a log written on `date`
+    pub fn with_date(date: NaiveDate) -> Parser {
+        Parser {
+            xml: None,
+            date: Some(date),
+        }
+    }
+
+    pub fn push(&mut self, line: &str) -> Option<Parsed> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        if let Some(buffer) = &mut self.xml {
+            buffer.push('\n');
+            buffer.push_str(line);
+            if line.contains("</log4j:Event>") || buffer.len() > MAX_EVENT {
+                let buffer = self.xml.take().unwrap_or_default();
+                return Some(parse_xml(&buffer).map_or(Parsed::Continuation(buffer), Parsed::Event));
+            }
+            return None;
+        }
+
+        if line.trim_start().starts_with("<log4j:Event") {
+            if line.contains("</log4j:Event>") {
+                return parse_xml(line).map(Parsed::Event);
+            }
+            self.xml = Some(line.to_string());
+            return None;
+        }
+        if line.trim().is_empty() {
+            return None;
+        }
+
+        let date = self.date.unwrap_or_else(|| Local::now().date_naive());
+        Some(match parse_plain(line, date) {
+            Some(event) => Parsed::Event(event),
+            // indented lines continue a multi-line message, anything else
+            // is unstructured output such as early JVM warnings
+            None if is_stack_line(line) || line.starts_with([' ', '\t']) => {
+                Parsed::Continuation(line.to_string())
+            }
+            None => Parsed::Event(Event::plain(line)),
+        })
+    }
+
+    /// Whatever is left of an unfinished XML event
+    pub fn finish(&mut self) -> Option<Parsed> {
+        self.xml.take().map(Parsed::Continuation)
+    }
+}
+
+/// Parses a whole log
+pub fn parse(text: &str, parser: &mut Parser) -> Vec<Event> {
+    let mut events = Vec::new();
+    for line in text.lines() {
+        if let Some(parsed) = parser.push(line) {
+            append(&mut events, parsed);
+        }
+    }
+    if let Some(parsed) = parser.finish() {
+        append(&mut events, parsed);
+    }
+    events
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#10;", "\n")
+        .replace("&#13;", "")
+        .replace("&amp;", "&")
+}
+
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let start = tag.find(&format!(" {}=\"", name))? + name.len() + 3;
+    let end = tag[start..].find('"')? + start;
+    Some(unescape(&tag[start..end]))
+}
+
+/// Text of `<log4j:name>`, with or without CDATA
+fn element(xml: &str, name: &str) -> Option<String> {
+    let open = format!("<log4j:{}>", name);
+    let close = format!("</log4j:{}>", name);
+    let start = xml.find(&open)? + open.len();
+    let end = xml[start..].find(&close)? + start;
+    let inner = xml[start..end].trim();
+    Some(match inner.strip_prefix("<![CDATA[").and_then(|rest| rest.strip_suffix("]]>")) {
+        Some(data) => data.to_string(),
+        None => unescape(inner),
+    })
+}
+
+fn parse_xml(xml: &str) -> Option<Event> {
+    let tag_end = xml.find('>')?;
+    let tag = &xml[..tag_end];
+    let time = attribute(tag, "timestamp")
+        .and_then(|millis| millis.parse::<i64>().ok())
+        .and_then(DateTime::from_timestamp_millis)
+        .map(|time| time.with_timezone(&Local).naive_local());
+
+    Some(Event {
+        time,
+        level: attribute(tag, "level").and_then(|level| Level::parse(&level)).unwrap_or(Level::Info),
+        thread: attribute(tag, "thread").unwrap_or_default(),
+        logger: attribute(tag, "logger").unwrap_or_default(),
+        message: element(xml, "Message").unwrap_or_default(),
+        throwable: element(xml, "Throwable").filter(|throwable| !throwable.trim().is_empty()),
+    })
+}
+
+/// Splits `[group] rest` into the group and the rest
+fn bracket(text: &str) -> Option<(&str, &str)> {
+    let inner = text.strip_prefix('[')?;
+    let end = inner.find(']')?;
+    Some((&inner[..end], &inner[end + 1..]))
+}
+
+/// The `HH:MM:SS` part of a time group like `12:00:00` or
+/// `01Jan2024 12:00:00.123`
+fn time_of_day(group: &str) -> Option<NaiveTime> {
+    group.split_whitespace().find_map(|part| {
+        let part = part.get(..8)?;
+        NaiveTime::parse_from_str(part, "%H:%M:%S").ok()
+    })
+}
+
+fn parse_plain(line: &str, date: NaiveDate) -> Option<Event> {
+    // 2013-07-01 12:00:00 [INFO] message, before 1.7
+    if let Some(time) = line.get(..19).and_then(|time| NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S").ok()) {
+        let rest = line[19..].trim_start();
+        let (level, message) = bracket(rest)?;
+        return Some(Event {
+            time: Some(time),
+            level: Level::parse(level).unwrap_or(Level::Info),
+            thread: String::new(),
+            logger: String::new(),
+            message: message.trim_start().to_string(),
+            throwable: None,
+        });
+    }
+
+    // [12:00:00] [Render thread/INFO]: message, with an optional
+    // [logger/]: (Forge) or (logger) (Fabric) after the thread
+    let (time, rest) = bracket(line)?;
+    let time = time_of_day(time)?;
+    let (source, rest) = bracket(rest.trim_start())?;
+    let (thread, level) = source.rsplit_once('/')?;
+    let level = Level::parse(level)?;
+
+    let mut rest = rest.trim_start();
+    let mut logger = String::new();
+    if let Some((name, tail)) = bracket(rest) {
+        logger = name.trim_end_matches('/').to_string();
+        rest = tail;
+    } else if let Some(inner) = rest.strip_prefix('(') {
+        if let Some((name, tail)) = inner.split_once(')') {
+            logger = name.to_string();
+            rest = tail;
+        }
+    }
+    let message = rest.strip_prefix(':').unwrap_or(rest).trim_start();
+
+    Some(Event {
+        time: Some(date.and_time(time)),
+        level,
+        thread: thread.to_string(),
+        logger,
+        message: message.to_string(),
+        throwable: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()
+    }
+
+    #[test]
+    fn xml_events_split_over_lines() {
+        let text = r#"<log4j:Event logger="net.minecraft.client.Minecraft" timestamp="1709294400000" level="WARN" thread="Render thread">
+  <log4j:Message><![CDATA[Missing <sound> & friends]]></log4j:Message>
+  <log4j:Throwable><![CDATA[java.io.IOException: gone
+	at a.B.c(B.java:1)
+]]></log4j:Throwable>
+</log4j:Event>
+<log4j:Event logger="Main" timestamp="1709294401000" level="INFO" thread="main"><log4j:Message>a &lt;b&gt;</log4j:Message></log4j:Event>"#;
+        let mut parser = Parser::with_date(date());
+        let lines: Vec<_> = text.lines().map(|line| parser.push(line)).collect();
+        assert!(lines[..5].iter().all(Option::is_none), "an event is only emitted once it ends");
+
+        let events = parse(text, &mut Parser::with_date(date()));
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].level, Level::Warn);
+        assert_eq!(events[0].thread, "Render thread");
+        assert_eq!(events[0].logger, "net.minecraft.client.Minecraft");
+        assert_eq!(events[0].message, "Missing <sound> & friends");
+        assert_eq!(
+            events[0].throwable.as_deref().map(str::trim_end),
+            Some("java.io.IOException: gone\n\tat a.B.c(B.java:1)")
+        );
+        assert!(events[0].time.is_some());
+        assert_eq!(events[1].message, "a <b>");
+        assert_eq!(events[1].throwable, None);
+    }
+
+    #[test]
+    fn unfinished_xml_event_is_kept() {
+        let mut parser = Parser::default();
+        assert_eq!(parser.push(r#"<log4j:Event logger="Main" level="INFO" thread="main">"#), None);
+        assert_eq!(parser.push("  <log4j:Message>cut"), None);
+        assert!(matches!(parser.finish(), Some(Parsed::Continuation(rest)) if rest.contains("cut")));
+        assert_eq!(parser.finish(), None);
+    }
+
+    #[test]
+    fn parse_xml_without_message() {
+        let event = parse_xml(r#"<log4j:Event logger="L" level="BOGUS" thread="t"></log4j:Event>"#).unwrap();
+        assert_eq!(event.level, Level::Info);
+        assert_eq!(event.message, "");
+        assert_eq!(event.time, None);
+        assert_eq!(parse_xml("no tag end"), None);
+    }
+
+    #[test]
+    fn plain_formats() {
+        let vanilla = parse_plain("[12:00:00] [Render thread/INFO]: Setting user: Steve", date()).unwrap();
+        assert_eq!(vanilla.time, Some(date().and_hms_opt(12, 0, 0).unwrap()));
+        assert_eq!(vanilla.thread, "Render thread");
+        assert_eq!(vanilla.logger, "");
+        assert_eq!(vanilla.message, "Setting user: Steve");
+
+        let forge = parse_plain("[01Mar2024 12:00:01.123] [main/WARN] [net.minecraftforge.Foo/]: hi", date()).unwrap();
+        assert_eq!(forge.level, Level::Warn);
+        assert_eq!(forge.logger, "net.minecraftforge.Foo");
+        assert_eq!(forge.message, "hi");
+
+        let fabric = parse_plain("[12:00:02] [main/ERROR] (FabricLoader) oops", date()).unwrap();
+        assert_eq!(fabric.level, Level::Error);
+        assert_eq!(fabric.logger, "FabricLoader");
+        assert_eq!(fabric.message, "oops");
+
+        let legacy = parse_plain("2013-07-01 12:00:00 [SEVERE] old", date()).unwrap();
+        assert_eq!(legacy.time, NaiveDate::from_ymd_opt(2013, 7, 1).unwrap().and_hms_opt(12, 0, 0));
+        assert_eq!(legacy.message, "old");
+
+        assert_eq!(parse_plain("[12:00:00] no thread", date()), None);
+        assert_eq!(parse_plain("[12:00:00] [main/LOUD]: unknown level", date()), None);
+        assert_eq!(parse_plain("OpenJDK 64-Bit Server VM warning: Options", date()), None);
+    }
+
+    #[test]
+    fn plain_fallback_lines() {
+        let text = "OpenJDK 64-Bit Server VM warning: Sharing is only supported for boot loader classes\n\
+                    \n\
+                    [12:00:00] [main/INFO]: Loading\n\
+                    \tcontinued message\n\
+                    random output";
+        let events = parse(text, &mut Parser::with_date(date()));
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].time, None);
+        assert_eq!(events[0].level, Level::Info);
+        assert!(events[0].message.starts_with("OpenJDK"));
+        assert_eq!(events[1].message, "Loading\n\tcontinued message");
+        assert_eq!(events[1].throwable, None);
+        assert_eq!(events[2].message, "random output");
+        assert_eq!(events[2].time, None);
+    }
+
+    #[test]
+    fn stack_traces_join_the_previous_event() {
+        let text = "[12:00:00] [Render thread/ERROR]: Unreported exception thrown!\n\
+                    java.lang.NullPointerException: Cannot invoke \"Object.toString()\"\n\
+                    \tat net.minecraft.client.Minecraft.run(Minecraft.java:100)\n\
+                    Caused by: java.lang.IllegalStateException: boom\n\
+                    \t... 12 more\n\
+                    [12:00:01] [Render thread/INFO]: Stopping!";
+        let events = parse(text, &mut Parser::with_date(date()));
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].message, "Unreported exception thrown!");
+        let throwable = events[0].throwable.as_deref().unwrap();
+        assert_eq!(throwable.lines().count(), 4);
+        assert!(throwable.starts_with("java.lang.NullPointerException"));
+        assert!(throwable.ends_with("... 12 more"));
+        assert_eq!(events[1].message, "Stopping!");
+
+        // a trace before any event still shows up
+        let orphan = parse("\tat a.B.c(B.java:1)", &mut Parser::default());
+        assert_eq!(orphan.len(), 1);
+        assert_eq!(orphan[0].message, "\tat a.B.c(B.java:1)");
+    }
+}
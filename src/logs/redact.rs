@@ -0,0 +1,263 @@
+use std::net::Ipv6Addr;
+
+use regex::{Captures, Regex};
+
+/// What redacted values are replaced with
+const HIDDEN: &str = "<redacted>";
+
+/// Strips what shouldn't end up in a bug report from log lines: player
+/// names, session tokens, UUIDs, IP addresses and home directories. Player
+/// names are learned from lines like `Setting user: Steve`, so feed it the
+/// log in order.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    names: Vec<Regex>,
+    home: Option<String>,
+    learn: Vec<Regex>,
+    /// Replaced by their first group followed by [`HIDDEN`]
+    secrets: Vec<Regex>,
+    uuid: Regex,
+    ipv4: Regex,
+    ipv6: Regex,
+    user_dir: Regex,
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new(&[])
+    }
+}
+
+impl Redactor {
+    /// `names` are redacted from the start, along with the system user
+    pub fn new(names: &[String]) -> Redactor {
+        let mut redactor = Redactor {
+            names: Vec::new(),
+            home: dirs_next::home_dir()
+                .map(|home| home.to_string_lossy().to_string())
+                .filter(|home| home.len() > 1),
+            learn: [
+                r"Setting user: (\S+)",
+                r"--username[= ](\S+)",
+                r"Username: (\S+)",
+            ]
+            .iter()
+            .map(|pattern| Regex::new(pattern).expect("valid pattern"))
+            .collect(),
+            secrets: [
+                r"(--(?:accessToken|session|xuid|clientId|uuid)[= ])\S+",
+                r#"(?i)((?:access_?token|session_?id|client_?token|bearer)["']?\s*[:= ]\s*["']?)[\w.+/=-]{8,}"#,
+                r"()eyJ[\w-]+\.[\w-]+\.[\w-]*",
+                r"()token:[\w-]+:[\w-]+",
+            ]
+            .iter()
+            .map(|pattern| Regex::new(pattern).expect("valid pattern"))
+            .collect(),
+            uuid: Regex::new(r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b")
+                .expect("valid pattern"),
+            ipv4: Regex::new(r"\b(\d{1,3})\.(\d{1,3})\.(\d{1,3})\.(\d{1,3})\b").expect("valid pattern"),
+            // candidates only, `::` compression makes a precise pattern unreadable
+            ipv6: Regex::new(concat!(
+                r"[0-9a-fA-F]{0,4}(?:(?::[0-9a-fA-F]{0,4}){1,6}:\d{1,3}(?:\.\d{1,3}){3}",
+                r"|(?::[0-9a-fA-F]{0,4}){2,7})(?:%[\w.-]+)?"
+            ))
+            .expect("valid pattern"),
+            user_dir: Regex::new(r"(/home/|/Users/|[A-Za-z]:\\Users\\|[A-Za-z]:/Users/)[^/\\\s]+")
+                .expect("valid pattern"),
+        };
+        for name in names {
+            redactor.add_name(name);
+        }
+        for variable in ["USER", "USERNAME"] {
+            if let Ok(name) = std::env::var(variable) {
+                redactor.add_name(&name);
+            }
+        }
+        redactor
+    }
+
+    fn add_name(&mut self, name: &str) {
+        // short names would eat ordinary words
+        if name.len() < 3 || name == HIDDEN {
+            return;
+        }
+        let pattern = format!(r"\b{}\b", regex::escape(name));
+        if self.names.iter().all(|existing| existing.as_str() != pattern) {
+            if let Ok(regex) = Regex::new(&pattern) {
+                self.names.push(regex);
+            }
+        }
+    }
+
+    /// Picks up player names from a line without redacting it, for lines a
+    /// filter leaves out of the output
+    pub fn learn(&mut self, text: &str) {
+        let learned: Vec<String> = self
+            .learn
+            .iter()
+            .flat_map(|pattern| pattern.captures_iter(text))
+            .filter_map(|captures| captures.get(1).map(|name| name.as_str().to_string()))
+            .collect();
+        for name in learned {
+            self.add_name(&name);
+        }
+    }
+
+    pub fn redact(&mut self, text: &str) -> String {
+        self.learn(text);
+        let mut text = text.to_string();
+        for secret in &self.secrets {
+            text = secret.replace_all(&text, format!("${{1}}{}", HIDDEN)).to_string();
+        }
+        text = self.uuid.replace_all(&text, "<uuid>").to_string();
+        let source = text.clone();
+        text = self
+            .ipv6
+            .replace_all(&source, |captures: &Captures| {
+                let whole = captures.get(0).expect("whole match");
+                if is_ipv6(&source, whole.start(), whole.end()) {
+                    "<ip>".to_string()
+                } else {
+                    whole.as_str().to_string()
+                }
+            })
+            .to_string();
+        let source = text.clone();
+        text = self
+            .ipv4
+            .replace_all(&source, |captures: &Captures| {
+                let whole = captures.get(0).expect("whole match");
+                if is_address(&source, whole.start(), whole.end(), captures) {
+                    "<ip>".to_string()
+                } else {
+                    whole.as_str().to_string()
+                }
+            })
+            .to_string();
+        if let Some(home) = &self.home {
+            text = text.replace(home.as_str(), "~");
+        }
+        text = self.user_dir.replace_all(&text, format!("${{1}}{}", HIDDEN)).to_string();
+        for name in &self.names {
+            text = name.replace_all(&text, "<player>").to_string();
+        }
+        text
+    }
+}
+
+/// Whether four dotted numbers are an IP address worth hiding rather than a
+/// version number or a loopback address
+fn is_address(text: &str, start: usize, end: usize, captures: &Captures) -> bool {
+    let octets: Vec<u32> = (1..=4)
+        .filter_map(|i| captures.get(i).and_then(|octet| octet.as_str().parse().ok()))
+        .collect();
+    if octets.len() != 4 || octets.iter().any(|octet| *octet > 255) {
+        return false;
+    }
+    // 0.x.x.x isn't routable but is a common version number
+    if octets[0] == 0 || octets[0] == 127 {
+        return false;
+    }
+    let before = text[..start].chars().next_back();
+    let mut after = text[end..].chars();
+    let next = after.next();
+    let dotted_after = next == Some('.') && after.next().is_some_and(|c| c.is_ascii_digit());
+    !matches!(before, Some('.' | '-' | '+')) && !matches!(next, Some('-' | '+')) && !dotted_after
+}
+
+/// Whether a candidate is a whole IPv6 address, with an optional zone like
+/// `%eth0`, rather than a timestamp or part of a `Class::method`
+fn is_ipv6(text: &str, start: usize, end: usize) -> bool {
+    let word = |c: char| c.is_alphanumeric() || matches!(c, '_' | ':' | '%');
+    let before = text[..start].chars().next_back();
+    let mut after = text[end..].chars();
+    let next = after.next();
+    let dotted_after = next == Some('.') && after.next().is_some_and(|c| c.is_alphanumeric());
+    if before.is_some_and(|c| word(c) || c == '.') || next.is_some_and(word) || dotted_after {
+        return false;
+    }
+    let candidate = &text[start..end];
+    let address = candidate.split_once('%').map_or(candidate, |(address, _)| address);
+    address
+        .parse::<Ipv6Addr>()
+        .is_ok_and(|address| !address.is_loopback() && !address.is_unspecified())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redactor() -> Redactor {
+        let mut redactor = Redactor::new(&[]);
+        // keep the machine's own user and home out of the expectations
+        redactor.names.clear();
+        redactor.home = None;
+        redactor
+    }
+
+    #[test]
+    fn learns_player_names() {
+        let mut redactor = redactor();
+        assert_eq!(redactor.redact("Setting user: Notch"), "Setting user: <player>");
+        assert_eq!(redactor.redact("<Notch> hello, Notchy"), "<<player>> hello, Notchy");
+    }
+
+    #[test]
+    fn names_learned_from_filtered_lines() {
+        let mut redactor = redactor();
+        let lines = [
+            "[12:00:00] [Render thread/INFO]: Setting user: Notch",
+            "[12:05:00] [Server thread/WARN]: Notch moved too quickly!",
+        ];
+        // only the warning passes the filter, the name comes from the line before
+        let shown: Vec<String> = lines
+            .iter()
+            .filter_map(|line| {
+                redactor.learn(line);
+                line.contains("WARN").then(|| redactor.redact(line))
+            })
+            .collect();
+        assert_eq!(shown, ["[12:05:00] [Server thread/WARN]: <player> moved too quickly!"]);
+    }
+
+    #[test]
+    fn hides_tokens_and_uuids() {
+        let mut redactor = redactor();
+        assert_eq!(
+            redactor.redact("--accessToken eyJhbGciOi.eyJzdWIi.c2lnbmF0dXJl --version 1.20.1"),
+            "--accessToken <redacted> --version 1.20.1"
+        );
+        assert_eq!(
+            redactor.redact("UUID of player is 069a79f4-44e9-4726-a5be-fca90e38aaf5"),
+            "UUID of player is <uuid>"
+        );
+    }
+
+    #[test]
+    fn ipv4_but_not_versions() {
+        let mut redactor = redactor();
+        assert_eq!(redactor.redact("Connecting to 192.168.1.20, 25565"), "Connecting to <ip>, 25565");
+        assert_eq!(redactor.redact("Fabric Loader 0.14.21.1"), "Fabric Loader 0.14.21.1");
+        assert_eq!(redactor.redact("lwjgl 3.3.1.7-SNAPSHOT"), "lwjgl 3.3.1.7-SNAPSHOT");
+        assert_eq!(redactor.redact("bound to 127.0.0.1"), "bound to 127.0.0.1");
+    }
+
+    #[test]
+    fn ipv6_forms() {
+        let mut redactor = redactor();
+        assert_eq!(redactor.redact("from 2001:0db8:0000:0000:0000:ff00:0042:8329"), "from <ip>");
+        assert_eq!(redactor.redact("from 2001:db8::ff00:42:8329."), "from <ip>.");
+        assert_eq!(redactor.redact("from [fe80::1%eth0]:25565"), "from [<ip>]:25565");
+        assert_eq!(redactor.redact("via ::ffff:203.0.113.7"), "via <ip>");
+        assert_eq!(redactor.redact("bound to ::1 and ::"), "bound to ::1 and ::");
+        assert_eq!(redactor.redact("[12:34:56] [Server thread/INFO]"), "[12:34:56] [Server thread/INFO]");
+        assert_eq!(redactor.redact("at Foo::bar and dead::beef"), "at Foo::bar and <ip>");
+    }
+
+    #[test]
+    fn home_directories() {
+        let mut redactor = redactor();
+        assert_eq!(redactor.redact("/home/alex/.minecraft"), "/home/<redacted>/.minecraft");
+        assert_eq!(redactor.redact(r"C:\Users\alex\AppData"), r"C:\Users\<redacted>\AppData");
+    }
+}
@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{Result, WrapErr};
+use regex::{Captures, Regex};
+use serde::Deserialize;
+
+use crate::{config::get_config_path, error};
+
+use super::crash::Report;
+
+/// Rules shipped with mcl
+const BUILTIN: &str = include_str!("../../assets/crash-rules.toml");
+
+/// Longest matched line quoted as evidence
+const MAX_EVIDENCE: usize = 200;
+
+/// Extra rules, one or more `*.toml` files in the format of the built-in
+/// `assets/crash-rules.toml`
+pub fn user_rules_dir() -> PathBuf {
+    get_config_path().join("crash-rules")
+}
+
+#[derive(Debug, Deserialize)]
+struct RuleFile {
+    #[serde(default)]
+    rules: Vec<RuleData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RuleData {
+    id: String,
+    title: String,
+    patterns: Vec<String>,
+    /// Report kinds the rule applies to, all when empty
+    #[serde(default)]
+    kinds: Vec<String>,
+    /// Values of capture groups that didn't take part in the match
+    #[serde(default)]
+    defaults: HashMap<String, String>,
+    advice: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub id: String,
+    pub title: String,
+    patterns: Vec<Regex>,
+    kinds: Vec<String>,
+    defaults: HashMap<String, String>,
+    advice: String,
+}
+
+/// A rule that matched a report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnosis {
+    pub id: String,
+    pub title: String,
+    pub advice: String,
+    /// Line of the report the rule matched
+    pub evidence: String,
+}
+
+impl Rule {
+    fn compile(data: RuleData) -> Result<Rule> {
+        let patterns = data
+            .patterns
+            .iter()
+            .map(|pattern| Regex::new(pattern).wrap_err_with(|| format!("rule '{}'", data.id)))
+            .collect::<Result<_>>()?;
+        Ok(Rule {
+            id: data.id,
+            title: data.title,
+            patterns,
+            kinds: data.kinds,
+            defaults: data.defaults,
+            advice: data.advice,
+        })
+    }
+
+    pub fn check(&self, report: &Report) -> Option<Diagnosis> {
+        if !self.kinds.is_empty() && !self.kinds.iter().any(|kind| kind == report.kind.name()) {
+            return None;
+        }
+        let captures = self.patterns.iter().find_map(|pattern| pattern.captures(&report.text))?;
+        let matched = captures.get(0)?;
+        Some(Diagnosis {
+            id: self.id.clone(),
+            title: self.title.clone(),
+            advice: self.advice(&captures),
+            evidence: evidence(&report.text, matched.start(), matched.end()),
+        })
+    }
+
+    /// Fills `{group}` placeholders from the match
+    fn advice(&self, captures: &Captures) -> String {
+        let placeholder = Regex::new(r"\{(\w+)\}").expect("valid placeholder pattern");
+        placeholder
+            .replace_all(&self.advice, |placeholder: &Captures| {
+                let name = &placeholder[1];
+                captures
+                    .name(name)
+                    .map(|value| value.as_str().trim().to_string())
+                    .or_else(|| self.defaults.get(name).cloned())
+                    .unwrap_or_else(|| "?".to_string())
+            })
+            .to_string()
+    }
+}
+
+/// The lines a match spans, shortened to fit a line of output
+fn evidence(text: &str, start: usize, end: usize) -> String {
+    let from = text[..start].rfind('\n').map_or(0, |i| i + 1);
+    let to = text[end..].find('\n').map_or(text.len(), |i| end + i);
+    let line = text[from..to].split_whitespace().collect::<Vec<_>>().join(" ");
+    match line.char_indices().nth(MAX_EVIDENCE) {
+        Some((i, _)) => format!("{}...", &line[..i]),
+        None => line,
+    }
+}
+
+fn parse(text: &str, source: &str) -> Vec<Rule> {
+    let file: RuleFile = match toml::from_str(text) {
+        Ok(file) => file,
+        Err(err) => {
+            error!("Skipping crash rules in {}: {}", source, err);
+            return Vec::new();
+        }
+    };
+    file.rules
+        .into_iter()
+        .filter_map(|data| match Rule::compile(data) {
+            Ok(rule) => Some(rule),
+            Err(err) => {
+                error!("Skipping crash rule in {}: {:#}", source, err);
+                None
+            }
+        })
+        .collect()
+}
+
+fn read_dir(dir: &Path) -> Vec<Rule> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "toml"))
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .flat_map(|path| match fs::read_to_string(path) {
+            Ok(text) => parse(&text, &path.display().to_string()),
+            Err(err) => {
+                error!("Failed to read {}: {}", path.display(), err);
+                Vec::new()
+            }
+        })
+        .collect()
+}
+
+/// Built-in rules followed by the user's, which replace built-in rules with
+/// the same id
+pub fn load() -> Vec<Rule> {
+    let mut rules = parse(BUILTIN, "built-in rules");
+    for rule in read_dir(&user_rules_dir()) {
+        match rules.iter_mut().find(|existing| existing.id == rule.id) {
+            Some(existing) => *existing = rule,
+            None => rules.push(rule),
+        }
+    }
+    rules
+}
+
+/// Every rule that matches the report, in rule order
+pub fn diagnose(report: &Report, rules: &[Rule]) -> Vec<Diagnosis> {
+    rules.iter().filter_map(|rule| rule.check(report)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logs::crash::{self, Kind};
+
+    fn diagnose_text(kind: Kind, text: &str) -> Vec<Diagnosis> {
+        let report = crash::parse(Path::new("/nonexistent/report.txt"), kind, text.to_string());
+        diagnose(&report, &parse(BUILTIN, "built-in rules"))
+    }
+
+    fn ids(diagnoses: &[Diagnosis]) -> Vec<&str> {
+        diagnoses.iter().map(|diagnosis| diagnosis.id.as_str()).collect()
+    }
+
+    #[test]
+    fn builtin_rules_compile() {
+        let rules = parse(BUILTIN, "built-in rules");
+        let raw: RuleFile = toml::from_str(BUILTIN).unwrap();
+        assert_eq!(rules.len(), raw.rules.len());
+    }
+
+    #[test]
+    fn out_of_memory_crash_report() {
+        let text = "---- Minecraft Crash Report ----\n\
+                    // Don't be sad, have a hug! <3\n\
+                    \n\
+                    Time: 2024-03-01 12:00:00\n\
+                    Description: Unexpected error\n\
+                    \n\
+                    java.lang.OutOfMemoryError: Java heap space\n\
+                    \tat java.base/java.util.Arrays.copyOf(Arrays.java:3537)\n\
+                    \tat net.minecraft.client.Minecraft.run(Minecraft.java:100)\n\
+                    \n\
+                    -- System Details --\n\
+                    Details:\n\
+                    \tMinecraft Version: 1.20.1\n";
+        let diagnoses = diagnose_text(Kind::Game, text);
+        assert_eq!(ids(&diagnoses), ["out-of-memory"]);
+        assert!(diagnoses[0].advice.contains("(Java heap space)"));
+        assert_eq!(diagnoses[0].evidence, "java.lang.OutOfMemoryError: Java heap space");
+    }
+
+    #[test]
+    fn native_memory_only_in_jvm_logs() {
+        let text = "#\n\
+                    # There is insufficient memory for the Java Runtime Environment to continue.\n\
+                    # Native memory allocation (mmap) failed to map 65536 bytes for committing reserved memory.\n";
+        assert_eq!(ids(&diagnose_text(Kind::Jvm, text)), ["native-memory"]);
+        assert!(diagnose_text(Kind::Output, text).is_empty());
+    }
+
+    #[test]
+    fn fabric_missing_dependency_in_latest_log() {
+        let text = "[12:00:00] [main/INFO]: Loading Minecraft 1.20.1 with Fabric Loader 0.14.22\n\
+                    [12:00:01] [main/ERROR]: Incompatible mods found!\n\
+                    net.fabricmc.loader.impl.FormattedException: Some of your mods are incompatible with the game or each other!\n\
+                    A potential solution has been determined:\n\
+                    \t - Install fabric-api, any version.\n\
+                    Unmet dependency listing:\n\
+                    \t - Mod 'Sodium Extra' (sodium-extra) 0.5.1 requires any version of 'Sodium' (sodium), which is missing!\n";
+        let diagnoses = diagnose_text(Kind::Output, text);
+        assert_eq!(ids(&diagnoses), ["missing-dependency"]);
+        assert!(diagnoses[0].advice.starts_with("Sodium Extra needs Sodium,"), "{}", diagnoses[0].advice);
+    }
+
+    #[test]
+    fn forge_missing_dependency() {
+        let text = "[12:00:00] [main/ERROR] [net.minecraftforge.fml.loading.ModSorter/LOADING]: Missing or unsupported mandatory dependencies:\n\
+                    \tMod ID: 'geckolib', Requested by: 'alexsmobs', Expected range: '[4.2,)', Actual version: '[MISSING]'\n";
+        let diagnoses = diagnose_text(Kind::Output, text);
+        assert_eq!(ids(&diagnoses), ["missing-dependency"]);
+        assert!(diagnoses[0].advice.starts_with("alexsmobs needs geckolib,"));
+    }
+
+    #[test]
+    fn java_version_mismatch() {
+        let text = "Error: LinkageError occurred while loading main class net.minecraft.client.main.Main\n\
+                    \tjava.lang.UnsupportedClassVersionError: net/minecraft/client/main/Main has been compiled by a more \
+                    recent version of the Java Runtime (class file version 65.0), this version of the Java Runtime only \
+                    recognizes class file versions up to 52.0\n";
+        let diagnoses = diagnose_text(Kind::Output, text);
+        assert_eq!(ids(&diagnoses), ["wrong-java"]);
+        assert!(diagnoses[0].advice.contains("class file 65, this Java reads up to 52"));
+        assert!(diagnoses[0].evidence.len() <= MAX_EVIDENCE + 3);
+
+        let text = "[12:00:00] [main/ERROR]: Minecraft 1.20.5 requires Java 21 but Java 17 was found\n";
+        let diagnoses = diagnose_text(Kind::Output, text);
+        assert_eq!(ids(&diagnoses), ["java-required"]);
+        assert!(diagnoses[0].advice.contains("needs Java 21"));
+    }
+
+    #[test]
+    fn clean_log_has_no_diagnosis() {
+        let text = "[12:00:00] [Render thread/INFO]: Setting user: Steve\n\
+                    [12:00:05] [Render thread/INFO]: Stopping!\n";
+        assert!(diagnose_text(Kind::Output, text).is_empty());
+    }
+}
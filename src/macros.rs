@@ -1,29 +1,92 @@
+//! Logging macros. Structured fields go before the message, separated by a
+//! semicolon: `info!(instance = id, duration_ms = ms; "Started {}", name)`.
+
 #[macro_export]
-macro_rules! debug {
+macro_rules! error {
+    ($($key:ident = $value:expr),+ ; $($rest:tt)+) => {
+        $crate::logger::log(
+            $crate::logger::Level::Error,
+            module_path!(),
+            &format!($($rest)+),
+            vec![$((stringify!($key), $crate::logger::field(&$value))),+],
+        );
+    };
+    ($msg:expr) => {
+        $crate::logger::log($crate::logger::Level::Error, module_path!(), $msg, Vec::new());
+    };
+    ($fmt:expr, $($arg:tt)*) => {
+        $crate::logger::log($crate::logger::Level::Error, module_path!(), &format!($fmt, $($arg)*), Vec::new());
+    };
+}
+
+#[macro_export]
+macro_rules! warn {
+    ($($key:ident = $value:expr),+ ; $($rest:tt)+) => {
+        $crate::logger::log(
+            $crate::logger::Level::Warn,
+            module_path!(),
+            &format!($($rest)+),
+            vec![$((stringify!($key), $crate::logger::field(&$value))),+],
+        );
+    };
     ($msg:expr) => {
-        $crate::logger::get_logger().debug($msg);
+        $crate::logger::log($crate::logger::Level::Warn, module_path!(), $msg, Vec::new());
     };
     ($fmt:expr, $($arg:tt)*) => {
-        $crate::logger::get_logger().debug(&format!($fmt, $($arg)*));
+        $crate::logger::log($crate::logger::Level::Warn, module_path!(), &format!($fmt, $($arg)*), Vec::new());
     };
 }
 
 #[macro_export]
 macro_rules! info {
+    ($($key:ident = $value:expr),+ ; $($rest:tt)+) => {
+        $crate::logger::log(
+            $crate::logger::Level::Info,
+            module_path!(),
+            &format!($($rest)+),
+            vec![$((stringify!($key), $crate::logger::field(&$value))),+],
+        );
+    };
     ($msg:expr) => {
-        $crate::logger::get_logger().info($msg);
+        $crate::logger::log($crate::logger::Level::Info, module_path!(), $msg, Vec::new());
     };
     ($fmt:expr, $($arg:tt)*) => {
-        $crate::logger::get_logger().info(&format!($fmt, $($arg)*));
+        $crate::logger::log($crate::logger::Level::Info, module_path!(), &format!($fmt, $($arg)*), Vec::new());
     };
 }
 
 #[macro_export]
-macro_rules! error {
+macro_rules! debug {
+    ($($key:ident = $value:expr),+ ; $($rest:tt)+) => {
+        $crate::logger::log(
+            $crate::logger::Level::Debug,
+            module_path!(),
+            &format!($($rest)+),
+            vec![$((stringify!($key), $crate::logger::field(&$value))),+],
+        );
+    };
+    ($msg:expr) => {
+        $crate::logger::log($crate::logger::Level::Debug, module_path!(), $msg, Vec::new());
+    };
+    ($fmt:expr, $($arg:tt)*) => {
+        $crate::logger::log($crate::logger::Level::Debug, module_path!(), &format!($fmt, $($arg)*), Vec::new());
+    };
+}
+
+#[macro_export]
+macro_rules! trace {
+    ($($key:ident = $value:expr),+ ; $($rest:tt)+) => {
+        $crate::logger::log(
+            $crate::logger::Level::Trace,
+            module_path!(),
+            &format!($($rest)+),
+            vec![$((stringify!($key), $crate::logger::field(&$value))),+],
+        );
+    };
     ($msg:expr) => {
-        $crate::logger::get_logger().error($msg);
+        $crate::logger::log($crate::logger::Level::Trace, module_path!(), $msg, Vec::new());
     };
     ($fmt:expr, $($arg:tt)*) => {
-        $crate::logger::get_logger().error(&format!($fmt, $($arg)*));
+        $crate::logger::log($crate::logger::Level::Trace, module_path!(), &format!($fmt, $($arg)*), Vec::new());
     };
 }
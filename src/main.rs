@@ -1,13 +1,20 @@
-use config::SETTINGS;
-use logger::Logger;
-
 mod cli;
 pub mod config;
+pub mod instance;
+pub mod launch;
 pub mod logger;
+pub mod logs;
 pub mod macros;
+pub mod modrinth;
+pub mod mods;
+pub mod nbt;
+pub mod net;
+pub mod packs;
+pub mod packwiz;
+pub mod servers;
 pub mod tui;
+pub mod worlds;
 
 fn main() {
-    Logger::init(SETTINGS.general.debug);
     cli::init()
 }
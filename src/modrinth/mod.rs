@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde::Deserialize;
+use serde_json::json;
+use sha1::{Digest, Sha1};
+
+use crate::{
+    debug,
+    net::{self, AGENT},
+};
+
+const API_URL: &str = "https://api.modrinth.com/v2";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Hashes {
+    pub sha1: String,
+    #[serde(default)]
+    pub sha512: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VersionFile {
+    pub hashes: Hashes,
+    pub url: String,
+    pub filename: String,
+    #[serde(default)]
+    pub primary: bool,
+    #[serde(default)]
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Version {
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub version_number: String,
+    #[serde(default)]
+    pub changelog: Option<String>,
+    pub version_type: String,
+    pub date_published: String,
+    #[serde(default)]
+    pub game_versions: Vec<String>,
+    #[serde(default)]
+    pub loaders: Vec<String>,
+    pub files: Vec<VersionFile>,
+}
+
+impl Version {
+    /// The file Modrinth marks as primary, or the first one
+    pub fn primary_file(&self) -> Option<&VersionFile> {
+        self.files
+            .iter()
+            .find(|file| file.primary)
+            .or_else(|| self.files.first())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Project {
+    pub id: String,
+    pub slug: String,
+    pub title: String,
+    #[serde(default)]
+    pub project_type: String,
+}
+
+fn get<T: for<'de> Deserialize<'de>>(url: &str) -> Result<T> {
+    debug!(url = url; "GET {}", url);
+    AGENT
+        .get(url)
+        .call()
+        .wrap_err_with(|| format!("request to {} failed", url))?
+        .into_json()
+        .wrap_err_with(|| format!("invalid response from {}", url))
+}
+
+fn post<T: for<'de> Deserialize<'de>>(url: &str, body: serde_json::Value) -> Result<T> {
+    debug!(url = url; "POST {}", url);
+    AGENT
+        .post(url)
+        .send_json(body)
+        .wrap_err_with(|| format!("request to {} failed", url))?
+        .into_json()
+        .wrap_err_with(|| format!("invalid response from {}", url))
+}
+
+fn json_list(values: &[&str]) -> String {
+    serde_json::to_string(values).unwrap_or_default()
+}
+
+/// Looks up versions by SHA-1 file hash. Unknown hashes are missing from the map.
+pub fn versions_from_hashes(hashes: &[String]) -> Result<HashMap<String, Version>> {
+    if hashes.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    post(
+        &format!("{}/version_files", API_URL),
+        json!({ "hashes": hashes, "algorithm": "sha1" }),
+    )
+}
+
+pub fn project_versions(project: &str, loaders: &[&str], game_versions: &[&str]) -> Result<Vec<Version>> {
+    let mut url = format!("{}/project/{}/version", API_URL, project);
+    let mut query = Vec::new();
+    if !loaders.is_empty() {
+        query.push(format!("loaders={}", json_list(loaders)));
+    }
+    if !game_versions.is_empty() {
+        query.push(format!("game_versions={}", json_list(game_versions)));
+    }
+    if !query.is_empty() {
+        url = format!("{}?{}", url, query.join("&"));
+    }
+
+    get(&url)
+}
+
+pub fn projects(ids: &[&str]) -> Result<Vec<Project>> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    get(&format!("{}/projects?ids={}", API_URL, json_list(ids)))
+}
+
+pub fn sha1_file(path: &Path) -> Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha1::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Downloads a version file into `dir`, verifying its SHA-1 before it is
+/// moved into place.
+pub fn download(file: &VersionFile, dir: &Path) -> Result<std::path::PathBuf> {
+    let body = net::get_bytes(&file.url)?;
+
+    let hash = format!("{:x}", Sha1::digest(&body));
+    if hash != file.hashes.sha1 {
+        return Err(eyre!(
+            "hash mismatch for {}: expected {}, got {}",
+            file.filename,
+            file.hashes.sha1,
+            hash
+        ));
+    }
+
+    fs::create_dir_all(dir)?;
+    let target = dir.join(&file.filename);
+    let partial = dir.join(format!("{}.part", file.filename));
+    fs::write(&partial, body)?;
+    fs::rename(&partial, &target)?;
+    Ok(target)
+}
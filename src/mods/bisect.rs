@@ -0,0 +1,184 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::PathBuf;
+
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{debug, instance::Instance};
+
+use super::ModInfo;
+
+/// Enabled mods before the bisection started, so an interrupted run can be undone
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Session {
+    enabled: Vec<String>,
+}
+
+fn session_path(instance: &Instance) -> PathBuf {
+    instance.path.join(".mcl").join("bisect.json")
+}
+
+pub fn in_progress(instance: &Instance) -> bool {
+    session_path(instance).exists()
+}
+
+#[derive(Debug)]
+pub enum Outcome {
+    /// The problem follows these mods
+    Found(Vec<ModInfo>),
+    /// Dependencies prevent splitting the remaining suspects any further
+    Inconclusive(Vec<ModInfo>),
+}
+
+/// One launch of the bisection, handed to the caller's test
+#[derive(Debug)]
+pub struct Step<'a> {
+    pub number: usize,
+    pub suspects: usize,
+    pub enabled: Vec<&'a ModInfo>,
+}
+
+/// `selected` plus every mod they transitively require
+pub fn closure(mods: &[ModInfo], selected: &[String]) -> BTreeSet<String> {
+    let mut providers: HashMap<&str, &ModInfo> = HashMap::new();
+    for info in mods {
+        for id in info.provided_ids() {
+            providers.entry(id).or_insert(info);
+        }
+    }
+
+    let mut result: BTreeSet<String> = BTreeSet::new();
+    let mut queue: Vec<&str> = selected.iter().map(String::as_str).collect();
+    while let Some(file_name) = queue.pop() {
+        if !result.insert(file_name.to_string()) {
+            continue;
+        }
+        let Some(meta) = mods
+            .iter()
+            .find(|info| info.file.file_name == file_name)
+            .and_then(|info| info.metadata.as_ref())
+        else {
+            continue;
+        };
+
+        for dep in meta.required() {
+            // minecraft, java and the loader itself have no provider
+            if let Some(provider) = providers.get(dep.id.as_str()) {
+                queue.push(&provider.file.file_name);
+            }
+        }
+    }
+    result
+}
+
+/// Enables exactly the mods in `enabled` out of `mods`
+fn apply(mods: &mut [ModInfo], enabled: &BTreeSet<String>) -> Result<()> {
+    for info in mods.iter_mut() {
+        info.file.set_enabled(enabled.contains(&info.file.file_name))?;
+    }
+    Ok(())
+}
+
+/// Restores the mods enabled before an interrupted bisection.
+/// Returns false if there was nothing to restore.
+pub fn restore(instance: &Instance) -> Result<bool> {
+    let path = session_path(instance);
+    let Ok(raw) = fs::read_to_string(&path) else {
+        return Ok(false);
+    };
+    let session: Session = serde_json::from_str(&raw)?;
+
+    let mut mods = super::load(instance);
+    let enabled: BTreeSet<String> = session.enabled.into_iter().collect();
+    apply(&mut mods, &enabled)?;
+    fs::remove_file(path)?;
+    Ok(true)
+}
+
+/// Halves the enabled mods until the problem is narrowed down. `test`
+/// launches the game with the given step's mods and reports whether the
+/// problem still happens. The original state is restored afterwards.
+pub fn run(instance: &Instance, test: impl FnMut(&Step) -> Result<bool>) -> Result<Outcome> {
+    if in_progress(instance) {
+        return Err(eyre!(
+            "a bisection of '{}' is already in progress, reset it first",
+            instance.name
+        ));
+    }
+
+    let mut mods = super::load(instance);
+    let candidates: Vec<String> = mods
+        .iter()
+        .filter(|info| info.file.enabled)
+        .map(|info| info.file.file_name.clone())
+        .collect();
+    if candidates.is_empty() {
+        return Err(eyre!("'{}' has no enabled mods", instance.name));
+    }
+
+    let path = session_path(instance);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let session = Session {
+        enabled: candidates.clone(),
+    };
+    fs::write(&path, serde_json::to_string_pretty(&session)?)?;
+
+    let result = bisect(&mut mods, candidates, test);
+    restore(instance)?;
+    result
+}
+
+fn bisect(
+    mods: &mut [ModInfo],
+    mut suspects: Vec<String>,
+    mut test: impl FnMut(&Step) -> Result<bool>,
+) -> Result<Outcome> {
+    let mut number = 0;
+    let pick = |mods: &[ModInfo], names: &[String]| -> Vec<ModInfo> {
+        mods.iter()
+            .filter(|info| names.contains(&info.file.file_name))
+            .cloned()
+            .collect()
+    };
+
+    // only mods enabled at the start may be pulled in as dependencies
+    let pool = pick(mods, &suspects);
+
+    while suspects.len() > 1 {
+        let (first, second) = suspects.split_at(suspects.len() / 2);
+
+        // a half is only useful if its dependency closure leaves something out
+        let enabled = [first, second]
+            .into_iter()
+            .map(|half| closure(&pool, half))
+            .find(|set| suspects.iter().any(|name| !set.contains(name)));
+        let Some(enabled) = enabled else {
+            return Ok(Outcome::Inconclusive(pick(mods, &suspects)));
+        };
+
+        apply(mods, &enabled)?;
+        number += 1;
+        let step = Step {
+            number,
+            suspects: suspects.len(),
+            enabled: mods
+                .iter()
+                .filter(|info| enabled.contains(&info.file.file_name))
+                .collect(),
+        };
+
+        let persists = test(&step)?;
+        debug!(
+            "Bisect step {}: {} mods enabled, problem {}",
+            number,
+            enabled.len(),
+            if persists { "persists" } else { "gone" }
+        );
+        suspects.retain(|name| enabled.contains(name) == persists);
+    }
+
+    Ok(Outcome::Found(pick(mods, &suspects)))
+}
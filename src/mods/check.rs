@@ -0,0 +1,201 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::instance::{Instance, Loader};
+
+use super::{
+    metadata::{Dependency, DependencyKind, Source},
+    version, ModInfo,
+};
+
+/// Dependencies on the game, Java or the loader itself, which are not mods
+const BUILTIN: &[&str] = &[
+    "java",
+    "fabricloader",
+    "fabric-loader",
+    "quilt_loader",
+    "forge",
+    "neoforge",
+    "javafml",
+    "lowcodefml",
+    "mcp",
+    "fml",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub severity: Severity,
+    /// File names of the jars involved
+    pub files: Vec<String>,
+    pub message: String,
+}
+
+impl Problem {
+    fn error(files: Vec<String>, message: String) -> Self {
+        Problem {
+            severity: Severity::Error,
+            files,
+            message,
+        }
+    }
+
+    fn warning(files: Vec<String>, message: String) -> Self {
+        Problem {
+            severity: Severity::Warning,
+            files,
+            message,
+        }
+    }
+
+    pub fn involves(&self, file_name: &str) -> bool {
+        self.files.iter().any(|file| file == file_name)
+    }
+}
+
+fn describe(dep: &Dependency) -> String {
+    if dep.versions.is_empty() {
+        dep.id.clone()
+    } else {
+        format!("{} {}", dep.id, dep.versions.join(" || "))
+    }
+}
+
+/// Whether `installed` satisfies the dependency's version requirements
+fn satisfies(dep: &Dependency, source: Source, installed: &str) -> bool {
+    if dep.versions.is_empty() || !version::comparable(installed) {
+        return true;
+    }
+
+    dep.versions.iter().any(|range| match source {
+        Source::Fabric | Source::Quilt => version::matches_predicate(range, installed),
+        Source::Forge | Source::NeoForge | Source::Legacy => version::matches_maven(range, installed),
+    })
+}
+
+/// Checks the enabled mods of an instance against each other
+pub fn check(instance: &Instance, mods: &[ModInfo]) -> Vec<Problem> {
+    let enabled: Vec<&ModInfo> = mods.iter().filter(|info| info.file.enabled).collect();
+    let mut problems = Vec::new();
+
+    if instance.loader == Loader::Vanilla && !enabled.is_empty() {
+        problems.push(Problem::error(
+            Vec::new(),
+            format!("{} mods are enabled but the instance has no mod loader", enabled.len()),
+        ));
+    }
+
+    let mut by_id: BTreeMap<&str, Vec<&ModInfo>> = BTreeMap::new();
+    let mut providers: HashMap<&str, &ModInfo> = HashMap::new();
+    for info in &enabled {
+        let Some(meta) = &info.metadata else {
+            problems.push(Problem::warning(
+                vec![info.file.file_name.clone()],
+                format!("{} has no mod metadata and cannot be checked", info.file.file_name),
+            ));
+            continue;
+        };
+
+        if instance.loader != Loader::Vanilla && !meta.source.loadable_by(instance.loader) {
+            problems.push(Problem::error(
+                vec![info.file.file_name.clone()],
+                format!(
+                    "{} only has {}, which {} cannot load",
+                    info.name(),
+                    meta.source.name(),
+                    instance.loader.as_str()
+                ),
+            ));
+        }
+
+        by_id.entry(meta.id.as_str()).or_default().push(info);
+        for id in info.provided_ids() {
+            providers.entry(id).or_insert(info);
+        }
+    }
+
+    for (id, infos) in &by_id {
+        if infos.len() > 1 {
+            let files: Vec<String> = infos.iter().map(|info| info.file.file_name.clone()).collect();
+            problems.push(Problem::error(
+                files.clone(),
+                format!("mod id '{}' is provided by several jars: {}", id, files.join(", ")),
+            ));
+        }
+    }
+
+    for info in &enabled {
+        let Some(meta) = &info.metadata else {
+            continue;
+        };
+        let file = vec![info.file.file_name.clone()];
+
+        for dep in &meta.dependencies {
+            let id = dep.id.to_lowercase();
+            if BUILTIN.contains(&id.as_str()) {
+                continue;
+            }
+
+            if id == "minecraft" {
+                if dep.kind != DependencyKind::Breaks
+                    && !satisfies(dep, meta.source, &instance.game_version)
+                {
+                    problems.push(Problem::error(
+                        file.clone(),
+                        format!(
+                            "{} requires Minecraft {}, the instance uses {}",
+                            info.name(),
+                            dep.versions.join(" || "),
+                            instance.game_version
+                        ),
+                    ));
+                }
+                continue;
+            }
+
+            let provider = providers.get(dep.id.as_str());
+            match (dep.kind, provider) {
+                (DependencyKind::Required, None) => problems.push(Problem::error(
+                    file.clone(),
+                    format!("{} requires {}, which is not installed", info.name(), describe(dep)),
+                )),
+                (DependencyKind::Required, Some(other)) if !satisfies(dep, meta.source, other.version()) => {
+                    problems.push(Problem::error(
+                        vec![info.file.file_name.clone(), other.file.file_name.clone()],
+                        format!(
+                            "{} requires {}, but {} is installed",
+                            info.name(),
+                            describe(dep),
+                            other.version()
+                        ),
+                    ))
+                }
+                (DependencyKind::Optional, Some(other)) if !satisfies(dep, meta.source, other.version()) => {
+                    problems.push(Problem::warning(
+                        vec![info.file.file_name.clone(), other.file.file_name.clone()],
+                        format!(
+                            "{} works best with {}, but {} is installed",
+                            info.name(),
+                            describe(dep),
+                            other.version()
+                        ),
+                    ))
+                }
+                (DependencyKind::Breaks, Some(other)) if satisfies(dep, meta.source, other.version()) => {
+                    problems.push(Problem::error(
+                        vec![info.file.file_name.clone(), other.file.file_name.clone()],
+                        format!("{} is incompatible with {} {}", info.name(), other.name(), other.version()),
+                    ))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    problems.sort_by_key(|problem| std::cmp::Reverse(problem.severity));
+    problems
+}
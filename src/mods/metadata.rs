@@ -0,0 +1,476 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use color_eyre::eyre::{Result, WrapErr};
+use serde_json::Value as Json;
+use toml::Value as Toml;
+use zip::ZipArchive;
+
+use crate::instance::Loader;
+
+/// Descriptor file a piece of metadata was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Fabric,
+    Quilt,
+    Forge,
+    NeoForge,
+    Legacy,
+}
+
+impl Source {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Source::Fabric => "fabric.mod.json",
+            Source::Quilt => "quilt.mod.json",
+            Source::Forge => "META-INF/mods.toml",
+            Source::NeoForge => "META-INF/neoforge.mods.toml",
+            Source::Legacy => "mcmod.info",
+        }
+    }
+
+    /// Whether a loader can load mods described by this file
+    pub fn loadable_by(&self, loader: Loader) -> bool {
+        match self {
+            Source::Fabric => matches!(loader, Loader::Fabric | Loader::Quilt),
+            Source::Quilt => loader == Loader::Quilt,
+            // NeoForge still reads mods.toml for early 1.20.1 ports
+            Source::Forge => matches!(loader, Loader::Forge | Loader::NeoForge),
+            Source::NeoForge => loader == Loader::NeoForge,
+            Source::Legacy => loader == Loader::Forge,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DependencyKind {
+    Required,
+    Optional,
+    Breaks,
+}
+
+#[derive(Debug, Clone)]
+pub struct Dependency {
+    pub id: String,
+    /// Version predicates as written in the descriptor, any of which may match
+    pub versions: Vec<String>,
+    pub kind: DependencyKind,
+}
+
+#[derive(Debug, Clone)]
+pub struct ModMetadata {
+    pub source: Source,
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub authors: Vec<String>,
+    /// Path of the icon inside the jar
+    pub icon: Option<String>,
+    /// Additional mod ids this jar provides
+    pub provides: Vec<String>,
+    pub dependencies: Vec<Dependency>,
+}
+
+impl ModMetadata {
+    fn new(source: Source, id: String) -> Self {
+        ModMetadata {
+            source,
+            name: id.clone(),
+            id,
+            version: String::new(),
+            description: String::new(),
+            authors: Vec::new(),
+            icon: None,
+            provides: Vec::new(),
+            dependencies: Vec::new(),
+        }
+    }
+
+    pub fn required(&self) -> impl Iterator<Item = &Dependency> {
+        self.dependencies
+            .iter()
+            .filter(|dep| dep.kind == DependencyKind::Required)
+    }
+}
+
+fn read_entry(archive: &mut ZipArchive<File>, name: &str) -> Option<Vec<u8>> {
+    let mut entry = archive.by_name(name).ok()?;
+    let mut buf = Vec::new();
+    entry.read_to_end(&mut buf).ok()?;
+    Some(buf)
+}
+
+fn read_text(archive: &mut ZipArchive<File>, name: &str) -> Option<String> {
+    read_entry(archive, name).map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Reads every mod descriptor found in the jar, in the order of [`Source`]
+pub fn read(path: &Path) -> Result<Vec<ModMetadata>> {
+    let file = File::open(path)?;
+    let mut archive =
+        ZipArchive::new(file).wrap_err_with(|| format!("{} is not a jar", path.display()))?;
+
+    let mut found = Vec::new();
+    if let Some(raw) = read_text(&mut archive, Source::Fabric.name()) {
+        found.extend(parse_fabric(&raw));
+    }
+    if let Some(raw) = read_text(&mut archive, Source::Quilt.name()) {
+        found.extend(parse_quilt(&raw));
+    }
+    for source in [Source::Forge, Source::NeoForge] {
+        if let Some(raw) = read_text(&mut archive, source.name()) {
+            let manifest = read_text(&mut archive, "META-INF/MANIFEST.MF");
+            found.extend(parse_mods_toml(&raw, source, manifest.as_deref()));
+        }
+    }
+    if let Some(raw) = read_text(&mut archive, Source::Legacy.name()) {
+        found.extend(parse_mcmod_info(&raw));
+    }
+
+    Ok(found)
+}
+
+/// Picks the descriptor the instance's loader will use
+pub fn preferred(found: Vec<ModMetadata>, loader: Loader) -> Option<ModMetadata> {
+    let index = found
+        .iter()
+        .position(|meta| meta.source.loadable_by(loader))
+        .unwrap_or(0);
+    found.into_iter().nth(index)
+}
+
+pub fn read_icon(path: &Path, icon: &str) -> Option<Vec<u8>> {
+    let mut archive = ZipArchive::new(File::open(path).ok()?).ok()?;
+    read_entry(&mut archive, icon.trim_start_matches('/'))
+}
+
+fn json_str(value: &Json, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(Json::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Accepts `"1.0"`, `["1.0", "2.0"]` and `{ "versions": ... }` style values
+fn json_versions(value: &Json) -> Vec<String> {
+    match value {
+        Json::String(version) => vec![version.clone()],
+        Json::Array(versions) => versions.iter().flat_map(json_versions).collect(),
+        Json::Object(map) => map.get("versions").map(json_versions).unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+fn json_people(value: Option<&Json>) -> Vec<String> {
+    match value {
+        Some(Json::Array(people)) => people
+            .iter()
+            .filter_map(|person| match person {
+                Json::String(name) => Some(name.clone()),
+                Json::Object(map) => map.get("name").and_then(Json::as_str).map(String::from),
+                _ => None,
+            })
+            .collect(),
+        Some(Json::Object(people)) => people.keys().cloned().collect(),
+        Some(Json::String(name)) => vec![name.clone()],
+        _ => Vec::new(),
+    }
+}
+
+/// Icons are either a path or a map of sizes to paths, pick the largest
+fn json_icon(value: Option<&Json>) -> Option<String> {
+    match value? {
+        Json::String(path) => Some(path.clone()),
+        Json::Object(sizes) => sizes
+            .iter()
+            .max_by_key(|(size, _)| size.parse::<u32>().unwrap_or_default())
+            .and_then(|(_, path)| path.as_str().map(String::from)),
+        _ => None,
+    }
+}
+
+fn parse_fabric(raw: &str) -> Option<ModMetadata> {
+    let json: Json = serde_json::from_str(raw).ok()?;
+    let mut meta = ModMetadata::new(Source::Fabric, json_str(&json, "id"));
+    meta.version = json_str(&json, "version");
+    meta.description = json_str(&json, "description");
+    if let Some(name) = json.get("name").and_then(Json::as_str) {
+        meta.name = name.to_string();
+    }
+    meta.authors = json_people(json.get("authors"));
+    meta.icon = json_icon(json.get("icon"));
+    meta.provides = json_versions(json.get("provides").unwrap_or(&Json::Null));
+
+    let kinds = [
+        ("depends", DependencyKind::Required),
+        ("recommends", DependencyKind::Optional),
+        ("suggests", DependencyKind::Optional),
+        ("breaks", DependencyKind::Breaks),
+        ("conflicts", DependencyKind::Breaks),
+    ];
+    for (key, kind) in kinds {
+        let Some(Json::Object(deps)) = json.get(key) else {
+            continue;
+        };
+        for (id, versions) in deps {
+            meta.dependencies.push(Dependency {
+                id: id.clone(),
+                versions: json_versions(versions),
+                kind,
+            });
+        }
+    }
+
+    Some(meta)
+}
+
+fn quilt_dependencies(value: Option<&Json>, kind: DependencyKind) -> Vec<Dependency> {
+    let Some(Json::Array(entries)) = value else {
+        return Vec::new();
+    };
+
+    entries
+        .iter()
+        .filter_map(|entry| match entry {
+            Json::String(id) => Some(Dependency {
+                id: id.split_once(':').map_or(id.as_str(), |(_, id)| id).to_string(),
+                versions: Vec::new(),
+                kind,
+            }),
+            Json::Object(map) => {
+                let id = map.get("id")?.as_str()?;
+                let optional = map.get("optional").and_then(Json::as_bool).unwrap_or(false);
+                Some(Dependency {
+                    id: id.split_once(':').map_or(id, |(_, id)| id).to_string(),
+                    versions: map.get("versions").map(json_versions).unwrap_or_default(),
+                    kind: if optional && kind == DependencyKind::Required {
+                        DependencyKind::Optional
+                    } else {
+                        kind
+                    },
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn parse_quilt(raw: &str) -> Option<ModMetadata> {
+    let json: Json = serde_json::from_str(raw).ok()?;
+    let loader = json.get("quilt_loader")?;
+    let mut meta = ModMetadata::new(Source::Quilt, json_str(loader, "id"));
+    meta.version = json_str(loader, "version");
+
+    if let Some(info) = loader.get("metadata") {
+        if let Some(name) = info.get("name").and_then(Json::as_str) {
+            meta.name = name.to_string();
+        }
+        meta.description = json_str(info, "description");
+        meta.authors = json_people(info.get("contributors"));
+        meta.icon = json_icon(info.get("icon"));
+    }
+
+    if let Some(Json::Array(provides)) = loader.get("provides") {
+        meta.provides = provides
+            .iter()
+            .filter_map(|entry| match entry {
+                Json::String(id) => Some(id.clone()),
+                Json::Object(map) => map.get("id").and_then(Json::as_str).map(String::from),
+                _ => None,
+            })
+            .collect();
+    }
+
+    meta.dependencies = quilt_dependencies(loader.get("depends"), DependencyKind::Required);
+    meta.dependencies
+        .extend(quilt_dependencies(loader.get("breaks"), DependencyKind::Breaks));
+    Some(meta)
+}
+
+/// `${file.jarVersion}` is substituted from the jar manifest at load time
+fn manifest_version(manifest: Option<&str>) -> Option<String> {
+    manifest?
+        .lines()
+        .find_map(|line| line.strip_prefix("Implementation-Version:"))
+        .map(|version| version.trim().to_string())
+}
+
+fn toml_str(value: &Toml, key: &str) -> String {
+    value
+        .get(key)
+        .and_then(Toml::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn parse_mods_toml(raw: &str, source: Source, manifest: Option<&str>) -> Vec<ModMetadata> {
+    let Ok(toml) = raw.parse::<toml::Table>() else {
+        return Vec::new();
+    };
+    let Some(Toml::Array(mods)) = toml.get("mods") else {
+        return Vec::new();
+    };
+
+    let logo = toml.get("logoFile").and_then(Toml::as_str).map(String::from);
+    let mut found: Vec<ModMetadata> = mods
+        .iter()
+        .map(|entry| {
+            let mut meta = ModMetadata::new(source, toml_str(entry, "modId"));
+            meta.version = toml_str(entry, "version");
+            if meta.version.contains("${file.jarVersion}") {
+                meta.version = manifest_version(manifest).unwrap_or(meta.version);
+            }
+            if let Some(name) = entry.get("displayName").and_then(Toml::as_str) {
+                meta.name = name.to_string();
+            }
+            meta.description = toml_str(entry, "description").trim().to_string();
+            meta.authors = toml_str(entry, "authors")
+                .split(',')
+                .map(|author| author.trim().to_string())
+                .filter(|author| !author.is_empty())
+                .collect();
+            meta.icon = entry
+                .get("logoFile")
+                .and_then(Toml::as_str)
+                .map(String::from)
+                .or_else(|| logo.clone());
+
+            let deps = toml
+                .get("dependencies")
+                .and_then(|deps| deps.get(&meta.id))
+                .and_then(Toml::as_array);
+            for dep in deps.into_iter().flatten() {
+                let kind = match dep.get("type").and_then(Toml::as_str) {
+                    Some(kind) => match kind.to_lowercase().as_str() {
+                        "required" => DependencyKind::Required,
+                        "incompatible" => DependencyKind::Breaks,
+                        _ => DependencyKind::Optional,
+                    },
+                    None if dep.get("mandatory").and_then(Toml::as_bool) == Some(true) => {
+                        DependencyKind::Required
+                    }
+                    None => DependencyKind::Optional,
+                };
+                let range = toml_str(dep, "versionRange");
+                meta.dependencies.push(Dependency {
+                    id: toml_str(dep, "modId"),
+                    versions: if range.is_empty() { Vec::new() } else { vec![range] },
+                    kind,
+                });
+            }
+            meta
+        })
+        .collect();
+
+    // Extra mods in the same jar are treated as provided ids of the first one
+    if found.len() > 1 {
+        let extra: Vec<String> = found[1..].iter().map(|meta| meta.id.clone()).collect();
+        found[0].provides.extend(extra);
+        found.truncate(1);
+    }
+    found
+}
+
+/// Drops commas right before a closing `}` or `]`, leaving strings alone
+fn strip_trailing_commas(raw: &str) -> String {
+    let chars: Vec<char> = raw.chars().collect();
+    let mut out = String::with_capacity(raw.len());
+    let (mut in_string, mut escaped) = (false, false);
+    for (i, &c) in chars.iter().enumerate() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else if c == '"' {
+            in_string = true;
+        } else if c == ',' {
+            let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+            if matches!(next, Some('}' | ']')) {
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn parse_mcmod_info(raw: &str) -> Option<ModMetadata> {
+    // Some old mods ship with trailing commas or control characters
+    let cleaned: String = raw.chars().filter(|c| !c.is_control() || *c == '\n').collect();
+    let json: Json = serde_json::from_str(&strip_trailing_commas(&cleaned)).ok()?;
+    let list = match &json {
+        Json::Array(list) => list.clone(),
+        Json::Object(map) => map.get("modList")?.as_array()?.clone(),
+        _ => return None,
+    };
+
+    let entry = list.first()?;
+    let mut meta = ModMetadata::new(Source::Legacy, json_str(entry, "modid"));
+    meta.version = json_str(entry, "version");
+    if let Some(name) = entry.get("name").and_then(Json::as_str) {
+        meta.name = name.to_string();
+    }
+    meta.description = json_str(entry, "description");
+    meta.authors = json_people(entry.get("authorList").or_else(|| entry.get("authors")));
+    meta.icon = entry
+        .get("logoFile")
+        .and_then(Json::as_str)
+        .filter(|logo| !logo.is_empty())
+        .map(String::from);
+
+    let mcversion = json_str(entry, "mcversion");
+    if !mcversion.is_empty() {
+        meta.dependencies.push(Dependency {
+            id: "minecraft".to_string(),
+            versions: vec![mcversion],
+            kind: DependencyKind::Required,
+        });
+    }
+    for id in json_versions(entry.get("requiredMods").unwrap_or(&Json::Null)) {
+        // Entries look like "Forge@[10.13,)"
+        let (id, version) = id.split_once('@').unwrap_or((id.as_str(), ""));
+        meta.dependencies.push(Dependency {
+            id: id.to_string(),
+            versions: if version.is_empty() { Vec::new() } else { vec![version.to_string()] },
+            kind: DependencyKind::Required,
+        });
+    }
+
+    Some(meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_trailing_commas_outside_strings() {
+        assert_eq!(strip_trailing_commas(r#"[{"a": 1,}, ]"#), r#"[{"a": 1} ]"#);
+        assert_eq!(strip_trailing_commas("{\"a\": [1, 2,\n  ],\n}"), "{\"a\": [1, 2\n  ]\n}");
+        assert_eq!(strip_trailing_commas(r#"{"a": "x,}", "b": "\",]"}"#), r#"{"a": "x,}", "b": "\",]"}"#);
+    }
+
+    #[test]
+    fn mcmod_info_with_trailing_commas() {
+        let raw = r#"[
+  {
+    "modid": "examplemod",
+    "name": "Example Mod",
+    "version": "1.2.3",
+    "authorList": ["Someone", "Someone else",],
+    "logoFile": "",
+  },
+]"#;
+        let meta = parse_mcmod_info(raw).unwrap();
+        assert_eq!(meta.id, "examplemod");
+        assert_eq!(meta.name, "Example Mod");
+        assert_eq!(meta.authors, ["Someone", "Someone else"]);
+        assert!(meta.icon.is_none());
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{debug, instance::Instance, modrinth};
+
+use metadata::ModMetadata;
+
+pub mod bisect;
+pub mod check;
+pub mod metadata;
+pub mod update;
+pub mod version;
+
+/// Suffix other launchers use for disabled mods as well
+pub const DISABLED_SUFFIX: &str = ".disabled";
+
+#[derive(Debug, Clone)]
+pub struct ModFile {
+    pub path: PathBuf,
+    /// Jar name without the `.disabled` suffix
+    pub file_name: String,
+    pub enabled: bool,
+}
+
+impl ModFile {
+    pub fn from_path(path: PathBuf) -> Option<ModFile> {
+        let name = path.file_name()?.to_string_lossy().to_string();
+        let (file_name, enabled) = match name.strip_suffix(DISABLED_SUFFIX) {
+            Some(jar) => (jar.to_string(), false),
+            None => (name, true),
+        };
+        if !file_name.ends_with(".jar") {
+            return None;
+        }
+
+        Some(ModFile {
+            path,
+            file_name,
+            enabled,
+        })
+    }
+
+    /// Renames the jar to or from `.jar.disabled`
+    pub fn set_enabled(&mut self, enabled: bool) -> Result<()> {
+        if self.enabled == enabled {
+            return Ok(());
+        }
+
+        let dir = self
+            .path
+            .parent()
+            .ok_or_else(|| eyre!("{} has no parent directory", self.path.display()))?;
+        let target = if enabled {
+            dir.join(&self.file_name)
+        } else {
+            dir.join(format!("{}{}", self.file_name, DISABLED_SUFFIX))
+        };
+        if target.exists() {
+            return Err(eyre!("{} already exists", target.display()));
+        }
+
+        fs::rename(&self.path, &target)?;
+        self.path = target;
+        self.enabled = enabled;
+        Ok(())
+    }
+
+    pub fn sha1(&self) -> Result<String> {
+        modrinth::sha1_file(&self.path)
+    }
+
+    /// Size and modification time in nanoseconds, which change when the
+    /// jar is replaced even by one of the same size
+    fn stamp(&self) -> (u64, Option<u64>) {
+        let Ok(meta) = fs::metadata(&self.path) else {
+            return (0, None);
+        };
+        let modified = meta
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|since| since.as_nanos() as u64);
+        (meta.len(), modified)
+    }
+}
+
+/// Modrinth project a jar was identified as
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectLink {
+    pub project_id: String,
+    pub slug: String,
+    pub title: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ModInfo {
+    pub file: ModFile,
+    pub metadata: Option<ModMetadata>,
+    pub project: Option<ProjectLink>,
+}
+
+impl ModInfo {
+    pub fn name(&self) -> &str {
+        if let Some(meta) = &self.metadata {
+            return &meta.name;
+        }
+        if let Some(project) = &self.project {
+            return &project.title;
+        }
+        &self.file.file_name
+    }
+
+    pub fn version(&self) -> &str {
+        match (&self.metadata, &self.project) {
+            (Some(meta), _) if !meta.version.is_empty() => &meta.version,
+            (_, Some(project)) => &project.version,
+            _ => "",
+        }
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.metadata.as_ref().map(|meta| meta.id.as_str())
+    }
+
+    /// Mod ids other mods can depend on to require this jar
+    pub fn provided_ids(&self) -> Vec<&str> {
+        match &self.metadata {
+            Some(meta) => std::iter::once(meta.id.as_str())
+                .chain(meta.provides.iter().map(String::as_str))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Whether `query` names this mod by id, name, Modrinth slug or file name
+    pub fn matches(&self, query: &str) -> bool {
+        self.file.file_name == query
+            || self.name().eq_ignore_ascii_case(query)
+            || self.id() == Some(query)
+            || self
+                .project
+                .as_ref()
+                .is_some_and(|project| project.slug == query || project.project_id == query)
+    }
+}
+
+/// Enabled mods that require one of the ids `target` provides
+pub fn dependents<'a>(mods: &'a [ModInfo], target: &ModInfo) -> Vec<&'a ModInfo> {
+    let provided = target.provided_ids();
+    if provided.is_empty() {
+        return Vec::new();
+    }
+
+    mods.iter()
+        .filter(|info| info.file.enabled && info.file.file_name != target.file.file_name)
+        .filter(|info| {
+            info.metadata.as_ref().is_some_and(|meta| {
+                meta.required().any(|dep| provided.contains(&dep.id.as_str()))
+            })
+        })
+        .collect()
+}
+
+/// Lists the jars in the instance's `mods/` directory, including disabled
+/// ones, sorted by name
+pub fn list(instance: &Instance) -> Vec<ModFile> {
+    let Ok(entries) = fs::read_dir(instance.mods_dir()) else {
+        return Vec::new();
+    };
+
+    let mut mods: Vec<ModFile> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter_map(ModFile::from_path)
+        .collect();
+
+    mods.sort_by_key(|file| file.file_name.to_lowercase());
+    mods
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CachedLink {
+    size: u64,
+    #[serde(default)]
+    modified: Option<u64>,
+    sha1: String,
+    project: Option<ProjectLink>,
+}
+
+impl CachedLink {
+    /// Whether the entry is still about the jar on disk
+    fn matches(&self, file: &ModFile) -> bool {
+        let (size, modified) = file.stamp();
+        self.size == size && self.modified.is_some() && self.modified == modified
+    }
+}
+
+fn cache_path(instance: &Instance) -> PathBuf {
+    instance.path.join(".mcl").join("modrinth.json")
+}
+
+fn read_cache(instance: &Instance) -> HashMap<String, CachedLink> {
+    fs::read_to_string(cache_path(instance))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn write_cache(instance: &Instance, cache: &HashMap<String, CachedLink>) -> Result<()> {
+    let path = cache_path(instance);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(cache)?)?;
+    Ok(())
+}
+
+/// Reads the embedded metadata of every mod along with cached Modrinth links
+pub fn load(instance: &Instance) -> Vec<ModInfo> {
+    let cache = read_cache(instance);
+
+    list(instance)
+        .into_iter()
+        .map(|file| {
+            let metadata = match metadata::read(&file.path) {
+                Ok(found) => metadata::preferred(found, instance.loader),
+                Err(err) => {
+                    debug!("Failed to read metadata of {}: {}", file.file_name, err);
+                    None
+                }
+            };
+            let project = cache
+                .get(&file.file_name)
+                .filter(|cached| cached.matches(&file))
+                .and_then(|cached| cached.project.clone());
+
+            ModInfo {
+                file,
+                metadata,
+                project,
+            }
+        })
+        .collect()
+}
+
+/// Looks up the SHA-1 of every mod on Modrinth and caches the result.
+/// Returns how many mods could be linked to a project.
+pub fn identify(instance: &Instance) -> Result<usize> {
+    let mut cache = read_cache(instance);
+    let files = list(instance);
+
+    // keyed by file name, copies of the same jar share a hash
+    let mut pending = HashMap::new();
+    for file in &files {
+        let known = cache
+            .get(&file.file_name)
+            .is_some_and(|cached| cached.matches(file) && cached.project.is_some());
+        if !known {
+            pending.insert(file.file_name.clone(), (file.sha1()?, file.stamp()));
+        }
+    }
+
+    let mut keys: Vec<String> = pending.values().map(|(sha1, _)| sha1.clone()).collect();
+    keys.sort();
+    keys.dedup();
+    let versions = modrinth::versions_from_hashes(&keys)?;
+    let ids: Vec<&str> = versions.values().map(|v| v.project_id.as_str()).collect();
+    let projects: HashMap<String, modrinth::Project> = modrinth::projects(&ids)?
+        .into_iter()
+        .map(|project| (project.id.clone(), project))
+        .collect();
+
+    for (file_name, (sha1, (size, modified))) in pending {
+        let project = versions.get(&sha1).and_then(|version| {
+            projects.get(&version.project_id).map(|project| ProjectLink {
+                project_id: project.id.clone(),
+                slug: project.slug.clone(),
+                title: project.title.clone(),
+                version: version.version_number.clone(),
+            })
+        });
+        cache.insert(
+            file_name,
+            CachedLink {
+                size,
+                modified,
+                sha1,
+                project,
+            },
+        );
+    }
+
+    // forget jars that were removed
+    cache.retain(|file_name, _| files.iter().any(|file| &file.file_name == file_name));
+    write_cache(instance, &cache)?;
+
+    Ok(cache.values().filter(|cached| cached.project.is_some()).count())
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::fs;
+
+use chrono::{DateTime, FixedOffset};
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+    debug,
+    instance::{Channel, Instance},
+    modrinth::{self, Version},
+};
+
+use super::ModFile;
+
+#[derive(Debug, Clone)]
+pub struct Update {
+    pub file: ModFile,
+    pub slug: String,
+    pub title: String,
+    pub current: Version,
+    pub latest: Version,
+    /// Every allowed version newer than `current`, newest first
+    pub newer: Vec<Version>,
+}
+
+impl Update {
+    /// Whether the update is selected by `query` (slug, title, project id or file name)
+    pub fn matches(&self, query: &str) -> bool {
+        self.slug.eq_ignore_ascii_case(query)
+            || self.title.eq_ignore_ascii_case(query)
+            || self.current.project_id == query
+            || self.file.file_name == query
+    }
+
+    /// First non-empty lines of each newer version's changelog
+    pub fn changelog_summary(&self, max_lines: usize) -> Vec<String> {
+        let mut lines = Vec::new();
+        for version in &self.newer {
+            lines.push(format!("{} ({})", version.version_number, version.version_type));
+            let changelog = version.changelog.as_deref().unwrap_or_default();
+            let entries: Vec<&str> = changelog
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .collect();
+
+            for line in entries.iter().take(max_lines) {
+                lines.push(format!("  {}", line));
+            }
+            if entries.len() > max_lines {
+                lines.push(format!("  ... {} more lines", entries.len() - max_lines));
+            }
+        }
+        lines
+    }
+}
+
+fn published(version: &Version) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(&version.date_published).ok()
+}
+
+/// Compares installed mods against Modrinth for the instance's game version
+/// and loader. `channel` overrides the instance's configured channel.
+pub fn outdated(instance: &Instance, channel: Option<Channel>) -> Result<Vec<Update>> {
+    let files = super::list(instance);
+    let mut by_hash = HashMap::new();
+    for file in files {
+        match file.sha1() {
+            Ok(hash) => {
+                by_hash.insert(hash, file);
+            }
+            Err(err) => {
+                debug!("Failed to hash {}: {}", file.file_name, err);
+            }
+        }
+    }
+
+    let hashes: Vec<String> = by_hash.keys().cloned().collect();
+    let current = modrinth::versions_from_hashes(&hashes)?;
+
+    let ids: Vec<&str> = current.values().map(|v| v.project_id.as_str()).collect();
+    let projects: HashMap<String, modrinth::Project> = modrinth::projects(&ids)?
+        .into_iter()
+        .map(|project| (project.id.clone(), project))
+        .collect();
+
+    let loaders = [instance.loader.as_str()];
+    let game_versions = [instance.game_version.as_str()];
+
+    let mut updates = Vec::new();
+    for (hash, version) in current {
+        let Some(file) = by_hash.remove(&hash) else {
+            continue;
+        };
+        let (slug, title) = projects
+            .get(&version.project_id)
+            .map(|p| (p.slug.clone(), p.title.clone()))
+            .unwrap_or_else(|| (version.project_id.clone(), file.file_name.clone()));
+
+        let keys = [slug.as_str(), version.project_id.as_str(), file.file_name.as_str()];
+        if instance.mods.is_pinned(&keys) {
+            debug!("Skipping pinned mod {}", slug);
+            continue;
+        }
+
+        let channel = channel.unwrap_or_else(|| instance.mods.channel_for(&slug));
+        let installed_at = published(&version);
+        let mut newer: Vec<Version> =
+            modrinth::project_versions(&version.project_id, &loaders, &game_versions)?
+                .into_iter()
+                .filter(|candidate| candidate.id != version.id)
+                .filter(|candidate| channel.allows(&candidate.version_type))
+                .filter(|candidate| published(candidate) > installed_at)
+                .collect();
+
+        if newer.is_empty() {
+            continue;
+        }
+
+        newer.sort_by_key(|candidate| std::cmp::Reverse(published(candidate)));
+        updates.push(Update {
+            file,
+            slug,
+            title,
+            current: version,
+            latest: newer[0].clone(),
+            newer,
+        });
+    }
+
+    updates.sort_by_key(|update| update.title.to_lowercase());
+    Ok(updates)
+}
+
+/// Downloads the latest version and replaces the old jar
+pub fn apply(instance: &Instance, update: &Update) -> Result<()> {
+    let file = update
+        .latest
+        .primary_file()
+        .ok_or_else(|| eyre!("version {} has no files", update.latest.version_number))?;
+
+    let target = modrinth::download(file, &instance.mods_dir())?;
+    if target != update.file.path {
+        fs::remove_file(&update.file.path)?;
+    }
+
+    // keep disabled mods disabled
+    if !update.file.enabled {
+        if let Some(mut updated) = ModFile::from_path(target) {
+            updated.set_enabled(false)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,245 @@
+use std::cmp::Ordering;
+
+use crate::warn;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Number(u64),
+    Text(String),
+}
+
+/// Splits `1.20.1-beta.2+build.5` into comparable parts, dropping build metadata
+fn parts(version: &str) -> (Vec<Part>, Vec<Part>) {
+    let version = version.split('+').next().unwrap_or_default();
+    let (release, pre) = version.split_once('-').unwrap_or((version, ""));
+    let split = |value: &str| {
+        value
+            .split(['.', '-', '_'])
+            .filter(|part| !part.is_empty())
+            .map(|part| match part.parse() {
+                Ok(number) => Part::Number(number),
+                Err(_) => Part::Text(part.to_lowercase()),
+            })
+            .collect::<Vec<_>>()
+    };
+    (split(release), split(pre))
+}
+
+fn compare_parts(a: &[Part], b: &[Part]) -> Ordering {
+    for i in 0..a.len().max(b.len()) {
+        let ordering = match (a.get(i), b.get(i)) {
+            (Some(Part::Number(x)), Some(Part::Number(y))) => x.cmp(y),
+            (Some(Part::Text(x)), Some(Part::Text(y))) => x.cmp(y),
+            (Some(Part::Number(_)), Some(Part::Text(_))) => Ordering::Greater,
+            (Some(Part::Text(_)), Some(Part::Number(_))) => Ordering::Less,
+            (Some(Part::Number(x)), None) => x.cmp(&0),
+            (None, Some(Part::Number(y))) => 0.cmp(y),
+            (Some(Part::Text(_)), None) => Ordering::Less,
+            (None, Some(Part::Text(_))) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// Loose semver-style comparison; pre-releases sort before their release
+pub fn compare(a: &str, b: &str) -> Ordering {
+    let (a_release, a_pre) = parts(a);
+    let (b_release, b_pre) = parts(b);
+    compare_parts(&a_release, &b_release).then_with(|| match (a_pre.is_empty(), b_pre.is_empty()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => compare_parts(&a_pre, &b_pre),
+    })
+}
+
+/// Whether a version is numeric enough to compare (skips snapshots like 23w31a)
+pub fn comparable(version: &str) -> bool {
+    version
+        .split(['.', '-', '+'])
+        .next()
+        .is_some_and(|major| major.parse::<u64>().is_ok())
+}
+
+/// Bumps the component at `index` and drops the rest, for `~` and `^`
+fn bump(version: &str, index: usize) -> String {
+    let mut numbers: Vec<u64> = version
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect();
+    numbers.resize(numbers.len().max(index + 1), 0);
+    numbers[index] += 1;
+    numbers.truncate(index + 1);
+    numbers
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn matches_single(predicate: &str, version: &str) -> bool {
+    let predicate = predicate.trim();
+    if predicate.is_empty() || predicate == "*" {
+        return true;
+    }
+
+    for (operator, check) in [
+        (">=", Ordering::is_ge as fn(Ordering) -> bool),
+        ("<=", Ordering::is_le),
+        (">", Ordering::is_gt),
+        ("<", Ordering::is_lt),
+        ("=", Ordering::is_eq),
+    ] {
+        if let Some(bound) = predicate.strip_prefix(operator) {
+            return check(compare(version, bound.trim()));
+        }
+    }
+
+    if let Some(base) = predicate.strip_prefix('~') {
+        let minor = if base.matches('.').count() == 0 { 0 } else { 1 };
+        return compare(version, base).is_ge() && compare(version, &bump(base, minor)).is_lt();
+    }
+    if let Some(base) = predicate.strip_prefix('^') {
+        return compare(version, base).is_ge() && compare(version, &bump(base, 0)).is_lt();
+    }
+
+    // 1.20.x style wildcards
+    if predicate.ends_with(".x") || predicate.ends_with(".*") {
+        let prefix = &predicate[..predicate.len() - 1];
+        return version.starts_with(prefix) || version == &prefix[..prefix.len() - 1];
+    }
+
+    compare(version, predicate).is_eq()
+}
+
+/// Fabric/Quilt predicates: space separated terms must all match
+pub fn matches_predicate(predicate: &str, version: &str) -> bool {
+    predicate
+        .split_whitespace()
+        .all(|term| matches_single(term, version))
+}
+
+/// Maven ranges as used by Forge, e.g. `[1.20,1.21)` or `[47,)`.
+/// A plain version is a soft requirement and matches anything, and so does
+/// a malformed range since nothing can be said about it.
+pub fn matches_maven(range: &str, version: &str) -> bool {
+    let range = range.trim();
+    if range.is_empty() || range == "*" || !range.starts_with(['[', '(']) {
+        return true;
+    }
+
+    // split "[1,2),[3,4)" into its individual ranges
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    for (i, c) in range.char_indices() {
+        if c == ']' || c == ')' {
+            ranges.push(&range[start..=i]);
+            start = i + 1;
+        } else if c == ',' && start == i {
+            start = i + 1;
+        }
+    }
+
+    let valid = |range: &&str| range.len() > 2 && range.starts_with(['[', '(']);
+    if ranges.is_empty() || !ranges.iter().all(valid) {
+        warn!("Ignoring malformed version range '{}'", range);
+        return true;
+    }
+
+    ranges.into_iter().any(|range| {
+        let inclusive_start = range.starts_with('[');
+        let inclusive_end = range.ends_with(']');
+        let inner = &range[1..range.len() - 1];
+        match inner.split_once(',') {
+            None => compare(version, inner.trim()).is_eq(),
+            Some((low, high)) => {
+                let low = low.trim();
+                let high = high.trim();
+                let above = low.is_empty()
+                    || match compare(version, low) {
+                        Ordering::Greater => true,
+                        Ordering::Equal => inclusive_start,
+                        Ordering::Less => false,
+                    };
+                let below = high.is_empty()
+                    || match compare(version, high) {
+                        Ordering::Less => true,
+                        Ordering::Equal => inclusive_end,
+                        Ordering::Greater => false,
+                    };
+                above && below
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_orders_versions() {
+        assert_eq!(compare("1.20.1", "1.20.1"), Ordering::Equal);
+        assert_eq!(compare("1.20", "1.20.0"), Ordering::Equal);
+        assert_eq!(compare("1.20.10", "1.20.9"), Ordering::Greater);
+        assert_eq!(compare("1.19.4", "1.20"), Ordering::Less);
+        assert_eq!(compare("0.14.21+build.3", "0.14.21"), Ordering::Equal);
+    }
+
+    #[test]
+    fn pre_releases_sort_before_their_release() {
+        assert_eq!(compare("1.0.0-beta.2", "1.0.0"), Ordering::Less);
+        assert_eq!(compare("1.0.0-alpha", "1.0.0-beta"), Ordering::Less);
+        assert_eq!(compare("1.0.0-beta.10", "1.0.0-beta.2"), Ordering::Greater);
+        assert_eq!(compare("1.0.1-rc.1", "1.0.0"), Ordering::Greater);
+    }
+
+    #[test]
+    fn comparable_skips_snapshots() {
+        assert!(comparable("1.20.1"));
+        assert!(!comparable("23w31a"));
+    }
+
+    #[test]
+    fn predicates() {
+        assert!(matches_predicate(">=1.20", "1.20.1"));
+        assert!(!matches_predicate(">1.20.1", "1.20.1"));
+        assert!(matches_predicate(">=1.19 <1.20", "1.19.4"));
+        assert!(!matches_predicate(">=1.19 <1.20", "1.20"));
+        assert!(matches_predicate("~1.20", "1.20.6"));
+        assert!(!matches_predicate("~1.20", "1.21"));
+        assert!(matches_predicate("^1.2.3", "1.9.0"));
+        assert!(!matches_predicate("^1.2.3", "2.0.0"));
+        assert!(matches_predicate("1.20.x", "1.20.4"));
+        assert!(matches_predicate("1.20.x", "1.20"));
+        assert!(!matches_predicate("1.20.x", "1.21"));
+        assert!(matches_predicate("*", "anything"));
+        assert!(matches_predicate("=1.20.1", "1.20.1"));
+        assert!(matches_predicate("1.20.1", "1.20.1"));
+    }
+
+    #[test]
+    fn maven_ranges() {
+        assert!(matches_maven("[1.20,1.21)", "1.20.1"));
+        assert!(!matches_maven("[1.20,1.21)", "1.21"));
+        assert!(matches_maven("[47,)", "47.1.0"));
+        assert!(!matches_maven("(47,)", "47"));
+        assert!(matches_maven("[1.18],[1.20,)", "1.18"));
+        assert!(!matches_maven("[1.18],[1.20,)", "1.19"));
+        assert!(matches_maven("1.20.1", "1.19"));
+    }
+
+    #[test]
+    fn malformed_maven_ranges_match_anything() {
+        for range in ["[1],]", "[1.0,1.1),)", "[", "(]", "[1.0,1.1)]", "[1.0,,", "[)"] {
+            assert!(matches_maven(range, "1.20.1"), "{}", range);
+        }
+    }
+}
@@ -0,0 +1,453 @@
+use std::fs::{self, File};
+use std::io::{BufReader, Read, Write};
+use std::path::Path;
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+/// Named Binary Tag value, as used by `level.dat`, `servers.dat` and
+/// region files
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    /// Element type id and elements, so empty lists keep their type
+    List(u8, Vec<Tag>),
+    Compound(Compound),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+/// Compound entries in file order, so rewriting a file keeps its layout
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Compound(pub Vec<(String, Tag)>);
+
+impl Compound {
+    pub fn get(&self, key: &str) -> Option<&Tag> {
+        self.0.iter().find(|(name, _)| name == key).map(|(_, tag)| tag)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Tag> {
+        self.0.iter_mut().find(|(name, _)| name == key).map(|(_, tag)| tag)
+    }
+
+    pub fn insert(&mut self, key: &str, tag: Tag) {
+        match self.get_mut(key) {
+            Some(existing) => *existing = tag,
+            None => self.0.push((key.to_string(), tag)),
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<Tag> {
+        let index = self.0.iter().position(|(name, _)| name == key)?;
+        Some(self.0.remove(index).1)
+    }
+
+    pub fn compound(&self, key: &str) -> Option<&Compound> {
+        self.get(key).and_then(Tag::as_compound)
+    }
+
+    pub fn compound_mut(&mut self, key: &str) -> Option<&mut Compound> {
+        match self.get_mut(key) {
+            Some(Tag::Compound(compound)) => Some(compound),
+            _ => None,
+        }
+    }
+
+    pub fn string(&self, key: &str) -> Option<&str> {
+        self.get(key).and_then(Tag::as_str)
+    }
+
+    pub fn number(&self, key: &str) -> Option<i64> {
+        self.get(key).and_then(Tag::as_i64)
+    }
+
+    pub fn list(&self, key: &str) -> &[Tag] {
+        match self.get(key) {
+            Some(Tag::List(_, items)) => items,
+            _ => &[],
+        }
+    }
+}
+
+impl Tag {
+    fn id(&self) -> u8 {
+        match self {
+            Tag::Byte(_) => 1,
+            Tag::Short(_) => 2,
+            Tag::Int(_) => 3,
+            Tag::Long(_) => 4,
+            Tag::Float(_) => 5,
+            Tag::Double(_) => 6,
+            Tag::ByteArray(_) => 7,
+            Tag::String(_) => 8,
+            Tag::List(..) => 9,
+            Tag::Compound(_) => 10,
+            Tag::IntArray(_) => 11,
+            Tag::LongArray(_) => 12,
+        }
+    }
+
+    /// Any integer tag widened to `i64`
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Tag::Byte(value) => Some(*value as i64),
+            Tag::Short(value) => Some(*value as i64),
+            Tag::Int(value) => Some(*value as i64),
+            Tag::Long(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_compound(&self) -> Option<&Compound> {
+        match self {
+            Tag::Compound(compound) => Some(compound),
+            _ => None,
+        }
+    }
+}
+
+/// Decodes Java's modified UTF-8: NUL is `C0 80` and supplementary
+/// characters are surrogate pairs of three bytes each (CESU-8)
+fn decode_mutf8(bytes: &[u8]) -> String {
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        let continuation = |offset: usize| bytes.get(i + offset).filter(|b| *b & 0xc0 == 0x80).map(|b| (b & 0x3f) as u16);
+        match byte {
+            0x00..=0x7f => {
+                units.push(byte as u16);
+                i += 1;
+            }
+            0xc0..=0xdf => match continuation(1) {
+                Some(low) => {
+                    units.push(((byte & 0x1f) as u16) << 6 | low);
+                    i += 2;
+                }
+                None => {
+                    units.push(0xfffd);
+                    i += 1;
+                }
+            },
+            0xe0..=0xef => match (continuation(1), continuation(2)) {
+                (Some(middle), Some(low)) => {
+                    units.push(((byte & 0x0f) as u16) << 12 | middle << 6 | low);
+                    i += 3;
+                }
+                _ => {
+                    units.push(0xfffd);
+                    i += 1;
+                }
+            },
+            _ => {
+                units.push(0xfffd);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf16_lossy(&units)
+}
+
+/// Encodes Java's modified UTF-8, see [`decode_mutf8`]
+fn encode_mutf8(value: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(value.len());
+    for unit in value.encode_utf16() {
+        match unit {
+            0x01..=0x7f => bytes.push(unit as u8),
+            0x00 | 0x80..=0x7ff => {
+                bytes.push(0xc0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            }
+            _ => {
+                bytes.push(0xe0 | (unit >> 12) as u8);
+                bytes.push(0x80 | ((unit >> 6) & 0x3f) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            }
+        }
+    }
+    bytes
+}
+
+struct Reader<R> {
+    inner: R,
+    depth: usize,
+}
+
+/// Deeply nested lists are legal NBT but only show up in malicious files
+const MAX_DEPTH: usize = 512;
+
+impl<R: Read> Reader<R> {
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut buf = [0u8; N];
+        self.inner.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes::<1>()?[0])
+    }
+
+    fn length(&mut self) -> Result<usize> {
+        let length = i32::from_be_bytes(self.bytes()?);
+        usize::try_from(length).map_err(|_| eyre!("negative NBT length {}", length))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let length = u16::from_be_bytes(self.bytes()?) as usize;
+        let mut buf = vec![0u8; length];
+        self.inner.read_exact(&mut buf)?;
+        Ok(decode_mutf8(&buf))
+    }
+
+    fn array<T, const N: usize>(&mut self, convert: fn([u8; N]) -> T) -> Result<Vec<T>> {
+        let size = self.length()? * N;
+        // read through `take` so a corrupt length cannot allocate gigabytes up front
+        let mut buf = Vec::new();
+        (&mut self.inner).take(size as u64).read_to_end(&mut buf)?;
+        if buf.len() != size {
+            return Err(eyre!("NBT array cut short"));
+        }
+        Ok(buf
+            .chunks_exact(N)
+            .map(|chunk| convert(chunk.try_into().unwrap_or([0; N])))
+            .collect())
+    }
+
+    fn payload(&mut self, id: u8) -> Result<Tag> {
+        Ok(match id {
+            1 => Tag::Byte(i8::from_be_bytes(self.bytes()?)),
+            2 => Tag::Short(i16::from_be_bytes(self.bytes()?)),
+            3 => Tag::Int(i32::from_be_bytes(self.bytes()?)),
+            4 => Tag::Long(i64::from_be_bytes(self.bytes()?)),
+            5 => Tag::Float(f32::from_be_bytes(self.bytes()?)),
+            6 => Tag::Double(f64::from_be_bytes(self.bytes()?)),
+            7 => Tag::ByteArray(self.array(i8::from_be_bytes)?),
+            8 => Tag::String(self.string()?),
+            9 => {
+                let element = self.u8()?;
+                let length = self.length()?;
+                self.depth += 1;
+                if self.depth > MAX_DEPTH {
+                    return Err(eyre!("NBT nested too deeply"));
+                }
+                let mut items = Vec::with_capacity(length.min(1024));
+                for _ in 0..length {
+                    items.push(self.payload(element)?);
+                }
+                self.depth -= 1;
+                Tag::List(element, items)
+            }
+            10 => {
+                self.depth += 1;
+                if self.depth > MAX_DEPTH {
+                    return Err(eyre!("NBT nested too deeply"));
+                }
+                let mut entries = Vec::new();
+                loop {
+                    let id = self.u8()?;
+                    if id == 0 {
+                        break;
+                    }
+                    let name = self.string()?;
+                    entries.push((name, self.payload(id)?));
+                }
+                self.depth -= 1;
+                Tag::Compound(Compound(entries))
+            }
+            11 => Tag::IntArray(self.array(i32::from_be_bytes)?),
+            12 => Tag::LongArray(self.array(i64::from_be_bytes)?),
+            _ => return Err(eyre!("unknown NBT tag type {}", id)),
+        })
+    }
+}
+
+/// Reads an uncompressed root tag and its name
+pub fn read(reader: impl Read) -> Result<(String, Compound)> {
+    let mut reader = Reader { inner: reader, depth: 0 };
+    let id = reader.u8()?;
+    if id != 10 {
+        return Err(eyre!("NBT root is not a compound (type {})", id));
+    }
+    let name = reader.string()?;
+    match reader.payload(id)? {
+        Tag::Compound(root) => Ok((name, root)),
+        _ => unreachable!(),
+    }
+}
+
+/// Reads a file, gzip'd (`level.dat`) or not (`servers.dat`)
+pub fn read_file(path: &Path) -> Result<Compound> {
+    read_named_file(path).map(|(_, root)| root)
+}
+
+/// Like [`read_file`], with the root tag's name for writing it back
+pub fn read_named_file(path: &Path) -> Result<(String, Compound)> {
+    let mut file = BufReader::new(
+        File::open(path).wrap_err_with(|| format!("failed to open {}", path.display()))?,
+    );
+    let mut magic = [0u8; 2];
+    file.read_exact(&mut magic)?;
+    let head = std::io::Cursor::new(magic).chain(file);
+
+    let result = if magic == [0x1f, 0x8b] {
+        read(GzDecoder::new(head))
+    } else {
+        read(head)
+    };
+    result.wrap_err_with(|| format!("invalid NBT in {}", path.display()))
+}
+
+fn write_payload(out: &mut impl Write, tag: &Tag) -> Result<()> {
+    match tag {
+        Tag::Byte(value) => out.write_all(&value.to_be_bytes())?,
+        Tag::Short(value) => out.write_all(&value.to_be_bytes())?,
+        Tag::Int(value) => out.write_all(&value.to_be_bytes())?,
+        Tag::Long(value) => out.write_all(&value.to_be_bytes())?,
+        Tag::Float(value) => out.write_all(&value.to_be_bytes())?,
+        Tag::Double(value) => out.write_all(&value.to_be_bytes())?,
+        Tag::ByteArray(values) => {
+            out.write_all(&(values.len() as i32).to_be_bytes())?;
+            for value in values {
+                out.write_all(&value.to_be_bytes())?;
+            }
+        }
+        Tag::String(value) => write_string(out, value)?,
+        Tag::List(element, items) => {
+            let element = items.first().map_or(*element, Tag::id);
+            out.write_all(&[element])?;
+            out.write_all(&(items.len() as i32).to_be_bytes())?;
+            for item in items {
+                write_payload(out, item)?;
+            }
+        }
+        Tag::Compound(compound) => write_compound(out, compound)?,
+        Tag::IntArray(values) => {
+            out.write_all(&(values.len() as i32).to_be_bytes())?;
+            for value in values {
+                out.write_all(&value.to_be_bytes())?;
+            }
+        }
+        Tag::LongArray(values) => {
+            out.write_all(&(values.len() as i32).to_be_bytes())?;
+            for value in values {
+                out.write_all(&value.to_be_bytes())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_compound(out: &mut impl Write, compound: &Compound) -> Result<()> {
+    for (name, tag) in &compound.0 {
+        out.write_all(&[tag.id()])?;
+        write_string(out, name)?;
+        write_payload(out, tag)?;
+    }
+    out.write_all(&[0])?;
+    Ok(())
+}
+
+fn write_string(out: &mut impl Write, value: &str) -> Result<()> {
+    let bytes = encode_mutf8(value);
+    let length = u16::try_from(bytes.len()).map_err(|_| eyre!("NBT string too long"))?;
+    out.write_all(&length.to_be_bytes())?;
+    out.write_all(&bytes)?;
+    Ok(())
+}
+
+pub fn write(out: &mut impl Write, name: &str, root: &Compound) -> Result<()> {
+    out.write_all(&[10])?;
+    write_string(out, name)?;
+    write_compound(out, root)
+}
+
+/// Writes next to `path` and renames over it, so a crash never leaves a
+/// half-written world behind. `name` is the root tag's, as read by
+/// [`read_named_file`].
+pub fn write_file(path: &Path, name: &str, root: &Compound, compressed: bool) -> Result<()> {
+    let mut bytes = Vec::new();
+    if compressed {
+        let mut encoder = GzEncoder::new(&mut bytes, Compression::default());
+        write(&mut encoder, name, root)?;
+        encoder.finish()?;
+    } else {
+        write(&mut bytes, name, root)?;
+    }
+
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".part");
+    fs::write(&partial, bytes)?;
+    fs::rename(&partial, path).wrap_err_with(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Compound {
+        Compound(vec![
+            ("name".to_string(), Tag::String("Caf\u{e9} \u{1f600} a\0b".to_string())),
+            ("\u{1f30d} world".to_string(), Tag::Int(42)),
+            (
+                "nested".to_string(),
+                Tag::Compound(Compound(vec![
+                    ("list".to_string(), Tag::List(8, vec![Tag::String("\u{10ffff}".to_string())])),
+                    ("empty".to_string(), Tag::List(3, Vec::new())),
+                    ("longs".to_string(), Tag::LongArray(vec![-1, 2])),
+                ])),
+            ),
+        ])
+    }
+
+    #[test]
+    fn mutf8_encodes_nul_and_supplementary_characters() {
+        assert_eq!(encode_mutf8("a\0"), [b'a', 0xc0, 0x80]);
+        assert_eq!(encode_mutf8("\u{1f600}"), [0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80]);
+        assert_eq!(encode_mutf8("\u{e9}"), "\u{e9}".as_bytes());
+        for text in ["", "plain", "a\0b", "\u{1f600}\u{1f30d}", "\u{ffff}\u{10000}"] {
+            assert_eq!(decode_mutf8(&encode_mutf8(text)), text);
+        }
+    }
+
+    #[test]
+    fn read_write_round_trips_bytes() {
+        let mut original = Vec::new();
+        write(&mut original, "Data \u{1f600}", &sample()).unwrap();
+
+        let (name, root) = read(original.as_slice()).unwrap();
+        assert_eq!(name, "Data \u{1f600}");
+        assert_eq!(root, sample());
+
+        let mut rewritten = Vec::new();
+        write(&mut rewritten, &name, &root).unwrap();
+        assert_eq!(rewritten, original);
+    }
+
+    #[test]
+    fn file_round_trip_keeps_root_name() {
+        let dir = std::env::temp_dir().join(format!("mcl-nbt-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for compressed in [true, false] {
+            let path = dir.join(format!("test-{}.dat", compressed));
+            write_file(&path, "root", &sample(), compressed).unwrap();
+            let (name, root) = read_named_file(&path).unwrap();
+            write_file(&path, &name, &root, compressed).unwrap();
+            assert_eq!(read_named_file(&path).unwrap(), ("root".to_string(), sample()));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,33 @@
+use std::io::Read;
+use std::time::Instant;
+
+use color_eyre::eyre::{Result, WrapErr};
+use once_cell::sync::Lazy;
+
+use crate::debug;
+
+pub static AGENT: Lazy<ureq::Agent> = Lazy::new(|| {
+    ureq::AgentBuilder::new()
+        .user_agent(concat!("72-S/mcl/", env!("CARGO_PKG_VERSION")))
+        .build()
+});
+
+/// Downloads a whole response body into memory
+pub fn get_bytes(url: &str) -> Result<Vec<u8>> {
+    debug!(url = url; "Downloading {}", url);
+    let started = Instant::now();
+    let mut body = Vec::new();
+    AGENT
+        .get(url)
+        .call()
+        .wrap_err_with(|| format!("download of {} failed", url))?
+        .into_reader()
+        .read_to_end(&mut body)?;
+    debug!(
+        url = url,
+        bytes = body.len(),
+        duration_ms = started.elapsed().as_millis() as u64;
+        "Downloaded {}", url
+    );
+    Ok(body)
+}
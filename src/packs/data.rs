@@ -0,0 +1,197 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+
+use crate::{
+    debug,
+    instance::Instance,
+    modrinth,
+    nbt::{Compound, Tag},
+    worlds::{self, World},
+};
+
+use super::Pack;
+
+/// Whether a pack is turned on in the world's `level.dat`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Enabled,
+    Disabled,
+    /// Not listed yet, the game enables it the next time the world loads
+    New,
+}
+
+impl Status {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Status::Enabled => "enabled",
+            Status::Disabled => "disabled",
+            Status::New => "new",
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        *self != Status::Disabled
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DataPack {
+    pub pack: Pack,
+    pub status: Status,
+}
+
+impl DataPack {
+    /// How the pack is referenced in the `DataPacks` lists
+    pub fn key(&self) -> String {
+        format!("file/{}", self.pack.name)
+    }
+}
+
+pub fn datapacks_dir(world: &World) -> PathBuf {
+    world.path.join("datapacks")
+}
+
+fn string_list(compound: Option<&Compound>, key: &str) -> Vec<String> {
+    compound
+        .map(|compound| compound.list(key))
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|tag| tag.as_str().map(String::from))
+        .collect()
+}
+
+/// `Enabled` and `Disabled` from `level.dat`
+fn lists(world: &World) -> Result<(Vec<String>, Vec<String>)> {
+    let root = crate::nbt::read_file(&world.level_dat())?;
+    let packs = root.compound("Data").and_then(|data| data.compound("DataPacks"));
+    Ok((string_list(packs, "Enabled"), string_list(packs, "Disabled")))
+}
+
+/// Lists the packs in the world's `datapacks/` folder, sorted by name
+pub fn list(world: &World) -> Vec<DataPack> {
+    let (enabled, disabled) = lists(world).unwrap_or_else(|err| {
+        debug!("Could not read data packs of {}: {:#}", world.folder, err);
+        Default::default()
+    });
+
+    super::list(&datapacks_dir(world))
+        .into_iter()
+        .map(|pack| {
+            let key = format!("file/{}", pack.name);
+            let status = if enabled.contains(&key) {
+                Status::Enabled
+            } else if disabled.contains(&key) {
+                Status::Disabled
+            } else {
+                Status::New
+            };
+            DataPack { pack, status }
+        })
+        .collect()
+}
+
+/// Finds a pack by file or folder name
+pub fn find(world: &World, name: &str) -> Result<DataPack> {
+    list(world)
+        .into_iter()
+        .find(|pack| pack.pack.name == name || pack.pack.name.trim_end_matches(".zip") == name)
+        .ok_or_else(|| eyre!("no data pack '{}' in {}", name, world.name))
+}
+
+/// Moves `key` into the `Enabled` or `Disabled` list of `Data.DataPacks`,
+/// or out of both when `enabled` is `None`
+fn set_listed(data: &mut Compound, key: &str, enabled: Option<bool>) {
+    if data.compound("DataPacks").is_none() {
+        data.insert("DataPacks", Tag::Compound(Compound::default()));
+    }
+    let Some(packs) = data.compound_mut("DataPacks") else {
+        return;
+    };
+
+    for (list, wanted) in [("Enabled", Some(true)), ("Disabled", Some(false))] {
+        let mut entries: Vec<Tag> = packs
+            .list(list)
+            .iter()
+            .filter(|tag| tag.as_str() != Some(key))
+            .cloned()
+            .collect();
+        if enabled == wanted {
+            entries.push(Tag::String(key.to_string()));
+        }
+        packs.insert(list, Tag::List(8, entries));
+    }
+}
+
+pub fn set_enabled(world: &World, pack: &DataPack, enabled: bool) -> Result<()> {
+    let key = pack.key();
+    worlds::edit_level(world, |data| set_listed(data, &key, Some(enabled)))
+}
+
+/// Deletes the pack and drops it from `level.dat`
+pub fn remove(world: &World, pack: &DataPack) -> Result<()> {
+    if pack.pack.folder {
+        fs::remove_dir_all(&pack.pack.path)?;
+    } else {
+        fs::remove_file(&pack.pack.path)?;
+    }
+    let key = pack.key();
+    worlds::edit_level(world, |data| set_listed(data, &key, None))
+}
+
+fn enable_installed(world: &World, path: &Path) -> Result<DataPack> {
+    let pack = Pack::from_path(path.to_path_buf())
+        .ok_or_else(|| eyre!("{} is not a data pack", path.display()))?;
+    let pack = DataPack {
+        pack,
+        status: Status::Enabled,
+    };
+    set_enabled(world, &pack, true)?;
+    Ok(pack)
+}
+
+/// Copies a zipped data pack into the world and enables it
+pub fn install_file(world: &World, source: &Path) -> Result<DataPack> {
+    let name = source
+        .file_name()
+        .ok_or_else(|| eyre!("{} is not a file", source.display()))?;
+    if super::read_file(source, "pack.mcmeta").is_none() {
+        return Err(eyre!("{} has no pack.mcmeta", source.display()));
+    }
+
+    let dir = datapacks_dir(world);
+    fs::create_dir_all(&dir)?;
+    let target = dir.join(name);
+    fs::copy(source, &target).wrap_err_with(|| format!("failed to copy {}", source.display()))?;
+    enable_installed(world, &target)
+}
+
+/// Downloads a Modrinth data pack for the instance's game version and
+/// enables it
+pub fn install_modrinth(instance: &Instance, world: &World, project: &str) -> Result<DataPack> {
+    let versions = modrinth::project_versions(project, &["datapack"], &[instance.game_version.as_str()])?;
+    let version = versions
+        .iter()
+        .find(|version| version.version_type == "release")
+        .or_else(|| versions.first())
+        .ok_or_else(|| {
+            eyre!("{} has no data pack versions for {}", project, instance.game_version)
+        })?;
+    let file = version
+        .primary_file()
+        .ok_or_else(|| eyre!("{} {} has no files", project, version.version_number))?;
+
+    let path = modrinth::download(file, &datapacks_dir(world))?;
+    enable_installed(world, &path)
+}
+
+/// Installs from a local zip if `source` is one, from Modrinth otherwise
+pub fn install(instance: &Instance, world: &World, source: &str) -> Result<DataPack> {
+    let path = Path::new(source);
+    if path.is_file() {
+        install_file(world, path)
+    } else {
+        install_modrinth(instance, world, source)
+    }
+}
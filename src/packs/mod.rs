@@ -0,0 +1,153 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value as Json;
+use zip::ZipArchive;
+
+pub mod data;
+pub mod resource;
+pub mod shader;
+
+/// A pack in a `resourcepacks/`-style folder, either a zip or a directory
+#[derive(Debug, Clone)]
+pub struct Pack {
+    pub path: PathBuf,
+    /// File or folder name, as referenced from `options.txt`
+    pub name: String,
+    pub folder: bool,
+    pub description: String,
+    pub pack_format: Option<u32>,
+    /// Inclusive format range from `supported_formats`, if given
+    pub supported: Option<(u32, u32)>,
+}
+
+impl Pack {
+    /// Reads a pack, returning `None` for anything that is not one
+    pub fn from_path(path: PathBuf) -> Option<Pack> {
+        let name = path.file_name()?.to_string_lossy().to_string();
+        let folder = path.is_dir();
+        if !folder && !name.to_lowercase().ends_with(".zip") {
+            return None;
+        }
+
+        let meta: Option<Json> =
+            read_file(&path, "pack.mcmeta").and_then(|bytes| serde_json::from_slice(&bytes).ok());
+        let pack = meta.as_ref().and_then(|meta| meta.get("pack"));
+        let description = pack
+            .and_then(|pack| pack.get("description"))
+            .map(text_component)
+            .unwrap_or_default();
+        let pack_format = pack
+            .and_then(|pack| pack.get("pack_format"))
+            .and_then(Json::as_u64)
+            .map(|format| format as u32);
+        let supported = pack
+            .and_then(|pack| pack.get("supported_formats"))
+            .and_then(format_range);
+
+        Some(Pack {
+            path,
+            name,
+            folder,
+            description,
+            pack_format,
+            supported,
+        })
+    }
+
+    /// Whether the pack accepts the given format
+    pub fn supports(&self, format: u32) -> Option<bool> {
+        match (self.supported, self.pack_format) {
+            (Some((min, max)), _) => Some(min <= format && format <= max),
+            (None, Some(own)) => Some(own == format),
+            (None, None) => None,
+        }
+    }
+
+    pub fn read(&self, entry: &str) -> Option<Vec<u8>> {
+        read_file(&self.path, entry)
+    }
+
+    pub fn icon(&self) -> Option<Vec<u8>> {
+        self.read("pack.png")
+    }
+}
+
+/// Reads a file out of a zip or folder pack
+pub fn read_file(path: &Path, entry: &str) -> Option<Vec<u8>> {
+    if path.is_dir() {
+        return fs::read(path.join(entry)).ok();
+    }
+
+    let mut archive = ZipArchive::new(File::open(path).ok()?).ok()?;
+    let mut file = archive.by_name(entry).ok()?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).ok()?;
+    Some(bytes)
+}
+
+/// Lists the packs in `dir`, sorted by name
+pub fn list(dir: &Path) -> Vec<Pack> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut packs: Vec<Pack> = entries
+        .flatten()
+        .filter_map(|entry| Pack::from_path(entry.path()))
+        .collect();
+    packs.sort_by_key(|pack| pack.name.to_lowercase());
+    packs
+}
+
+/// Accepts `15`, `[15, 18]` and `{ "min_inclusive": 15, "max_inclusive": 18 }`
+fn format_range(value: &Json) -> Option<(u32, u32)> {
+    let number = |value: Option<&Json>| value.and_then(Json::as_u64).map(|n| n as u32);
+    match value {
+        Json::Number(_) => number(Some(value)).map(|n| (n, n)),
+        Json::Array(values) => Some((number(values.first())?, number(values.get(1))?)),
+        Json::Object(map) => Some((
+            number(map.get("min_inclusive"))?,
+            number(map.get("max_inclusive"))?,
+        )),
+        _ => None,
+    }
+}
+
+/// Flattens a chat text component to plain text, dropping `§` formatting codes
+pub fn text_component(value: &Json) -> String {
+    fn collect(value: &Json, out: &mut String) {
+        match value {
+            Json::String(text) => out.push_str(text),
+            Json::Array(parts) => parts.iter().for_each(|part| collect(part, out)),
+            Json::Object(map) => {
+                if let Some(text) = map.get("text").or_else(|| map.get("translate")) {
+                    collect(text, out);
+                }
+                if let Some(extra) = map.get("extra") {
+                    collect(extra, out);
+                }
+            }
+            Json::Number(number) => out.push_str(&number.to_string()),
+            _ => {}
+        }
+    }
+
+    let mut raw = String::new();
+    collect(value, &mut raw);
+    strip_formatting(&raw)
+}
+
+pub fn strip_formatting(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
@@ -0,0 +1,183 @@
+use std::cmp::Ordering;
+
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+    instance::{options::Options, Instance},
+    mods::version,
+};
+
+use super::Pack;
+
+/// First release using each resource pack format
+const FORMATS: &[(&str, u32)] = &[
+    ("1.6.1", 1),
+    ("1.9", 2),
+    ("1.11", 3),
+    ("1.13", 4),
+    ("1.15", 5),
+    ("1.16.2", 6),
+    ("1.17", 7),
+    ("1.18", 8),
+    ("1.19", 9),
+    ("1.19.3", 12),
+    ("1.19.4", 13),
+    ("1.20", 15),
+    ("1.20.2", 18),
+    ("1.20.3", 22),
+    ("1.20.5", 32),
+    ("1.21", 34),
+    ("1.21.2", 42),
+    ("1.21.4", 46),
+    ("1.21.5", 55),
+    ("1.21.6", 63),
+    ("1.21.7", 64),
+];
+
+/// Versions from here on are newer than the table above
+const UNKNOWN_FROM: &str = "1.21.9";
+
+const ENABLED_KEY: &str = "resourcePacks";
+const INCOMPATIBLE_KEY: &str = "incompatibleResourcePacks";
+
+/// Resource pack format a release expects, `None` for snapshots and
+/// versions newer than the launcher
+pub fn pack_format(game_version: &str) -> Option<u32> {
+    if !version::comparable(game_version) || version::compare(game_version, UNKNOWN_FROM).is_ge() {
+        return None;
+    }
+    FORMATS
+        .iter()
+        .rev()
+        .find(|(first, _)| version::compare(game_version, first) != Ordering::Less)
+        .map(|(_, format)| *format)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compatibility {
+    Compatible,
+    /// Made for an older version of the game
+    TooOld,
+    TooNew,
+    Unknown,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResourcePack {
+    pub pack: Pack,
+    pub enabled: bool,
+    pub compatibility: Compatibility,
+}
+
+impl ResourcePack {
+    /// How the pack is referenced in `options.txt`
+    pub fn key(&self) -> String {
+        format!("file/{}", self.pack.name)
+    }
+}
+
+fn compatibility(pack: &Pack, expected: Option<u32>) -> Compatibility {
+    let Some(expected) = expected else {
+        return Compatibility::Unknown;
+    };
+    match pack.supports(expected) {
+        Some(true) => Compatibility::Compatible,
+        Some(false) => {
+            let own = pack.supported.map(|(_, max)| max).or(pack.pack_format).unwrap_or(0);
+            if own < expected {
+                Compatibility::TooOld
+            } else {
+                Compatibility::TooNew
+            }
+        }
+        None => Compatibility::Unknown,
+    }
+}
+
+/// Lists the instance's resource packs, enabled ones first from highest
+/// to lowest priority, then the rest by name
+pub fn list(instance: &Instance) -> Vec<ResourcePack> {
+    let expected = pack_format(&instance.game_version);
+    let selected = Options::load(instance)
+        .map(|options| options.get_list(ENABLED_KEY))
+        .unwrap_or_default();
+
+    let mut packs: Vec<ResourcePack> = super::list(&instance.path.join("resourcepacks"))
+        .into_iter()
+        .map(|pack| ResourcePack {
+            compatibility: compatibility(&pack, expected),
+            enabled: false,
+            pack,
+        })
+        .collect();
+    for pack in &mut packs {
+        pack.enabled = selected.contains(&pack.key());
+    }
+
+    // options.txt lists packs from lowest to highest priority
+    let position = |pack: &ResourcePack| selected.iter().position(|key| *key == pack.key());
+    packs.sort_by_key(|pack| match position(pack) {
+        Some(index) => (0, usize::MAX - index),
+        None => (1, 0),
+    });
+    packs
+}
+
+/// Adds or removes the pack from the selection. Newly enabled packs get
+/// the highest priority.
+pub fn set_enabled(instance: &Instance, pack: &ResourcePack, enabled: bool) -> Result<()> {
+    let mut options = Options::load(instance)?;
+    let key = pack.key();
+
+    let mut selected = options.get_list(ENABLED_KEY);
+    if selected.is_empty() {
+        selected.push("vanilla".to_string());
+    }
+    selected.retain(|entry| *entry != key);
+
+    // the game drops incompatible packs unless they were confirmed
+    let mut incompatible = options.get_list(INCOMPATIBLE_KEY);
+    incompatible.retain(|entry| *entry != key);
+
+    if enabled {
+        selected.push(key.clone());
+        if matches!(pack.compatibility, Compatibility::TooOld | Compatibility::TooNew) {
+            incompatible.push(key);
+        }
+    }
+
+    options.set_list(ENABLED_KEY, &selected);
+    options.set_list(INCOMPATIBLE_KEY, &incompatible);
+    options.save()
+}
+
+/// Moves an enabled pack one step up (higher priority) or down
+pub fn move_pack(instance: &Instance, pack: &ResourcePack, up: bool) -> Result<()> {
+    let mut options = Options::load(instance)?;
+    let key = pack.key();
+    let mut selected = options.get_list(ENABLED_KEY);
+    let index = selected
+        .iter()
+        .position(|entry| *entry == key)
+        .ok_or_else(|| eyre!("{} is not enabled", pack.pack.name))?;
+
+    // only swap with other packs, built-in entries like "vanilla" stay put
+    let is_file = |entry: &String| entry.starts_with("file/");
+    let other = if up {
+        selected
+            .iter()
+            .enumerate()
+            .skip(index + 1)
+            .find(|(_, entry)| is_file(entry))
+            .map(|(i, _)| i)
+    } else {
+        selected[..index].iter().rposition(is_file)
+    };
+    let Some(other) = other else {
+        return Ok(());
+    };
+
+    selected.swap(index, other);
+    options.set_list(ENABLED_KEY, &selected);
+    options.save()
+}
@@ -0,0 +1,174 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+
+use crate::{debug, instance::Instance, modrinth, mods};
+
+use super::Pack;
+
+/// Mod that renders shader packs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderLoader {
+    /// Iris or its Forge port Oculus
+    Iris,
+    OptiFine,
+}
+
+impl ShaderLoader {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ShaderLoader::Iris => "Iris",
+            ShaderLoader::OptiFine => "OptiFine",
+        }
+    }
+
+    /// Loader name on Modrinth
+    fn modrinth(&self) -> &'static str {
+        match self {
+            ShaderLoader::Iris => "iris",
+            ShaderLoader::OptiFine => "optifine",
+        }
+    }
+
+    /// File holding the selected shader pack, relative to the instance
+    fn config_file(&self) -> &'static str {
+        match self {
+            ShaderLoader::Iris => "config/iris.properties",
+            ShaderLoader::OptiFine => "optionsshaders.txt",
+        }
+    }
+}
+
+/// Finds the shader loader among the enabled mods, or an OptiFine version
+pub fn detect(instance: &Instance) -> Option<ShaderLoader> {
+    let optifine_version = instance
+        .launch
+        .version
+        .as_deref()
+        .is_some_and(|version| version.to_lowercase().contains("optifine"));
+    if optifine_version {
+        return Some(ShaderLoader::OptiFine);
+    }
+
+    for info in mods::load(instance).iter().filter(|info| info.file.enabled) {
+        match info.id() {
+            Some("iris" | "oculus") => return Some(ShaderLoader::Iris),
+            Some("optifine") => return Some(ShaderLoader::OptiFine),
+            _ if info.file.file_name.to_lowercase().contains("optifine") => {
+                return Some(ShaderLoader::OptiFine)
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+pub fn shaderpacks_dir(instance: &Instance) -> PathBuf {
+    instance.path.join("shaderpacks")
+}
+
+/// Zips and folders in `shaderpacks/`
+pub fn list(instance: &Instance) -> Vec<Pack> {
+    super::list(&shaderpacks_dir(instance))
+}
+
+/// Warning shown when packs are installed that nothing can load
+pub fn warning(instance: &Instance, packs: &[Pack], loader: Option<ShaderLoader>) -> Option<String> {
+    if packs.is_empty() || loader.is_some() {
+        return None;
+    }
+    Some(format!(
+        "{} has {} shader packs but neither Iris nor OptiFine is installed",
+        instance.name,
+        packs.len()
+    ))
+}
+
+/// Reads a `key=value` properties file, keeping every line for rewriting
+fn read_properties(path: &Path) -> Vec<String> {
+    fs::read_to_string(path)
+        .map(|raw| raw.lines().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+fn property<'a>(lines: &'a [String], key: &str) -> Option<&'a str> {
+    lines.iter().find_map(|line| {
+        let (name, value) = line.split_once('=')?;
+        (name.trim() == key).then(|| value.trim())
+    })
+}
+
+fn set_property(lines: &mut Vec<String>, key: &str, value: &str) {
+    let entry = format!("{}={}", key, value);
+    match lines
+        .iter_mut()
+        .find(|line| line.split_once('=').is_some_and(|(name, _)| name.trim() == key))
+    {
+        Some(line) => *line = entry,
+        None => lines.push(entry),
+    }
+}
+
+/// Name of the shader pack the loader will use, if shaders are on
+pub fn active(instance: &Instance, loader: ShaderLoader) -> Option<String> {
+    let lines = read_properties(&instance.path.join(loader.config_file()));
+    let pack = property(&lines, "shaderPack")?;
+    let enabled = match loader {
+        ShaderLoader::Iris => property(&lines, "enableShaders") != Some("false"),
+        ShaderLoader::OptiFine => pack != "OFF" && pack != "(internal)",
+    };
+    (enabled && !pack.is_empty()).then(|| pack.to_string())
+}
+
+/// Selects a shader pack by file name, or turns shaders off with `None`
+pub fn set_active(instance: &Instance, loader: ShaderLoader, pack: Option<&str>) -> Result<()> {
+    if let Some(pack) = pack {
+        if !shaderpacks_dir(instance).join(pack).exists() {
+            return Err(eyre!("shader pack {} is not installed", pack));
+        }
+    }
+
+    let path = instance.path.join(loader.config_file());
+    let mut lines = read_properties(&path);
+    match (loader, pack) {
+        (ShaderLoader::Iris, Some(pack)) => {
+            set_property(&mut lines, "shaderPack", pack);
+            set_property(&mut lines, "enableShaders", "true");
+        }
+        (ShaderLoader::Iris, None) => set_property(&mut lines, "enableShaders", "false"),
+        (ShaderLoader::OptiFine, pack) => set_property(&mut lines, "shaderPack", pack.unwrap_or("OFF")),
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut raw = lines.join("\n");
+    raw.push('\n');
+    fs::write(&path, raw).wrap_err_with(|| format!("failed to write {}", path.display()))
+}
+
+/// Downloads the newest release of a Modrinth shader project into
+/// `shaderpacks/`, preferring versions made for the installed loader
+pub fn install(instance: &Instance, project: &str, loader: Option<ShaderLoader>) -> Result<PathBuf> {
+    let game_versions = [instance.game_version.as_str()];
+    let mut versions = modrinth::project_versions(project, &[], &game_versions)?;
+    if versions.is_empty() {
+        // most shader packs are not tied to a game version
+        debug!("No {} versions for {}, trying all", project, instance.game_version);
+        versions = modrinth::project_versions(project, &[], &[])?;
+    }
+
+    let wanted = loader.map(|loader| loader.modrinth());
+    let version = versions
+        .iter()
+        .filter(|version| version.version_type == "release")
+        .find(|version| wanted.is_none_or(|loader| version.loaders.iter().any(|l| l == loader)))
+        .or_else(|| versions.first())
+        .ok_or_else(|| eyre!("{} has no versions on Modrinth", project))?;
+    let file = version
+        .primary_file()
+        .ok_or_else(|| eyre!("{} {} has no files", project, version.version_number))?;
+
+    modrinth::download(file, &shaderpacks_dir(instance))
+}
@@ -0,0 +1,180 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+    debug, info,
+    instance::{Instance, Loader},
+    modrinth, mods,
+};
+
+use super::{hash, Index, IndexFile, IndexRef, MetaFile, Pack, PACK_FORMAT};
+
+/// Hash format used for everything written by the export
+const HASH_FORMAT: &str = "sha256";
+
+/// Instance folders shipped as plain files alongside the mods
+const EXPORTED_DIRS: &[&str] = &["config", "defaultconfigs", "kubejs", "resourcepacks", "shaderpacks"];
+
+/// Loader version out of an installer-style version id, the inverse of
+/// [`super::launch_version`]
+fn loader_version(instance: &Instance) -> Option<String> {
+    let id = instance.launch.version.as_deref()?;
+    let suffix = format!("-{}", instance.game_version);
+    match instance.loader {
+        Loader::Vanilla => None,
+        Loader::Fabric => id.strip_prefix("fabric-loader-")?.strip_suffix(&suffix).map(String::from),
+        Loader::Quilt => id.strip_prefix("quilt-loader-")?.strip_suffix(&suffix).map(String::from),
+        Loader::Forge => id
+            .strip_prefix(&format!("{}-forge-", instance.game_version))
+            .or_else(|| id.split_once("-forge-").map(|(_, version)| version))
+            .map(String::from),
+        Loader::NeoForge => id.strip_prefix("neoforge-").map(String::from),
+    }
+}
+
+fn write_file(dir: &Path, relative: &str, bytes: &[u8], files: &mut Vec<IndexFile>, metafile: bool) -> Result<()> {
+    let target = dir.join(relative);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&target, bytes)?;
+    files.push(IndexFile {
+        file: relative.to_string(),
+        hash: hash(HASH_FORMAT, bytes).unwrap_or_default(),
+        hash_format: None,
+        alias: None,
+        metafile,
+        preserve: false,
+    });
+    Ok(())
+}
+
+fn copy_tree(root: &Path, dir: &Path, prefix: &str, files: &mut Vec<IndexFile>) -> Result<()> {
+    let Ok(entries) = fs::read_dir(root.join(prefix)) else {
+        return Ok(());
+    };
+    for entry in entries.flatten() {
+        let relative = format!("{}/{}", prefix, entry.file_name().to_string_lossy());
+        if entry.path().is_dir() {
+            copy_tree(root, dir, &relative, files)?;
+        } else {
+            write_file(dir, &relative, &fs::read(entry.path())?, files, false)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the instance as a packwiz tree into `dir`. Enabled mods known to
+/// Modrinth become `.pw.toml` metafiles, anything else is copied as is.
+/// Returns the number of files in the index.
+pub fn export(instance: &Instance, dir: &Path) -> Result<usize> {
+    if dir.join("pack.toml").exists() {
+        return Err(eyre!("{} already contains a pack.toml", dir.display()));
+    }
+    fs::create_dir_all(dir)?;
+
+    let jars: Vec<mods::ModFile> = mods::list(instance)
+        .into_iter()
+        .filter(|file| file.enabled)
+        .collect();
+    let mut hashes = Vec::new();
+    for jar in &jars {
+        hashes.push(jar.sha1()?);
+    }
+    let versions = modrinth::versions_from_hashes(&hashes).unwrap_or_else(|err| {
+        info!("Modrinth lookup failed, copying all jars into the pack: {:#}", err);
+        Default::default()
+    });
+
+    let mut files = Vec::new();
+    for (jar, sha1) in jars.iter().zip(&hashes) {
+        let found = versions.get(sha1).and_then(|version| {
+            version
+                .files
+                .iter()
+                .find(|file| &file.hashes.sha1 == sha1)
+                .map(|file| (version, file))
+        });
+
+        let Some((version, file)) = found else {
+            debug!("{} is not on Modrinth, copying it", jar.file_name);
+            let relative = format!("mods/{}", jar.file_name);
+            write_file(dir, &relative, &fs::read(&jar.path)?, &mut files, false)?;
+            continue;
+        };
+
+        let (hash_format, hash) = if file.hashes.sha512.is_empty() {
+            ("sha1", file.hashes.sha1.clone())
+        } else {
+            ("sha512", file.hashes.sha512.clone())
+        };
+        let mut update = toml::Table::new();
+        let mut modrinth = toml::Table::new();
+        modrinth.insert("mod-id".into(), version.project_id.clone().into());
+        modrinth.insert("version".into(), version.id.clone().into());
+        update.insert("modrinth".into(), modrinth.into());
+
+        let meta = MetaFile {
+            name: jar.file_name.trim_end_matches(".jar").to_string(),
+            filename: jar.file_name.clone(),
+            side: Some("both".into()),
+            download: super::Download {
+                url: Some(file.url.clone()),
+                hash_format: hash_format.into(),
+                hash,
+                mode: None,
+            },
+            update: Some(update),
+        };
+        let stem = jar.file_name.trim_end_matches(".jar");
+        let relative = format!("mods/{}.pw.toml", stem);
+        write_file(dir, &relative, toml::to_string_pretty(&meta)?.as_bytes(), &mut files, true)?;
+    }
+
+    for prefix in EXPORTED_DIRS {
+        copy_tree(&instance.path, dir, prefix, &mut files)?;
+    }
+    files.sort_by(|a, b| a.file.cmp(&b.file));
+
+    let count = files.len();
+    let index = toml::to_string_pretty(&Index {
+        hash_format: HASH_FORMAT.into(),
+        files,
+    })?;
+    fs::write(dir.join("index.toml"), &index)?;
+
+    let mut versions = BTreeMap::new();
+    versions.insert("minecraft".to_string(), instance.game_version.clone());
+    if instance.loader != Loader::Vanilla {
+        match loader_version(instance) {
+            Some(version) => {
+                versions.insert(instance.loader.as_str().to_string(), version);
+            }
+            None => {
+                info!(
+                    "Cannot tell the {} version of {}, add it to pack.toml by hand",
+                    instance.loader.as_str(),
+                    instance.name
+                );
+            }
+        }
+    }
+
+    let pack = Pack {
+        name: instance.name.clone(),
+        author: None,
+        version: None,
+        pack_format: PACK_FORMAT.into(),
+        index: IndexRef {
+            file: "index.toml".into(),
+            hash_format: HASH_FORMAT.into(),
+            hash: hash(HASH_FORMAT, index.as_bytes()).unwrap_or_default(),
+        },
+        versions,
+    };
+    fs::write(dir.join("pack.toml"), toml::to_string_pretty(&pack)?)?;
+    Ok(count)
+}
@@ -0,0 +1,308 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde::{Deserialize, Serialize};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::{
+    info,
+    instance::{self, Instance, LaunchSettings, Loader},
+    net,
+};
+
+pub mod export;
+pub mod sync;
+
+pub const PACK_FORMAT: &str = "packwiz:1.1.0";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexRef {
+    pub file: String,
+    #[serde(rename = "hash-format")]
+    pub hash_format: String,
+    pub hash: String,
+}
+
+/// `pack.toml`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pack {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(rename = "pack-format", default)]
+    pub pack_format: String,
+    pub index: IndexRef,
+    /// `minecraft` plus one loader, e.g. `fabric = "0.15.0"`
+    pub versions: BTreeMap<String, String>,
+}
+
+impl Pack {
+    pub fn loader(&self) -> (Loader, Option<&str>) {
+        for (key, loader) in [
+            ("neoforge", Loader::NeoForge),
+            ("forge", Loader::Forge),
+            ("quilt", Loader::Quilt),
+            ("fabric", Loader::Fabric),
+        ] {
+            if let Some(version) = self.versions.get(key) {
+                return (loader, Some(version));
+            }
+        }
+        (Loader::Vanilla, None)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexFile {
+    pub file: String,
+    pub hash: String,
+    #[serde(rename = "hash-format", default, skip_serializing_if = "Option::is_none")]
+    pub hash_format: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub metafile: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub preserve: bool,
+}
+
+/// `index.toml`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Index {
+    #[serde(rename = "hash-format")]
+    pub hash_format: String,
+    #[serde(default)]
+    pub files: Vec<IndexFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Download {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(rename = "hash-format")]
+    pub hash_format: String,
+    pub hash: String,
+    /// `metadata:curseforge` downloads have no URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+}
+
+/// A `.pw.toml` metafile describing a single downloadable file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetaFile {
+    pub name: String,
+    pub filename: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub side: Option<String>,
+    pub download: Download,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub update: Option<toml::Table>,
+}
+
+/// Where a pack is read from, a local checkout or a served repository
+#[derive(Debug, Clone)]
+pub enum Location {
+    Local(PathBuf),
+    Remote(String),
+}
+
+impl Location {
+    pub fn parse(value: &str) -> Location {
+        if value.starts_with("http://") || value.starts_with("https://") {
+            Location::Remote(value.to_string())
+        } else {
+            Location::Local(PathBuf::from(value))
+        }
+    }
+
+    /// Resolves `path` relative to the directory containing this file
+    pub fn sibling(&self, path: &str) -> Location {
+        match self {
+            Location::Local(file) => {
+                Location::Local(file.parent().unwrap_or(Path::new("")).join(path))
+            }
+            Location::Remote(url) => {
+                let base = url.rsplit_once('/').map_or(url.as_str(), |(base, _)| base);
+                Location::Remote(format!("{}/{}", base, path.trim_start_matches("./")))
+            }
+        }
+    }
+
+    pub fn read(&self) -> Result<Vec<u8>> {
+        match self {
+            Location::Local(path) => {
+                fs::read(path).wrap_err_with(|| format!("failed to read {}", path.display()))
+            }
+            Location::Remote(url) => net::get_bytes(url),
+        }
+    }
+
+    pub fn read_toml<T: for<'de> Deserialize<'de>>(&self) -> Result<T> {
+        let bytes = self.read()?;
+        toml::from_str(&String::from_utf8_lossy(&bytes))
+            .wrap_err_with(|| format!("invalid packwiz file {}", self))
+    }
+}
+
+impl std::fmt::Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Location::Local(path) => write!(f, "{}", path.display()),
+            Location::Remote(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+/// CurseForge's file fingerprint: 32-bit MurmurHash2 with seed 1 over the
+/// file with tabs, newlines, carriage returns and spaces left out
+fn murmur2(bytes: &[u8]) -> u32 {
+    const M: u32 = 0x5bd1_e995;
+    let data: Vec<u8> = bytes
+        .iter()
+        .copied()
+        .filter(|byte| !matches!(byte, 9 | 10 | 13 | 32))
+        .collect();
+
+    let mut hash = 1 ^ data.len() as u32;
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> 24;
+        k = k.wrapping_mul(M);
+        hash = hash.wrapping_mul(M) ^ k;
+    }
+    let rest = chunks.remainder();
+    if !rest.is_empty() {
+        for (i, byte) in rest.iter().enumerate() {
+            hash ^= (*byte as u32) << (8 * i);
+        }
+        hash = hash.wrapping_mul(M);
+    }
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(M);
+    hash ^ (hash >> 15)
+}
+
+/// Hashes `bytes` in a packwiz hash format, `None` for unknown formats.
+/// murmur2 is written in decimal like packwiz does.
+pub fn hash(format: &str, bytes: &[u8]) -> Option<String> {
+    match format {
+        "sha1" => Some(format!("{:x}", Sha1::digest(bytes))),
+        "sha256" => Some(format!("{:x}", Sha256::digest(bytes))),
+        "sha512" => Some(format!("{:x}", Sha512::digest(bytes))),
+        "md5" => Some(format!("{:x}", Md5::digest(bytes))),
+        "murmur2" => Some(murmur2(bytes).to_string()),
+        _ => None,
+    }
+}
+
+/// Joins a pack-relative path onto `base`, rejecting paths escaping it
+pub fn safe_join(base: &Path, relative: &str) -> Result<PathBuf> {
+    let relative = Path::new(relative);
+    if relative
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(eyre!("refusing unsafe pack path {}", relative.display()));
+    }
+    Ok(base.join(relative))
+}
+
+/// Launcher version id the usual loader installers create
+pub fn launch_version(minecraft: &str, loader: Loader, version: Option<&str>) -> Option<String> {
+    let version = version?;
+    match loader {
+        Loader::Vanilla => None,
+        Loader::Fabric => Some(format!("fabric-loader-{}-{}", version, minecraft)),
+        Loader::Quilt => Some(format!("quilt-loader-{}-{}", version, minecraft)),
+        Loader::Forge => Some(format!("{}-forge-{}", minecraft, version)),
+        Loader::NeoForge => Some(format!("neoforge-{}", version)),
+    }
+}
+
+fn slugify(name: &str) -> String {
+    let slug: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    slug.split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Creates an instance from a `pack.toml` path or URL and installs its files
+pub fn import(source: &str, id: Option<&str>) -> Result<Instance> {
+    let source = match Location::parse(source) {
+        Location::Local(path) => fs::canonicalize(&path)
+            .wrap_err_with(|| format!("{} does not exist", path.display()))?
+            .to_string_lossy()
+            .to_string(),
+        Location::Remote(url) => url,
+    };
+    let pack: Pack = Location::parse(&source).read_toml()?;
+
+    let minecraft = pack
+        .versions
+        .get("minecraft")
+        .cloned()
+        .ok_or_else(|| eyre!("pack '{}' does not specify a minecraft version", pack.name))?;
+    let (loader, loader_version) = pack.loader();
+
+    let id = id.map(String::from).unwrap_or_else(|| slugify(&pack.name));
+    let path = instance::get_instances_path().join(&id);
+    if path.exists() {
+        return Err(eyre!("instance '{}' already exists", id));
+    }
+
+    let instance = Instance {
+        id,
+        path,
+        name: pack.name.clone(),
+        launch: LaunchSettings {
+            version: launch_version(&minecraft, loader, loader_version),
+            ..Default::default()
+        },
+        game_version: minecraft,
+        loader,
+        mods: Default::default(),
+        backups: Default::default(),
+        packwiz: Some(source.clone()),
+    };
+    instance.save()?;
+    info!(instance = instance.id; "Created instance '{}' from {}", instance.id, source);
+
+    sync::sync(&instance, &source)?;
+    Ok(instance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn murmur2_fingerprints() {
+        assert_eq!(murmur2(b""), 1540447798);
+        assert_eq!(murmur2(b"a"), 626045324);
+        assert_eq!(murmur2(b"The quick brown fox jumps over the lazy dog"), 3751777527);
+        // whitespace doesn't count, so line endings don't change it
+        assert_eq!(murmur2(b"key = value\r\n\tother\n"), 3040262904);
+        assert_eq!(murmur2(b"key=value\nother"), 3040262904);
+    }
+
+    #[test]
+    fn hash_formats() {
+        assert_eq!(hash("md5", b"").as_deref(), Some("d41d8cd98f00b204e9800998ecf8427e"));
+        assert_eq!(hash("sha1", b"").as_deref(), Some("da39a3ee5e6b4b0d3255bfef95601890afd80709"));
+        assert_eq!(hash("murmur2", b"hello world\n").as_deref(), Some("2824650221"));
+        assert_eq!(hash("crc32", b""), None);
+    }
+}
@@ -0,0 +1,312 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{debug, info, instance::Instance, mods::DISABLED_SUFFIX, warn};
+
+use super::{hash, safe_join, Index, IndexFile, Location, MetaFile, Pack};
+
+/// Files installed by the last sync, relative to the instance directory,
+/// so files dropped from the pack can be removed again
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct State {
+    files: BTreeSet<String>,
+    /// What each file was verified against when it was installed
+    #[serde(default)]
+    verified: BTreeMap<String, Verified>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Verified {
+    format: String,
+    hash: String,
+    size: u64,
+    /// Modification time in nanoseconds, so an edit that keeps the size
+    /// still gets the file hashed again
+    #[serde(default)]
+    modified: Option<u64>,
+}
+
+/// Size and modification time of a file, None if it can't be read
+fn stamp(path: &Path) -> Option<(u64, Option<u64>)> {
+    let meta = fs::metadata(path).ok()?;
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|since| since.as_nanos() as u64);
+    Some((meta.len(), modified))
+}
+
+fn state_path(instance: &Instance) -> PathBuf {
+    instance.path.join(".mcl").join("packwiz.json")
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub installed: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+    /// Files that could not be installed, e.g. CurseForge-only downloads
+    pub skipped: Vec<String>,
+}
+
+/// Fails unless `bytes` hash to `expected`. Unknown formats cannot be
+/// checked, so they are let through with a warning.
+fn verify(name: &str, format: &str, expected: &str, bytes: &[u8]) -> Result<()> {
+    match hash(format, bytes) {
+        Some(actual) if actual.eq_ignore_ascii_case(expected) => Ok(()),
+        Some(actual) => Err(eyre!(
+            "hash mismatch for {}: expected {}, got {}",
+            name,
+            expected,
+            actual
+        )),
+        None => {
+            warn!(file = name; "Installing {} unverified, unknown hash format '{}'", name, format);
+            Ok(())
+        }
+    }
+}
+
+/// Hashes a file to sync, trusting the last sync if it verified the same
+/// hash and neither the size nor the modification time changed since
+struct Checker {
+    previous: BTreeMap<String, Verified>,
+    verified: BTreeMap<String, Verified>,
+}
+
+impl Checker {
+    /// Whether the file on disk already has the expected hash
+    fn up_to_date(&mut self, relative: &str, path: &Path, format: &str, expected: &str) -> bool {
+        let Some((size, modified)) = stamp(path) else {
+            return false;
+        };
+        let verified = Verified {
+            format: format.to_string(),
+            hash: expected.to_lowercase(),
+            size,
+            modified,
+        };
+        let current = (modified.is_some() && self.previous.get(relative) == Some(&verified))
+            || fs::read(path)
+                .ok()
+                .and_then(|bytes| hash(format, &bytes))
+                .is_some_and(|actual| actual.eq_ignore_ascii_case(expected));
+        if current {
+            self.verified.insert(relative.to_string(), verified);
+        }
+        current
+    }
+
+    /// Records a file just written to `path` after checking its bytes
+    /// against `expected`
+    fn installed(&mut self, relative: &str, path: &Path, format: &str, expected: &str, bytes: &[u8]) {
+        if hash(format, bytes).is_none() {
+            return;
+        }
+        if let Some((size, modified)) = stamp(path) {
+            let verified = Verified {
+                format: format.to_string(),
+                hash: expected.to_lowercase(),
+                size,
+                modified,
+            };
+            self.verified.insert(relative.to_string(), verified);
+        }
+    }
+}
+
+fn write(path: &Path, bytes: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".part");
+    fs::write(&partial, bytes)?;
+    fs::rename(&partial, path)?;
+    Ok(())
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// Installs a single index entry. Returns the instance-relative path it
+/// occupies and whether anything was written, or `None` if it was skipped.
+fn sync_file(
+    instance: &Instance,
+    index: &Location,
+    default_format: &str,
+    entry: &IndexFile,
+    checker: &mut Checker,
+    report: &mut Report,
+) -> Result<Option<String>> {
+    let format = entry.hash_format.as_deref().unwrap_or(default_format);
+    let source = index.sibling(&entry.file);
+
+    if !entry.metafile {
+        let relative = entry.alias.clone().unwrap_or_else(|| entry.file.clone());
+        let target = safe_join(&instance.path, &relative)?;
+        if (entry.preserve && target.exists()) || checker.up_to_date(&relative, &target, format, &entry.hash) {
+            report.unchanged += 1;
+            return Ok(Some(relative));
+        }
+
+        let bytes = source.read()?;
+        verify(&entry.file, format, &entry.hash, &bytes)?;
+        write(&target, &bytes)?;
+        checker.installed(&relative, &target, format, &entry.hash, &bytes);
+        debug!("Installed {}", relative);
+        report.installed += 1;
+        return Ok(Some(relative));
+    }
+
+    let bytes = source.read()?;
+    verify(&entry.file, format, &entry.hash, &bytes)?;
+    let meta: MetaFile = toml::from_str(&String::from_utf8_lossy(&bytes))
+        .map_err(|err| eyre!("invalid metafile {}: {}", entry.file, err))?;
+
+    if meta.side.as_deref() == Some("server") {
+        debug!("Skipping server-only {}", meta.name);
+        return Ok(None);
+    }
+
+    let folder = Path::new(&entry.file)
+        .parent()
+        .map(|parent| parent.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let relative = if folder.is_empty() {
+        meta.filename.clone()
+    } else {
+        format!("{}/{}", folder, meta.filename)
+    };
+    let target = safe_join(&instance.path, &relative)?;
+    let download = &meta.download;
+
+    // a mod the user disabled stays disabled as long as it is current
+    let disabled = with_suffix(&target, DISABLED_SUFFIX);
+    if checker.up_to_date(&relative, &target, &download.hash_format, &download.hash)
+        || checker.up_to_date(&relative, &disabled, &download.hash_format, &download.hash)
+    {
+        report.unchanged += 1;
+        return Ok(Some(relative));
+    }
+
+    let Some(url) = &download.url else {
+        report.skipped.push(meta.name.clone());
+        return Ok(None);
+    };
+    let bytes = Location::Remote(url.clone()).read()?;
+    verify(&meta.filename, &download.hash_format, &download.hash, &bytes)?;
+
+    let target = if disabled.exists() {
+        fs::remove_file(&disabled)?;
+        disabled
+    } else {
+        target
+    };
+    write(&target, &bytes)?;
+    checker.installed(&relative, &target, &download.hash_format, &download.hash, &bytes);
+    debug!("Installed {}", meta.filename);
+    report.installed += 1;
+    Ok(Some(relative))
+}
+
+/// Brings the instance in line with the pack at `source`, downloading
+/// missing or changed files and removing ones the pack no longer lists
+pub fn sync(instance: &Instance, source: &str) -> Result<Report> {
+    let location = Location::parse(source);
+    let pack: Pack = location.read_toml()?;
+
+    let index_location = location.sibling(&pack.index.file);
+    let raw = index_location.read()?;
+    verify(&pack.index.file, &pack.index.hash_format, &pack.index.hash, &raw)?;
+    let index: Index = toml::from_str(&String::from_utf8_lossy(&raw))
+        .map_err(|err| eyre!("invalid index {}: {}", index_location, err))?;
+
+    let path = state_path(instance);
+    let previous: State = fs::read_to_string(&path)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default();
+
+    let mut report = Report::default();
+    let mut files = BTreeSet::new();
+    let mut checker = Checker {
+        previous: previous.verified,
+        verified: BTreeMap::new(),
+    };
+    for entry in &index.files {
+        let format = &index.hash_format;
+        if let Some(relative) = sync_file(instance, &index_location, format, entry, &mut checker, &mut report)? {
+            files.insert(relative);
+        }
+    }
+
+    for relative in previous.files.difference(&files) {
+        let Ok(target) = safe_join(&instance.path, relative) else {
+            continue;
+        };
+        for target in [with_suffix(&target, DISABLED_SUFFIX), target] {
+            if target.is_file() {
+                fs::remove_file(&target)?;
+                debug!("Removed {}", target.display());
+                report.removed += 1;
+            }
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let state = State {
+        files,
+        verified: checker.verified,
+    };
+    fs::write(&path, serde_json::to_string_pretty(&state)?)?;
+
+    info!(
+        instance = instance.id,
+        installed = report.installed,
+        unchanged = report.unchanged,
+        removed = report.removed;
+        "Synced {} from {}: {} installed, {} unchanged, {} removed",
+        instance.name, pack.name, report.installed, report.unchanged, report.removed
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rehashes_files_modified_since_the_last_sync() {
+        let dir = std::env::temp_dir().join(format!("mcl-packwiz-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.txt");
+        let expected = hash("sha1", b"original").unwrap();
+
+        let mut first = Checker { previous: BTreeMap::new(), verified: BTreeMap::new() };
+        fs::write(&path, b"original").unwrap();
+        first.installed("config.txt", &path, "sha1", &expected, b"original");
+
+        let mut second = Checker { previous: first.verified.clone(), verified: BTreeMap::new() };
+        assert!(second.up_to_date("config.txt", &path, "sha1", &expected));
+
+        // same size, different content and a later modification time
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        fs::write(&path, b"tampered").unwrap();
+        let mut third = Checker { previous: first.verified, verified: BTreeMap::new() };
+        assert!(!third.up_to_date("config.txt", &path, "sha1", &expected));
+        assert!(third.verified.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
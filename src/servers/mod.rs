@@ -0,0 +1,268 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use color_eyre::eyre::{eyre, Result};
+
+use crate::{
+    instance::{process, Instance},
+    nbt::{self, Compound, Tag},
+};
+
+pub mod ping;
+mod srv;
+
+/// Whether the game may download the server's resource pack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Textures {
+    Prompt,
+    Enabled,
+    Disabled,
+}
+
+impl Textures {
+    pub fn parse(value: &str) -> Option<Textures> {
+        match value {
+            "prompt" => Some(Textures::Prompt),
+            "enabled" => Some(Textures::Enabled),
+            "disabled" => Some(Textures::Disabled),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Textures::Prompt => "prompt",
+            Textures::Enabled => "enabled",
+            Textures::Disabled => "disabled",
+        }
+    }
+}
+
+/// An entry of the multiplayer server list
+#[derive(Debug, Clone, PartialEq)]
+pub struct Server {
+    pub name: String,
+    /// Host with an optional `:port`, as typed in the game
+    pub ip: String,
+    pub textures: Textures,
+    /// Base64 PNG the game stores from the last ping
+    pub icon: Option<String>,
+    /// The original tag, so fields this launcher doesn't know survive a rewrite
+    raw: Compound,
+}
+
+impl Server {
+    pub fn new(name: &str, ip: &str) -> Server {
+        Server {
+            name: name.to_string(),
+            ip: ip.to_string(),
+            textures: Textures::Prompt,
+            icon: None,
+            raw: Compound::default(),
+        }
+    }
+
+    fn from_tag(tag: &Compound) -> Server {
+        let textures = match tag.number("acceptTextures") {
+            None => Textures::Prompt,
+            Some(0) => Textures::Disabled,
+            Some(_) => Textures::Enabled,
+        };
+        Server {
+            name: tag.string("name").unwrap_or_default().to_string(),
+            ip: tag.string("ip").unwrap_or_default().to_string(),
+            textures,
+            icon: tag.string("icon").map(String::from),
+            raw: tag.clone(),
+        }
+    }
+
+    fn to_tag(&self) -> Compound {
+        let mut tag = self.raw.clone();
+        tag.insert("name", Tag::String(self.name.clone()));
+        tag.insert("ip", Tag::String(self.ip.clone()));
+        match &self.icon {
+            Some(icon) => tag.insert("icon", Tag::String(icon.clone())),
+            None => {
+                tag.remove("icon");
+            }
+        }
+        match self.textures {
+            Textures::Prompt => {
+                tag.remove("acceptTextures");
+            }
+            Textures::Enabled => tag.insert("acceptTextures", Tag::Byte(1)),
+            Textures::Disabled => tag.insert("acceptTextures", Tag::Byte(0)),
+        }
+        tag
+    }
+
+    /// Decoded server icon
+    pub fn icon_png(&self) -> Option<Vec<u8>> {
+        decode_icon(self.icon.as_deref()?)
+    }
+
+    /// Status of the server as shown in the multiplayer list
+    pub fn ping(&self) -> Result<ping::Status> {
+        let (host, port) = self.address();
+        ping::ping(&host, port, ping::TIMEOUT)
+    }
+
+    /// Host and port, defaulting to 25565 when the entry has none
+    pub fn address(&self) -> (String, u16) {
+        split_address(&self.ip)
+    }
+}
+
+/// Splits `host[:port]`, keeping bracketed IPv6 literals intact
+pub fn split_address(address: &str) -> (String, u16) {
+    let address = address.trim();
+    if let Some(rest) = address.strip_prefix('[') {
+        if let Some((host, tail)) = rest.split_once(']') {
+            let port = tail.strip_prefix(':').and_then(|port| port.parse().ok());
+            return (host.to_string(), port.unwrap_or(DEFAULT_PORT));
+        }
+    }
+    match address.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') => match port.parse() {
+            Ok(port) => (host.to_string(), port),
+            Err(_) => (address.to_string(), DEFAULT_PORT),
+        },
+        _ => (address.to_string(), DEFAULT_PORT),
+    }
+}
+
+/// Accepts both the bare base64 stored in `servers.dat` and the
+/// `data:image/png;base64,` URL servers send
+pub fn decode_icon(icon: &str) -> Option<Vec<u8>> {
+    let data = icon.strip_prefix("data:image/png;base64,").unwrap_or(icon);
+    let data: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+    STANDARD.decode(data).ok()
+}
+
+pub const DEFAULT_PORT: u16 = 25565;
+
+pub fn servers_dat(instance: &Instance) -> PathBuf {
+    instance.path.join("servers.dat")
+}
+
+fn read(path: &Path) -> Result<Vec<Server>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let root = nbt::read_file(path)?;
+    Ok(root
+        .list("servers")
+        .iter()
+        .filter_map(Tag::as_compound)
+        .map(Server::from_tag)
+        .collect())
+}
+
+/// Servers in the order the game shows them, empty if it never saved any
+pub fn load(instance: &Instance) -> Result<Vec<Server>> {
+    read(&servers_dat(instance))
+}
+
+/// Rewrites `servers.dat`, keeping the previous file as `servers.dat_old`
+/// like the game does
+pub fn save(instance: &Instance, servers: &[Server]) -> Result<()> {
+    if let Some(pid) = process::running(instance) {
+        return Err(eyre!(
+            "{} is running (pid {}) and would overwrite servers.dat",
+            instance.name,
+            pid
+        ));
+    }
+
+    let path = servers_dat(instance);
+    let (name, mut root) = if path.exists() {
+        fs::copy(&path, instance.path.join("servers.dat_old"))?;
+        nbt::read_named_file(&path)?
+    } else {
+        fs::create_dir_all(&instance.path)?;
+        (String::new(), Compound::default())
+    };
+    let list = servers.iter().map(|server| Tag::Compound(server.to_tag())).collect();
+    root.insert("servers", Tag::List(10, list));
+    nbt::write_file(&path, &name, &root, false)
+}
+
+/// Index of a server given its 1-based position, name or address
+pub fn position(servers: &[Server], query: &str) -> Result<usize> {
+    if let Ok(number) = query.parse::<usize>() {
+        if (1..=servers.len()).contains(&number) {
+            return Ok(number - 1);
+        }
+    }
+    servers
+        .iter()
+        .position(|server| server.name == query)
+        .or_else(|| servers.iter().position(|server| server.ip.eq_ignore_ascii_case(query)))
+        .or_else(|| {
+            let query = query.to_lowercase();
+            servers.iter().position(|server| server.name.to_lowercase() == query)
+        })
+        .ok_or_else(|| eyre!("no server '{}'", query))
+}
+
+/// Adds a server at a 0-based position, or at the end
+pub fn add(instance: &Instance, server: Server, at: Option<usize>) -> Result<()> {
+    if server.name.trim().is_empty() || server.ip.trim().is_empty() {
+        return Err(eyre!("a server needs a name and an address"));
+    }
+    let mut servers = load(instance)?;
+    let index = at.unwrap_or(servers.len()).min(servers.len());
+    servers.insert(index, server);
+    save(instance, &servers)
+}
+
+pub fn remove(instance: &Instance, query: &str) -> Result<Server> {
+    let mut servers = load(instance)?;
+    let index = position(&servers, query)?;
+    let removed = servers.remove(index);
+    save(instance, &servers)?;
+    Ok(removed)
+}
+
+/// Changes a server in place, returning it as saved
+pub fn edit(instance: &Instance, query: &str, edit: impl FnOnce(&mut Server)) -> Result<Server> {
+    let mut servers = load(instance)?;
+    let index = position(&servers, query)?;
+    edit(&mut servers[index]);
+    let server = servers[index].clone();
+    if server.name.trim().is_empty() || server.ip.trim().is_empty() {
+        return Err(eyre!("a server needs a name and an address"));
+    }
+    save(instance, &servers)?;
+    Ok(server)
+}
+
+/// Moves a server to a new 0-based position
+pub fn move_to(instance: &Instance, query: &str, to: usize) -> Result<()> {
+    let mut servers = load(instance)?;
+    let index = position(&servers, query)?;
+    let server = servers.remove(index);
+    servers.insert(to.min(servers.len()), server);
+    save(instance, &servers)
+}
+
+/// Copies the server list of `source` into `target`, skipping addresses the
+/// target already has unless `replace` drops its list first. Returns how
+/// many servers were added
+pub fn copy(source: &Instance, target: &Instance, replace: bool) -> Result<usize> {
+    let incoming = load(source)?;
+    let mut servers = if replace { Vec::new() } else { load(target)? };
+
+    let mut added = 0;
+    for server in incoming {
+        if servers.iter().any(|existing| existing.ip.eq_ignore_ascii_case(&server.ip)) {
+            continue;
+        }
+        servers.push(server);
+        added += 1;
+    }
+    save(target, &servers)?;
+    Ok(added)
+}
@@ -0,0 +1,398 @@
+use std::io::{Read, Write};
+use std::net::{IpAddr, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use serde_json::Value;
+
+use super::{decode_icon, srv, DEFAULT_PORT};
+
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Status responses carry the favicon, so they are larger than most
+/// packets, but never this large
+const MAX_PACKET: usize = 1 << 21;
+
+/// What a server reports in the multiplayer list
+#[derive(Debug, Clone)]
+pub struct Status {
+    /// Message of the day with formatting codes removed
+    pub motd: String,
+    pub version: String,
+    pub protocol: Option<i64>,
+    pub online: i64,
+    pub max: i64,
+    /// Names from the player sample shown when hovering the player count
+    pub sample: Vec<String>,
+    /// Favicon PNG
+    pub favicon: Option<Vec<u8>>,
+    pub latency: Duration,
+    /// Answered the pre-1.7 ping only
+    pub legacy: bool,
+}
+
+fn write_varint(out: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        if value & !0x7f == 0 {
+            out.push(value as u8);
+            return;
+        }
+        out.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+}
+
+fn read_varint(input: &mut impl Read) -> Result<i32> {
+    let mut value = 0u32;
+    for i in 0..5 {
+        let mut byte = [0u8];
+        input.read_exact(&mut byte)?;
+        value |= ((byte[0] & 0x7f) as u32) << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+    Err(eyre!("VarInt is too long"))
+}
+
+fn write_string(out: &mut Vec<u8>, value: &str) {
+    write_varint(out, value.len() as i32);
+    out.extend_from_slice(value.as_bytes());
+}
+
+/// Length-prefixed packet with its id
+fn write_packet(stream: &mut impl Write, id: i32, data: &[u8]) -> Result<()> {
+    let mut body = Vec::with_capacity(data.len() + 1);
+    write_varint(&mut body, id);
+    body.extend_from_slice(data);
+    let mut packet = Vec::with_capacity(body.len() + 5);
+    write_varint(&mut packet, body.len() as i32);
+    packet.extend_from_slice(&body);
+    stream.write_all(&packet)?;
+    Ok(())
+}
+
+/// Reads one packet, returning its id and payload
+fn read_packet(stream: &mut impl Read) -> Result<(i32, Vec<u8>)> {
+    let length = read_varint(stream)?;
+    if length <= 0 || length as usize > MAX_PACKET {
+        return Err(eyre!("invalid packet length {}", length));
+    }
+    let mut body = vec![0u8; length as usize];
+    stream.read_exact(&mut body)?;
+    let mut cursor = body.as_slice();
+    let id = read_varint(&mut cursor)?;
+    Ok((id, cursor.to_vec()))
+}
+
+/// Connects to the server, following its SRV record when it uses the default
+/// port like the game does. The handshake still names the address as given.
+fn connect(host: &str, port: u16, timeout: Duration) -> Result<TcpStream> {
+    let redirect = (port == DEFAULT_PORT && host.parse::<IpAddr>().is_err())
+        .then(|| srv::lookup(host, timeout))
+        .flatten();
+    let (host, port) = match &redirect {
+        Some((target, port)) => (target.as_str(), *port),
+        None => (host, port),
+    };
+    let addresses = (host, port)
+        .to_socket_addrs()
+        .wrap_err_with(|| format!("failed to resolve {}", host))?;
+
+    let mut last_error = None;
+    for address in addresses {
+        match TcpStream::connect_timeout(&address, timeout) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(timeout))?;
+                stream.set_write_timeout(Some(timeout))?;
+                return Ok(stream);
+            }
+            Err(err) => last_error = Some(err),
+        }
+    }
+    match last_error {
+        Some(err) => Err(eyre!("failed to connect to {}:{}: {}", host, port, err)),
+        None => Err(eyre!("no address found for {}", host)),
+    }
+}
+
+/// Pings with the 1.7+ protocol, falling back to the legacy ping for
+/// servers that don't understand it
+pub fn ping(host: &str, port: u16, timeout: Duration) -> Result<Status> {
+    match ping_modern(host, port, timeout) {
+        Ok(status) => Ok(status),
+        Err(err) => ping_legacy(host, port, timeout).map_err(|_| err),
+    }
+}
+
+/// Handshake with next state 1, status request, then a ping for latency
+pub fn ping_modern(host: &str, port: u16, timeout: Duration) -> Result<Status> {
+    let mut stream = connect(host, port, timeout)?;
+
+    let mut handshake = Vec::new();
+    // -1 asks the server to report its own version
+    write_varint(&mut handshake, -1);
+    write_string(&mut handshake, host);
+    handshake.extend_from_slice(&port.to_be_bytes());
+    write_varint(&mut handshake, 1);
+    write_packet(&mut stream, 0x00, &handshake)?;
+    write_packet(&mut stream, 0x00, &[])?;
+
+    let (id, payload) = read_packet(&mut stream)?;
+    if id != 0x00 {
+        return Err(eyre!("unexpected packet {:#04x} instead of a status response", id));
+    }
+    let mut cursor = payload.as_slice();
+    let length = read_varint(&mut cursor)?;
+    let json = cursor
+        .get(..length.max(0) as usize)
+        .ok_or_else(|| eyre!("truncated status response"))?;
+    let response: Value = serde_json::from_slice(json).wrap_err("invalid status response")?;
+
+    // some servers close the connection instead of answering the ping
+    let started = Instant::now();
+    let latency = write_packet(&mut stream, 0x01, &0i64.to_be_bytes())
+        .and_then(|_| read_packet(&mut stream))
+        .map(|_| started.elapsed())
+        .unwrap_or_else(|_| started.elapsed());
+
+    let players = &response["players"];
+    Ok(Status {
+        motd: strip_codes(&component_text(&response["description"])),
+        version: response["version"]["name"].as_str().unwrap_or("?").to_string(),
+        protocol: response["version"]["protocol"].as_i64(),
+        online: players["online"].as_i64().unwrap_or(0),
+        max: players["max"].as_i64().unwrap_or(0),
+        sample: players["sample"]
+            .as_array()
+            .map(|sample| {
+                sample
+                    .iter()
+                    .filter_map(|player| player["name"].as_str())
+                    .map(strip_codes)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        favicon: response["favicon"].as_str().and_then(decode_icon),
+        latency,
+        legacy: false,
+    })
+}
+
+/// The 1.4-1.6 `0xFE 0x01` ping, which pre-1.4 servers answer in their own
+/// simpler format
+pub fn ping_legacy(host: &str, port: u16, timeout: Duration) -> Result<Status> {
+    let mut stream = connect(host, port, timeout)?;
+    let started = Instant::now();
+    stream.write_all(&[0xfe, 0x01])?;
+
+    let mut header = [0u8; 3];
+    stream.read_exact(&mut header)?;
+    let latency = started.elapsed();
+    if header[0] != 0xff {
+        return Err(eyre!("unexpected legacy response {:#04x}", header[0]));
+    }
+    let length = u16::from_be_bytes([header[1], header[2]]) as usize;
+    let mut data = vec![0u8; length * 2];
+    stream.read_exact(&mut data)?;
+    let units: Vec<u16> = data.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+    let text = String::from_utf16_lossy(&units);
+
+    let number = |value: Option<&str>| value.and_then(|v| v.parse().ok()).unwrap_or(0);
+    let status = match text.strip_prefix("§1\0") {
+        // protocol, version, motd, online, max
+        Some(rest) => {
+            let fields: Vec<&str> = rest.split('\0').collect();
+            Status {
+                motd: strip_codes(fields.get(2).unwrap_or(&"")),
+                version: fields.get(1).unwrap_or(&"?").to_string(),
+                protocol: fields.first().and_then(|v| v.parse().ok()),
+                online: number(fields.get(3).copied()),
+                max: number(fields.get(4).copied()),
+                sample: Vec::new(),
+                favicon: None,
+                latency,
+                legacy: true,
+            }
+        }
+        // motd§online§max, before 1.4
+        None => {
+            let mut fields = text.rsplitn(3, '§');
+            let max = number(fields.next());
+            let online = number(fields.next());
+            Status {
+                motd: strip_codes(fields.next().unwrap_or("")),
+                version: "1.3 or older".to_string(),
+                protocol: None,
+                online,
+                max,
+                sample: Vec::new(),
+                favicon: None,
+                latency,
+                legacy: true,
+            }
+        }
+    };
+    Ok(status)
+}
+
+/// Flattens a chat component, which may be a plain string, an object
+/// with `text` and `extra`, or a list of components
+pub fn component_text(component: &Value) -> String {
+    match component {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts.iter().map(component_text).collect(),
+        Value::Object(object) => {
+            let mut text = object
+                .get("text")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            if let Some(extra) = object.get("extra") {
+                text.push_str(&component_text(extra));
+            }
+            text
+        }
+        _ => String::new(),
+    }
+}
+
+/// Removes `§` formatting codes
+pub fn strip_codes(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+
+    use super::*;
+
+    /// Accepts one connection on a free local port and hands it to `serve`
+    fn server(serve: impl FnOnce(TcpStream) + Send + 'static) -> (u16, JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            stream.set_read_timeout(Some(TIMEOUT)).unwrap();
+            serve(stream);
+        });
+        (port, handle)
+    }
+
+    fn port_of(stream: &TcpStream) -> u16 {
+        stream.local_addr().unwrap().port()
+    }
+
+    fn legacy_reply(stream: &mut TcpStream, text: &str) {
+        let mut request = [0u8; 2];
+        stream.read_exact(&mut request).unwrap();
+        assert_eq!(request, [0xfe, 0x01]);
+        let units: Vec<u16> = text.encode_utf16().collect();
+        let mut reply = vec![0xff];
+        reply.extend_from_slice(&(units.len() as u16).to_be_bytes());
+        for unit in units {
+            reply.extend_from_slice(&unit.to_be_bytes());
+        }
+        stream.write_all(&reply).unwrap();
+    }
+
+    #[test]
+    fn varint_round_trip() {
+        for value in [0, 1, 127, 128, 255, 25565, 2097151, i32::MAX, -1, i32::MIN] {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, value);
+            assert!(bytes.len() <= 5);
+            assert_eq!(read_varint(&mut bytes.as_slice()).unwrap(), value);
+        }
+        let mut bytes = Vec::new();
+        write_varint(&mut bytes, 300);
+        assert_eq!(bytes, [0xac, 0x02]);
+    }
+
+    #[test]
+    fn varint_longer_than_five_bytes_is_rejected() {
+        let bytes = [0x80, 0x80, 0x80, 0x80, 0x80, 0x01];
+        assert!(read_varint(&mut bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn modern_status_and_pong() {
+        let (port, handle) = server(|mut stream| {
+            let (id, handshake) = read_packet(&mut stream).unwrap();
+            assert_eq!(id, 0x00);
+            let mut cursor = handshake.as_slice();
+            assert_eq!(read_varint(&mut cursor).unwrap(), -1);
+            let length = read_varint(&mut cursor).unwrap() as usize;
+            assert_eq!(&cursor[..length], b"127.0.0.1");
+            cursor = &cursor[length..];
+            assert_eq!(u16::from_be_bytes([cursor[0], cursor[1]]), port_of(&stream));
+            cursor = &cursor[2..];
+            assert_eq!(read_varint(&mut cursor).unwrap(), 1);
+            assert!(cursor.is_empty());
+
+            assert_eq!(read_packet(&mut stream).unwrap(), (0x00, Vec::new()));
+            let json = r#"{"version":{"name":"1.20.1","protocol":763},
+                "players":{"max":20,"online":2,"sample":[{"name":"§aAlex","id":"0"},{"name":"Steve","id":"1"}]},
+                "description":{"text":"§6Hello ","extra":[{"text":"world"}]}}"#;
+            let mut response = Vec::new();
+            write_string(&mut response, json);
+            write_packet(&mut stream, 0x00, &response).unwrap();
+
+            let (id, payload) = read_packet(&mut stream).unwrap();
+            assert_eq!(id, 0x01);
+            assert_eq!(payload.len(), 8);
+            write_packet(&mut stream, 0x01, &payload).unwrap();
+        });
+
+        let status = ping_modern("127.0.0.1", port, TIMEOUT).unwrap();
+        handle.join().unwrap();
+        assert_eq!(status.motd, "Hello world");
+        assert_eq!(status.version, "1.20.1");
+        assert_eq!(status.protocol, Some(763));
+        assert_eq!((status.online, status.max), (2, 20));
+        assert_eq!(status.sample, ["Alex", "Steve"]);
+        assert!(status.favicon.is_none());
+        assert!(!status.legacy);
+    }
+
+    #[test]
+    fn legacy_status() {
+        let (port, handle) = server(|mut stream| {
+            legacy_reply(&mut stream, "§1\u{0}47\u{0}1.4.2\u{0}§cA server\u{0}3\u{0}10");
+        });
+
+        let status = ping_legacy("127.0.0.1", port, TIMEOUT).unwrap();
+        handle.join().unwrap();
+        assert_eq!(status.motd, "A server");
+        assert_eq!(status.version, "1.4.2");
+        assert_eq!(status.protocol, Some(47));
+        assert_eq!((status.online, status.max), (3, 10));
+        assert!(status.legacy);
+    }
+
+    #[test]
+    fn pre_1_4_status() {
+        let (port, handle) = server(|mut stream| {
+            legacy_reply(&mut stream, "§aOld server§5§12");
+        });
+
+        let status = ping_legacy("127.0.0.1", port, TIMEOUT).unwrap();
+        handle.join().unwrap();
+        assert_eq!(status.motd, "Old server");
+        assert_eq!(status.version, "1.3 or older");
+        assert_eq!(status.protocol, None);
+        assert_eq!((status.online, status.max), (5, 12));
+    }
+}
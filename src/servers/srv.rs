@@ -0,0 +1,269 @@
+use std::fs;
+use std::net::{IpAddr, SocketAddr, UdpSocket};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use color_eyre::eyre::{eyre, Result};
+
+use crate::debug;
+
+const DNS_PORT: u16 = 53;
+
+/// Record type of SRV records
+const TYPE_SRV: u16 = 33;
+const CLASS_IN: u16 = 1;
+
+/// Compression pointers followed before a name is considered a loop
+const MAX_POINTERS: usize = 16;
+
+/// Looks up `_minecraft._tcp.<host>`, which lets a server live on another
+/// host or port than the address players type. `None` if there is no
+/// record or no name server answered.
+pub fn lookup(host: &str, timeout: Duration) -> Option<(String, u16)> {
+    let name = format!("_minecraft._tcp.{}", host.trim_end_matches('.'));
+    for server in name_servers() {
+        match query(server, &name, timeout) {
+            Ok(record) => return record,
+            Err(err) => {
+                debug!("SRV lookup of {} via {} failed: {:#}", name, server, err);
+            }
+        }
+    }
+    None
+}
+
+/// Name servers from `/etc/resolv.conf`
+fn name_servers() -> Vec<SocketAddr> {
+    let Ok(conf) = fs::read_to_string("/etc/resolv.conf") else {
+        return Vec::new();
+    };
+    conf.lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        .filter_map(|address| address.trim().split('%').next()?.parse::<IpAddr>().ok())
+        .map(|ip| SocketAddr::new(ip, DNS_PORT))
+        .collect()
+}
+
+fn query(server: SocketAddr, name: &str, timeout: Duration) -> Result<Option<(String, u16)>> {
+    let bind: SocketAddr = if server.is_ipv4() {
+        ([0, 0, 0, 0], 0).into()
+    } else {
+        ([0u16; 8], 0).into()
+    };
+    let socket = UdpSocket::bind(bind)?;
+    socket.set_read_timeout(Some(timeout))?;
+    socket.connect(server)?;
+
+    // not meant to be unpredictable, only to tell answers apart
+    let id = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.subsec_nanos() as u16);
+    socket.send(&encode_query(id, name)?)?;
+    let mut buffer = [0u8; 4096];
+    let length = socket.recv(&mut buffer)?;
+    parse_response(id, &buffer[..length])
+}
+
+/// A recursive query for the SRV records of `name`
+fn encode_query(id: u16, name: &str) -> Result<Vec<u8>> {
+    let mut message = Vec::with_capacity(name.len() + 18);
+    message.extend_from_slice(&id.to_be_bytes());
+    // recursion desired
+    message.extend_from_slice(&0x0100u16.to_be_bytes());
+    // one question, no answer, authority or additional records
+    message.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in name.split('.').filter(|label| !label.is_empty()) {
+        if label.len() > 63 {
+            return Err(eyre!("'{}' is not a valid host name", name));
+        }
+        message.push(label.len() as u8);
+        message.extend_from_slice(label.as_bytes());
+    }
+    message.push(0);
+    message.extend_from_slice(&TYPE_SRV.to_be_bytes());
+    message.extend_from_slice(&CLASS_IN.to_be_bytes());
+    Ok(message)
+}
+
+fn read_u16(message: &[u8], offset: usize) -> Result<u16> {
+    message
+        .get(offset..offset + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| eyre!("truncated DNS response"))
+}
+
+/// Reads a possibly compressed name, returning it and the offset after it
+fn read_name(message: &[u8], mut offset: usize) -> Result<(String, usize)> {
+    let mut labels = Vec::new();
+    let mut end = None;
+    let mut pointers = 0;
+    loop {
+        let length = *message.get(offset).ok_or_else(|| eyre!("truncated DNS response"))? as usize;
+        if length & 0xc0 == 0xc0 {
+            pointers += 1;
+            if pointers > MAX_POINTERS {
+                return Err(eyre!("DNS name compression loop"));
+            }
+            end.get_or_insert(offset + 2);
+            offset = read_u16(message, offset)? as usize & 0x3fff;
+            continue;
+        }
+        if length == 0 {
+            let end = end.unwrap_or(offset + 1);
+            return Ok((labels.join("."), end));
+        }
+        let label = message
+            .get(offset + 1..offset + 1 + length)
+            .ok_or_else(|| eyre!("truncated DNS response"))?;
+        labels.push(String::from_utf8_lossy(label).to_string());
+        offset += 1 + length;
+    }
+}
+
+/// Target and port of the preferred SRV record: lowest priority first,
+/// then highest weight
+fn parse_response(id: u16, message: &[u8]) -> Result<Option<(String, u16)>> {
+    if read_u16(message, 0)? != id {
+        return Err(eyre!("DNS response for another query"));
+    }
+    let flags = read_u16(message, 2)?;
+    if flags & 0x8000 == 0 {
+        return Err(eyre!("DNS message is not a response"));
+    }
+    match flags & 0x000f {
+        0 => {}
+        // no such name
+        3 => return Ok(None),
+        code => return Err(eyre!("DNS error code {}", code)),
+    }
+    let questions = read_u16(message, 4)?;
+    let answers = read_u16(message, 6)?;
+
+    let mut offset = 12;
+    for _ in 0..questions {
+        offset = read_name(message, offset)?.1 + 4;
+    }
+    let mut best: Option<(u16, u16, String, u16)> = None;
+    for _ in 0..answers {
+        offset = read_name(message, offset)?.1;
+        let kind = read_u16(message, offset)?;
+        let length = read_u16(message, offset + 8)? as usize;
+        let data = offset + 10;
+        offset = data + length;
+        if kind != TYPE_SRV {
+            continue;
+        }
+        let priority = read_u16(message, data)?;
+        let weight = read_u16(message, data + 2)?;
+        let port = read_u16(message, data + 4)?;
+        let (target, _) = read_name(message, data + 6)?;
+        let better = best
+            .as_ref()
+            .is_none_or(|(p, w, _, _)| priority < *p || (priority == *p && weight > *w));
+        if better {
+            best = Some((priority, weight, target, port));
+        }
+    }
+    // a target of "." says the service is not offered
+    Ok(best
+        .filter(|(_, _, target, _)| !target.is_empty())
+        .map(|(_, _, target, port)| (target, port)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Response to `encode_query(id, name)` with the given SRV answers as
+    /// priority, weight, port and target
+    fn response(id: u16, name: &str, answers: &[(u16, u16, u16, &str)]) -> Vec<u8> {
+        let mut message = encode_query(id, name).unwrap();
+        message[2] = 0x81;
+        message[3] = 0x80;
+        message[7] = answers.len() as u8;
+        for (priority, weight, port, target) in answers {
+            // owner name points at the question
+            message.extend_from_slice(&[0xc0, 12]);
+            message.extend_from_slice(&TYPE_SRV.to_be_bytes());
+            message.extend_from_slice(&CLASS_IN.to_be_bytes());
+            message.extend_from_slice(&300u32.to_be_bytes());
+            let mut data = Vec::new();
+            for value in [priority, weight, port] {
+                data.extend_from_slice(&value.to_be_bytes());
+            }
+            for label in target.split('.').filter(|label| !label.is_empty()) {
+                data.push(label.len() as u8);
+                data.extend_from_slice(label.as_bytes());
+            }
+            data.push(0);
+            message.extend_from_slice(&(data.len() as u16).to_be_bytes());
+            message.extend_from_slice(&data);
+        }
+        message
+    }
+
+    #[test]
+    fn query_layout() {
+        let query = encode_query(0x1234, "_minecraft._tcp.example.org").unwrap();
+        assert_eq!(&query[..4], &[0x12, 0x34, 0x01, 0x00]);
+        assert_eq!(read_name(&query, 12).unwrap(), ("_minecraft._tcp.example.org".to_string(), 41));
+        assert_eq!(&query[41..], &[0, 33, 0, 1]);
+        assert!(encode_query(1, &"a".repeat(64)).is_err());
+    }
+
+    #[test]
+    fn picks_the_preferred_record() {
+        let name = "_minecraft._tcp.example.org";
+        let answers = [
+            (20, 0, 25570, "backup.example.org"),
+            (10, 5, 25566, "mc.example.org"),
+            (10, 50, 25567, "big.example.org"),
+        ];
+        let message = response(7, name, &answers);
+        assert_eq!(parse_response(7, &message).unwrap(), Some(("big.example.org".to_string(), 25567)));
+        assert!(parse_response(8, &message).is_err());
+    }
+
+    #[test]
+    fn missing_records() {
+        let name = "_minecraft._tcp.example.org";
+        assert_eq!(parse_response(1, &response(1, name, &[])).unwrap(), None);
+        assert_eq!(parse_response(1, &response(1, name, &[(0, 0, 0, ".")])).unwrap(), None);
+
+        let mut nxdomain = response(1, name, &[]);
+        nxdomain[3] = 0x83;
+        assert_eq!(parse_response(1, &nxdomain).unwrap(), None);
+        let mut refused = response(1, name, &[]);
+        refused[3] = 0x85;
+        assert!(parse_response(1, &refused).is_err());
+    }
+
+    #[test]
+    fn queries_a_name_server() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = server.local_addr().unwrap();
+        let handle = std::thread::spawn(move || {
+            let mut buffer = [0u8; 512];
+            let (length, from) = server.recv_from(&mut buffer).unwrap();
+            let id = u16::from_be_bytes([buffer[0], buffer[1]]);
+            let (name, _) = read_name(&buffer[..length], 12).unwrap();
+            let reply = response(id, &name, &[(0, 0, 25600, "play.example.org")]);
+            server.send_to(&reply, from).unwrap();
+            name
+        });
+
+        let record = query(address, "_minecraft._tcp.example.org", Duration::from_secs(5)).unwrap();
+        assert_eq!(record, Some(("play.example.org".to_string(), 25600)));
+        assert_eq!(handle.join().unwrap(), "_minecraft._tcp.example.org");
+    }
+
+    #[test]
+    fn malformed_responses() {
+        let name = "_minecraft._tcp.example.org";
+        let message = response(1, name, &[(0, 0, 25565, "mc.example.org")]);
+        for length in 0..message.len() {
+            assert!(parse_response(1, &message[..length]).is_err(), "cut at {}", length);
+        }
+        // a name pointing at itself
+        assert!(read_name(&[0xc0, 0x00], 0).is_err());
+    }
+}
@@ -1,25 +1,32 @@
 use super::{
-    widgets::{self, profiles, WidgetKey},
+    widgets::{self, content, profiles, status, WidgetKey},
     Tui,
 };
-use super::widgets::popups::new_instance;
+use super::widgets::popups::{crash, new_instance};
+use crate::instance::Instance;
 use color_eyre::eyre::Context;
 use crossterm::event::{self, Event};
+use std::time::Duration;
 use ratatui::{
     crossterm::event::{KeyCode, KeyEvent, KeyEventKind},
     layout::{Constraint, Direction, Layout},
     Frame,
 };
 
+const TICK_RATE: Duration = Duration::from_millis(250);
+
 #[derive(Debug, Default)]
 pub struct App {
     exit: bool,
     focused: FocusedArea,
     profiles_state: profiles::State,
+    content_state: content::State,
+    status_state: status::State,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum FocusedArea {
+    #[default]
     Profiles,
     Content,
     Account,
@@ -28,22 +35,34 @@ pub enum FocusedArea {
     Popup,
 }
 
-impl Default for FocusedArea {
-    fn default() -> Self {
-        FocusedArea::Profiles
+impl App {
+    pub fn new() -> Self {
+        let mut app = Self::default();
+        app.profiles_state.load();
+        app.sync_content();
+        app
     }
-}
 
-impl App {
     /// runs the main loop until the user quits
     pub fn run(&mut self, terminal: &mut Tui) -> color_eyre::Result<()> {
         while !self.exit {
             terminal.draw(|frame| self.render_frame(frame))?;
             self.handle_events().wrap_err("handle events failed")?;
+            self.content_state.poll();
+            self.profiles_state.refresh();
         }
         Ok(())
     }
 
+    /// points the content panel at the selected profile
+    fn sync_content(&mut self) {
+        let instance = self
+            .profiles_state
+            .selected()
+            .and_then(|data| Instance::load(&data.id).ok());
+        self.content_state.select(instance);
+    }
+
     fn render_frame(&mut self, frame: &mut Frame) {
         // Divide the screen into horizontal chunks
         let chunks = Layout::default()
@@ -63,12 +82,17 @@ impl App {
             .constraints([
                 Constraint::Length(3), // Title
                 Constraint::Min(10),   // Main Content
-                Constraint::Length(5), // Bottom panel
+                Constraint::Length(10), // Bottom panel
             ])
             .split(chunks[1]);
 
-        widgets::content::title(frame, main_chunks[0], self.focused);
-        widgets::content::render(frame, main_chunks[1], self.focused);
+        widgets::content::title(
+            frame,
+            main_chunks[0],
+            self.focused,
+            self.content_state.instance.as_ref(),
+        );
+        widgets::content::render(frame, main_chunks[1], self.focused, &mut self.content_state);
 
         let bottom_chunks = Layout::default()
             .direction(Direction::Horizontal)
@@ -80,12 +104,22 @@ impl App {
             .split(main_chunks[2]);
 
         widgets::account::render(frame, bottom_chunks[0], self.focused);
-        widgets::details::render(frame, bottom_chunks[1], self.focused);
-        widgets::status::render(frame, bottom_chunks[2], self.focused);
+        widgets::details::render(
+            frame,
+            bottom_chunks[1],
+            self.focused,
+            self.content_state.details(),
+        );
+        widgets::status::render(frame, bottom_chunks[2], self.focused, &mut self.status_state);
     }
 
     /// updates the application's state based on user input
     fn handle_events(&mut self) -> color_eyre::Result<()> {
+        // poll so background work (e.g. update checks) shows up without input
+        if !event::poll(TICK_RATE)? {
+            return Ok(());
+        }
+
         match event::read()? {
             Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
                 self.handle_key_event(key_event)
@@ -97,9 +131,15 @@ impl App {
 
     fn handle_key_event(&mut self, key_event: KeyEvent) -> color_eyre::Result<()> {
         match self.focused {
+            FocusedArea::Popup if self.profiles_state.crash.is_some() => {
+                crash::handle_key(&key_event, &mut self.profiles_state);
+            }
             FocusedArea::Popup => {
                 new_instance::handle_key(&key_event, &mut self.profiles_state);
             }
+            FocusedArea::Content if self.content_state.capturing_input() => {
+                self.content_state.handle_key(&key_event);
+            }
             _ => {
                 match key_event.code {
                     KeyCode::Char('q') => self.exit = true,
@@ -113,6 +153,8 @@ impl App {
 
                 match self.focused {
                     FocusedArea::Profiles => self.profiles_state.handle_key(&key_event),
+                    FocusedArea::Content => self.content_state.handle_key(&key_event),
+                    FocusedArea::Status => self.status_state.handle_key(&key_event),
                     _ => {}
                 }
             }
@@ -124,6 +166,7 @@ impl App {
             self.focused = FocusedArea::Profiles;
         }
 
+        self.sync_content();
         Ok(())
     }
 }
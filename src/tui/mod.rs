@@ -9,10 +9,13 @@ use crossterm::{
 };
 use ratatui::{prelude::CrosstermBackend, Terminal};
 
+use crate::logger::Logger;
+
 pub fn show() -> color_eyre::Result<()> {
     color_eyre::install()?;
     let mut terminal = init_ratatui()?;
-    let result = layout::App::default().run(&mut terminal);
+    crate::config::report_problems();
+    let result = layout::App::new().run(&mut terminal);
     if let Err(err) = restore_ratatui() {
         eprintln!(
             "failed to restore terminal. Run 'reset' or restart your terminal to recover: {}",
@@ -23,13 +26,14 @@ pub fn show() -> color_eyre::Result<()> {
 }
 
 /// TUI Impl
-
 pub type Tui = Terminal<CrosstermBackend<Stdout>>;
 
 fn init_ratatui() -> Result<Tui> {
     execute!(stdout(), EnterAlternateScreen)?;
     enable_raw_mode()?;
     set_panic_hook();
+    // lines printed now would be drawn over by the next frame
+    Logger::set_tui(true);
     Terminal::new(CrosstermBackend::new(stdout()))
 }
 
@@ -42,6 +46,7 @@ fn set_panic_hook() {
 }
 
 fn restore_ratatui() -> Result<()> {
+    Logger::set_tui(false);
     execute!(stdout(), LeaveAlternateScreen)?;
     disable_raw_mode()?;
     Ok(())
@@ -0,0 +1,33 @@
+use std::fs;
+use std::path::PathBuf;
+
+use dirs_next::cache_dir;
+use rascii_art::{charsets, RenderOptions};
+use sha1::{Digest, Sha1};
+
+fn image_cache_path() -> PathBuf {
+    cache_dir().unwrap_or_else(std::env::temp_dir).join("mcl/images")
+}
+
+/// Renders PNG bytes (mod icons, pack.png, favicons...) as block characters.
+/// rascii_art only reads from disk, so the image is cached by content hash.
+pub fn render(bytes: &[u8], width: u32) -> Option<String> {
+    // validate first so broken icons never reach the renderer
+    image::load_from_memory(bytes).ok()?;
+
+    let dir = image_cache_path();
+    fs::create_dir_all(&dir).ok()?;
+    let path = dir.join(format!("{:x}.png", Sha1::digest(bytes)));
+    if !path.exists() {
+        fs::write(&path, bytes).ok()?;
+    }
+
+    let mut buf = String::new();
+    rascii_art::render_to(
+        path.to_str()?,
+        &mut buf,
+        &RenderOptions::new().width(width).charset(charsets::BLOCK),
+    )
+    .ok()?;
+    Some(buf)
+}
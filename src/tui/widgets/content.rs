@@ -1,32 +1,566 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use color_eyre::eyre::Result;
+use crossterm::event::KeyCode;
 use ratatui::{
-    layout::Rect,
-    style::{Color, Style},
-    widgets::{Block, BorderType, Borders, Paragraph},
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Borders, Cell, Paragraph, Row, Table, TableState},
     Frame,
 };
 
-use crate::tui::layout::FocusedArea;
+use crate::{
+    config::SETTINGS,
+    instance::Instance,
+    mods::{
+        self,
+        check::{self, Problem, Severity},
+        metadata,
+        update::Update,
+        ModInfo,
+    },
+    tui::layout::FocusedArea,
+};
+
+use super::{
+    ascii, details::Details, log, options, resourcepacks, servers, shaders, styled_title, worlds,
+    WidgetKey,
+};
+
+const ICON_WIDTH: u32 = 16;
+
+/// Tabs of the Content panel, cycled with Tab
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum View {
+    #[default]
+    Mods,
+    ResourcePacks,
+    Shaders,
+    Worlds,
+    Servers,
+    Options,
+    Log,
+}
+
+impl View {
+    const ALL: [View; 7] = [
+        View::Mods,
+        View::ResourcePacks,
+        View::Shaders,
+        View::Worlds,
+        View::Servers,
+        View::Options,
+        View::Log,
+    ];
+
+    fn title(&self) -> &'static str {
+        match self {
+            View::Mods => "Mods",
+            View::ResourcePacks => "Resource Packs",
+            View::Shaders => "Shaders",
+            View::Worlds => "Worlds",
+            View::Servers => "Servers",
+            View::Options => "Options",
+            View::Log => "Log",
+        }
+    }
+
+    fn cycle(&self, forward: bool) -> View {
+        let index = View::ALL.iter().position(|view| view == self).unwrap_or(0);
+        let len = View::ALL.len();
+        let next = if forward { index + 1 } else { index + len - 1 };
+        View::ALL[next % len]
+    }
+}
+
+/// Work running off the UI thread
+#[derive(Debug)]
+enum Task {
+    Updates(Receiver<Result<Vec<Update>>>),
+    Identify(Receiver<Result<usize>>),
+}
+
+#[derive(Debug, Default)]
+pub struct State {
+    pub instance: Option<Instance>,
+    pub mods: Vec<ModInfo>,
+    pub table_state: TableState,
+    /// Latest version number per outdated mod, keyed by file name
+    pub outdated: HashMap<String, String>,
+    pub message: Option<String>,
+    pub details: Option<Details>,
+    pub problems: Vec<Problem>,
+    pub view: View,
+    pub resource_packs: resourcepacks::State,
+    pub shaders: shaders::State,
+    pub worlds: worlds::State,
+    pub servers: servers::State,
+    pub options: options::State,
+    pub log: log::State,
+    task: Option<Task>,
+    /// Set after the first space on a mod others depend on, the second one
+    /// disables it
+    confirm_disable: bool,
+}
+
+impl State {
+    /// Switches the panel to another instance, reloading its mods
+    pub fn select(&mut self, instance: Option<Instance>) {
+        let current = self.instance.as_ref().map(|i| i.id.as_str());
+        let next = instance.as_ref().map(|i| i.id.as_str());
+        if current == next {
+            return;
+        }
+
+        self.instance = instance;
+        self.outdated.clear();
+        self.message = None;
+        self.task = None;
+        self.confirm_disable = false;
+        self.resource_packs.select(self.instance.clone());
+        self.shaders.select(self.instance.clone());
+        self.worlds.select(self.instance.clone());
+        self.servers.select(self.instance.clone());
+        self.options.select(self.instance.clone());
+        self.log.select(self.instance.clone());
+        self.reload();
+    }
+
+    /// Details of the item selected in the current tab
+    pub fn details(&self) -> Option<&Details> {
+        match self.view {
+            View::Mods => self.details.as_ref(),
+            View::ResourcePacks => self.resource_packs.details.as_ref(),
+            View::Shaders => self.shaders.details.as_ref(),
+            View::Worlds => self.worlds.details(),
+            View::Servers => self.servers.details.as_ref(),
+            View::Options => self.options.details.as_ref(),
+            View::Log => None,
+        }
+    }
+
+    /// Whether the current tab takes key presses as text input
+    pub fn capturing_input(&self) -> bool {
+        match self.view {
+            View::Worlds => self.worlds.capturing_input(),
+            View::Servers => self.servers.capturing_input(),
+            View::Options => self.options.capturing_input(),
+            View::Log => self.log.capturing_input(),
+            _ => false,
+        }
+    }
+
+    pub fn reload(&mut self) {
+        self.mods = self.instance.as_ref().map(mods::load).unwrap_or_default();
+        self.problems = match &self.instance {
+            Some(instance) => check::check(instance, &self.mods),
+            None => Vec::new(),
+        };
+        if self.mods.is_empty() {
+            self.table_state.select(None);
+        } else {
+            let index = self.table_state.selected().unwrap_or(0);
+            self.table_state.select(Some(index.min(self.mods.len() - 1)));
+        }
+        self.update_details();
+    }
+
+    pub fn busy(&self) -> bool {
+        self.task.is_some()
+    }
+
+    /// Starts an update check in the background, see [`State::poll`]
+    pub fn check_updates(&mut self) {
+        let Some(instance) = self.instance.clone() else {
+            return;
+        };
+        if self.busy() {
+            return;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(mods::update::outdated(&instance, None));
+        });
+        self.task = Some(Task::Updates(receiver));
+        self.message = Some("Checking for updates...".to_string());
+    }
+
+    /// Links unknown jars to Modrinth projects by hash
+    pub fn identify(&mut self) {
+        let Some(instance) = self.instance.clone() else {
+            return;
+        };
+        if self.busy() {
+            return;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(mods::identify(&instance));
+        });
+        self.task = Some(Task::Identify(receiver));
+        self.message = Some("Looking up mods on Modrinth...".to_string());
+    }
+
+    /// Collects the result of a running background task
+    pub fn poll(&mut self) {
+        self.worlds.poll();
+        self.servers.poll();
+        self.log.poll();
+        let finished = match &self.task {
+            Some(Task::Updates(receiver)) => match receiver.try_recv() {
+                Ok(Ok(updates)) => {
+                    self.outdated = updates
+                        .into_iter()
+                        .map(|update| (update.file.file_name, update.latest.version_number))
+                        .collect();
+                    self.message = Some(format!("{} outdated", self.outdated.len()));
+                    true
+                }
+                Ok(Err(err)) => {
+                    self.message = Some(format!("Update check failed: {}", err));
+                    true
+                }
+                Err(TryRecvError::Empty) => false,
+                Err(TryRecvError::Disconnected) => true,
+            },
+            Some(Task::Identify(receiver)) => match receiver.try_recv() {
+                Ok(Ok(linked)) => {
+                    self.message = Some(format!("{} mods linked to Modrinth", linked));
+                    self.reload();
+                    true
+                }
+                Ok(Err(err)) => {
+                    self.message = Some(format!("Lookup failed: {}", err));
+                    true
+                }
+                Err(TryRecvError::Empty) => false,
+                Err(TryRecvError::Disconnected) => true,
+            },
+            None => false,
+        };
+
+        if finished {
+            self.task = None;
+        }
+    }
+
+    pub fn selected(&self) -> Option<&ModInfo> {
+        self.table_state.selected().and_then(|i| self.mods.get(i))
+    }
+
+    fn update_details(&mut self) {
+        self.details = self.selected().map(|info| {
+            let problems: Vec<&Problem> = self
+                .problems
+                .iter()
+                .filter(|problem| problem.involves(&info.file.file_name))
+                .collect();
+            mod_details(info, &problems)
+        });
+    }
+
+    /// Enables or disables the selected mod. Disabling one that enabled
+    /// mods depend on lists them and waits for a second press.
+    pub fn toggle_selected(&mut self) {
+        let Some(index) = self.table_state.selected() else {
+            return;
+        };
+        let Some(info) = self.mods.get(index) else {
+            return;
+        };
+
+        let enable = !info.file.enabled;
+        let dependents: Vec<String> = if enable {
+            Vec::new()
+        } else {
+            mods::dependents(&self.mods, info)
+                .iter()
+                .map(|dependent| dependent.name().to_string())
+                .collect()
+        };
+        if !dependents.is_empty() && !self.confirm_disable {
+            self.confirm_disable = true;
+            self.message = Some(format!(
+                "{} is required by {}, press space again to disable it",
+                info.name(),
+                dependents.join(", ")
+            ));
+            return;
+        }
+        self.confirm_disable = false;
+
+        let info = &mut self.mods[index];
+        self.message = Some(match info.file.set_enabled(enable) {
+            Err(err) => format!("Failed to toggle {}: {}", info.name(), err),
+            Ok(()) if !dependents.is_empty() => format!(
+                "Disabled {}, still required by {}",
+                info.name(),
+                dependents.join(", ")
+            ),
+            Ok(()) => format!(
+                "{} {}",
+                if enable { "Enabled" } else { "Disabled" },
+                info.name()
+            ),
+        });
+        if let Some(instance) = &self.instance {
+            self.problems = check::check(instance, &self.mods);
+        }
+        self.update_details();
+    }
 
-use super::styled_title;
+    fn next(&mut self) {
+        if self.mods.is_empty() {
+            return;
+        }
+        let i = match self.table_state.selected() {
+            Some(i) if i + 1 < self.mods.len() => i + 1,
+            _ => 0,
+        };
+        self.table_state.select(Some(i));
+        self.update_details();
+    }
 
-pub fn render(frame: &mut Frame, area: Rect, focused: FocusedArea) {
+    fn previous(&mut self) {
+        if self.mods.is_empty() {
+            return;
+        }
+        let i = match self.table_state.selected() {
+            Some(0) | None => self.mods.len() - 1,
+            Some(i) => i - 1,
+        };
+        self.table_state.select(Some(i));
+        self.update_details();
+    }
+}
+
+impl WidgetKey for State {
+    fn handle_key(&mut self, key_event: &crossterm::event::KeyEvent) {
+        let capturing = self.capturing_input();
+        if key_event.code != KeyCode::Char(' ') {
+            self.confirm_disable = false;
+        }
+        match key_event.code {
+            KeyCode::Tab if !capturing => self.view = self.view.cycle(true),
+            KeyCode::BackTab if !capturing => self.view = self.view.cycle(false),
+            _ => match self.view {
+                View::Mods => self.handle_mods_key(key_event),
+                View::ResourcePacks => self.resource_packs.handle_key(key_event),
+                View::Shaders => self.shaders.handle_key(key_event),
+                View::Worlds => self.worlds.handle_key(key_event),
+                View::Servers => self.servers.handle_key(key_event),
+                View::Options => self.options.handle_key(key_event),
+                View::Log => self.log.handle_key(key_event),
+            },
+        }
+    }
+}
+
+impl State {
+    fn handle_mods_key(&mut self, key_event: &crossterm::event::KeyEvent) {
+        match key_event.code {
+            KeyCode::Char('j') | KeyCode::Down => self.next(),
+            KeyCode::Char('k') | KeyCode::Up => self.previous(),
+            KeyCode::Char(' ') => self.toggle_selected(),
+            KeyCode::Char('u') => self.check_updates(),
+            KeyCode::Char('i') => self.identify(),
+            KeyCode::Char('r') => self.reload(),
+            _ => {}
+        }
+    }
+}
+
+fn severity_color(severity: Severity) -> Color {
+    match severity {
+        Severity::Error => Color::Red,
+        Severity::Warning => Color::Yellow,
+    }
+}
+
+fn mod_details(info: &ModInfo, problems: &[&Problem]) -> Details {
+    let mut lines = vec![Line::from(format!("{} {}", info.name(), info.version()))];
+    if !info.file.enabled {
+        lines.push(Line::from(Span::styled("disabled", Style::default().fg(Color::Red))));
+    }
+    for problem in problems {
+        lines.push(Line::from(Span::styled(
+            problem.message.clone(),
+            Style::default().fg(severity_color(problem.severity)),
+        )));
+    }
+
+    if let Some(meta) = &info.metadata {
+        lines.push(Line::from(format!("id: {} ({})", meta.id, meta.source.name())));
+        if !meta.authors.is_empty() {
+            lines.push(Line::from(format!("by {}", meta.authors.join(", "))));
+        }
+        if !meta.description.is_empty() {
+            lines.push(Line::from(meta.description.clone()));
+        }
+        let required: Vec<&str> = meta
+            .required()
+            .map(|dep| dep.id.as_str())
+            .filter(|id| !matches!(*id, "minecraft" | "java" | "fabricloader" | "forge" | "neoforge"))
+            .collect();
+        if !required.is_empty() {
+            lines.push(Line::from(format!("requires: {}", required.join(", "))));
+        }
+    }
+
+    match &info.project {
+        Some(project) => lines.push(Line::from(Span::styled(
+            format!("modrinth: {}", project.slug),
+            Style::default().fg(Color::Green),
+        ))),
+        None => lines.push(Line::from(Span::styled(
+            "not linked to Modrinth",
+            Style::default().fg(Color::DarkGray),
+        ))),
+    }
+
+    let icon = info
+        .metadata
+        .as_ref()
+        .and_then(|meta| meta.icon.as_deref())
+        .and_then(|icon| metadata::read_icon(&info.file.path, icon))
+        .and_then(|bytes| ascii::render(&bytes, ICON_WIDTH));
+
+    Details {
+        title: info.file.file_name.clone(),
+        lines,
+        icon,
+    }
+}
+
+pub fn render(frame: &mut Frame, area: Rect, focused: FocusedArea, state: &mut State) {
     let color = if focused == FocusedArea::Content {
-        Color::White
+        SETTINGS.colors.border_focused
     } else {
-        Color::DarkGray
+        SETTINGS.colors.border_unfocused
     };
 
+    let tabs: Vec<Span> = View::ALL
+        .iter()
+        .map(|view| {
+            let style = if *view == state.view {
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            Span::styled(format!(" {} ", view.title()), style)
+        })
+        .collect();
+
     let block = Block::default()
         .title(styled_title("Content", true))
+        .title(Line::from(tabs))
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
         .border_style(Style::default().fg(color));
 
-    let widget = Paragraph::new("TEST CONTENT").block(block);
-    frame.render_widget(widget, area);
+    if state.instance.is_none() {
+        let widget = Paragraph::new("No instance selected").block(block);
+        frame.render_widget(widget, area);
+        return;
+    }
+
+    match state.view {
+        View::Mods => render_mods(frame, area, block, state),
+        View::ResourcePacks => resourcepacks::render(frame, area, block, &mut state.resource_packs),
+        View::Shaders => shaders::render(frame, area, block, &mut state.shaders),
+        View::Worlds => worlds::render(frame, area, block, &mut state.worlds),
+        View::Servers => servers::render(frame, area, block, &mut state.servers),
+        View::Options => options::render(frame, area, block, &mut state.options),
+        View::Log => log::render(frame, area, block, &mut state.log),
+    }
 }
 
-pub fn title(frame: &mut Frame, area: Rect, focused: FocusedArea) {
+fn render_mods(frame: &mut Frame, area: Rect, mut block: Block, state: &mut State) {
+    if let Some(message) = &state.message {
+        block = block.title_bottom(Line::from(format!(" {} ", message)));
+    }
+
+    let errors = state
+        .problems
+        .iter()
+        .filter(|problem| problem.severity == Severity::Error)
+        .count();
+    let warnings = state.problems.len() - errors;
+    if !state.problems.is_empty() {
+        let color = if errors > 0 { Color::Red } else { Color::Yellow };
+        block = block.title_bottom(
+            Line::from(Span::styled(
+                format!(" {} errors, {} warnings ", errors, warnings),
+                Style::default().fg(color),
+            ))
+            .right_aligned(),
+        );
+    }
+
+    let rows = state.mods.iter().enumerate().map(|(i, info)| {
+        let background_color = if i % 2 == 0 {
+            SETTINGS.colors.row_background
+        } else {
+            SETTINGS.colors.row_alternate_bg
+        };
+
+        let worst = state
+            .problems
+            .iter()
+            .filter(|problem| problem.involves(&info.file.file_name))
+            .map(|problem| problem.severity)
+            .max();
+        let status = match (state.outdated.get(&info.file.file_name), worst) {
+            (Some(latest), _) => Span::styled(format!("↑ {}", latest), Style::default().fg(Color::Yellow)),
+            (None, Some(severity)) => Span::styled("!", Style::default().fg(severity_color(severity))),
+            (None, None) => Span::raw(""),
+        };
+
+        let (marker, style) = if info.file.enabled {
+            ("[x]", Style::default().bg(background_color))
+        } else {
+            (
+                "[ ]",
+                Style::default()
+                    .bg(background_color)
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::CROSSED_OUT),
+            )
+        };
+
+        Row::new(vec![
+            Cell::from(marker),
+            Cell::from(info.name().to_string()),
+            Cell::from(info.version().to_string()),
+            Cell::from(status),
+        ])
+        .style(style)
+    });
+
+    let widths = [
+        Constraint::Length(3),
+        Constraint::Percentage(50),
+        Constraint::Percentage(25),
+        Constraint::Percentage(25),
+    ];
+
+    let table = Table::new(rows, widths)
+        .block(block)
+        .row_highlight_style(
+            Style::default()
+                .add_modifier(Modifier::REVERSED)
+                .fg(SETTINGS.colors.row_highlight),
+        );
+
+    frame.render_stateful_widget(table, area, &mut state.table_state);
+}
+
+pub fn title(frame: &mut Frame, area: Rect, focused: FocusedArea, instance: Option<&Instance>) {
     let color = if focused == FocusedArea::Content {
         Color::White
     } else {
@@ -39,6 +573,16 @@ pub fn title(frame: &mut Frame, area: Rect, focused: FocusedArea) {
         .border_type(BorderType::Rounded)
         .border_style(Style::default().fg(color));
 
-    let widget = Paragraph::new("TEST CONTENT").block(block);
+    let text = match instance {
+        Some(instance) => format!(
+            "{} - {} {}",
+            instance.name,
+            instance.game_version,
+            instance.loader.as_str()
+        ),
+        None => String::new(),
+    };
+
+    let widget = Paragraph::new(text).block(block);
     frame.render_widget(widget, area);
 }
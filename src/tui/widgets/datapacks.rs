@@ -0,0 +1,303 @@
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use color_eyre::eyre::Result;
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Cell, Row, Table, TableState},
+    Frame,
+};
+
+use crate::{
+    config::SETTINGS,
+    instance::Instance,
+    packs::data::{self, DataPack, Status},
+    worlds::World,
+};
+
+use super::{ascii, details::Details, WidgetKey};
+
+const ICON_WIDTH: u32 = 16;
+
+/// Data packs of one world, opened from the Worlds tab
+#[derive(Debug)]
+pub struct State {
+    pub instance: Instance,
+    pub world: World,
+    pub packs: Vec<DataPack>,
+    pub table_state: TableState,
+    pub message: Option<String>,
+    pub details: Option<Details>,
+    /// Zip path or Modrinth project being typed after `i`
+    pub input: Option<String>,
+    /// Set after the first `d`, the second one removes
+    confirm_remove: bool,
+    install: Option<Receiver<Result<DataPack>>>,
+}
+
+impl State {
+    pub fn new(instance: Instance, world: World) -> State {
+        let mut state = State {
+            instance,
+            world,
+            packs: Vec::new(),
+            table_state: TableState::default(),
+            message: None,
+            details: None,
+            input: None,
+            confirm_remove: false,
+            install: None,
+        };
+        state.reload();
+        state
+    }
+
+    pub fn reload(&mut self) {
+        self.packs = data::list(&self.world);
+        if self.packs.is_empty() {
+            self.table_state.select(None);
+        } else {
+            let index = self.table_state.selected().unwrap_or(0);
+            self.table_state.select(Some(index.min(self.packs.len() - 1)));
+        }
+        self.update_details();
+    }
+
+    pub fn capturing_input(&self) -> bool {
+        self.input.is_some()
+    }
+
+    pub fn selected(&self) -> Option<&DataPack> {
+        self.table_state.selected().and_then(|i| self.packs.get(i))
+    }
+
+    fn update_details(&mut self) {
+        self.details = self.selected().map(pack_details);
+    }
+
+    fn toggle_selected(&mut self) {
+        let Some(pack) = self.selected() else {
+            return;
+        };
+        let enable = !pack.status.enabled();
+        self.message = Some(match data::set_enabled(&self.world, pack, enable) {
+            Ok(()) => format!("{} {}", if enable { "Enabled" } else { "Disabled" }, pack.pack.name),
+            Err(err) => format!("Failed to update level.dat: {:#}", err),
+        });
+        self.reload();
+    }
+
+    fn remove_selected(&mut self) {
+        let Some(pack) = self.selected().cloned() else {
+            return;
+        };
+        if !self.confirm_remove {
+            self.confirm_remove = true;
+            self.message = Some(format!("Press d again to delete {}", pack.pack.name));
+            return;
+        }
+
+        self.confirm_remove = false;
+        self.message = Some(match data::remove(&self.world, &pack) {
+            Ok(()) => format!("Removed {}", pack.pack.name),
+            Err(err) => format!("{:#}", err),
+        });
+        self.reload();
+    }
+
+    /// Installs in the background since Modrinth downloads can take a while
+    fn start_install(&mut self, source: String) {
+        let source = source.trim().to_string();
+        if source.is_empty() || self.install.is_some() {
+            return;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let (instance, world) = (self.instance.clone(), self.world.clone());
+        self.message = Some(format!("Installing {}...", source));
+        thread::spawn(move || {
+            let _ = sender.send(data::install(&instance, &world, &source));
+        });
+        self.install = Some(receiver);
+    }
+
+    pub fn poll(&mut self) {
+        let Some(receiver) = &self.install else {
+            return;
+        };
+        let message = match receiver.try_recv() {
+            Ok(Ok(pack)) => format!("Installed and enabled {}", pack.pack.name),
+            Ok(Err(err)) => format!("Install failed: {:#}", err),
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => "Install failed".to_string(),
+        };
+        self.message = Some(message);
+        self.install = None;
+        self.reload();
+    }
+
+    fn handle_input(&mut self, code: KeyCode) {
+        let Some(input) = &mut self.input else {
+            return;
+        };
+        match code {
+            KeyCode::Enter => {
+                let source = input.clone();
+                self.input = None;
+                self.start_install(source);
+            }
+            KeyCode::Esc => self.input = None,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        }
+    }
+
+    fn next(&mut self) {
+        if self.packs.is_empty() {
+            return;
+        }
+        let i = match self.table_state.selected() {
+            Some(i) if i + 1 < self.packs.len() => i + 1,
+            _ => 0,
+        };
+        self.table_state.select(Some(i));
+        self.update_details();
+    }
+
+    fn previous(&mut self) {
+        if self.packs.is_empty() {
+            return;
+        }
+        let i = match self.table_state.selected() {
+            Some(0) | None => self.packs.len() - 1,
+            Some(i) => i - 1,
+        };
+        self.table_state.select(Some(i));
+        self.update_details();
+    }
+}
+
+impl WidgetKey for State {
+    fn handle_key(&mut self, key_event: &crossterm::event::KeyEvent) {
+        if self.capturing_input() {
+            self.handle_input(key_event.code);
+            return;
+        }
+        if key_event.code != KeyCode::Char('d') {
+            self.confirm_remove = false;
+        }
+
+        match key_event.code {
+            KeyCode::Char('j') | KeyCode::Down => self.next(),
+            KeyCode::Char('k') | KeyCode::Up => self.previous(),
+            KeyCode::Char(' ') => self.toggle_selected(),
+            KeyCode::Char('d') => self.remove_selected(),
+            KeyCode::Char('i') => self.input = Some(String::new()),
+            KeyCode::Char('r') => self.reload(),
+            _ => {}
+        }
+    }
+}
+
+fn status_span(status: Status) -> Span<'static> {
+    match status {
+        Status::Enabled => Span::raw(""),
+        Status::Disabled => Span::styled("disabled", Style::default().fg(Color::Red)),
+        Status::New => Span::styled("new", Style::default().fg(Color::Yellow)),
+    }
+}
+
+fn pack_details(pack: &DataPack) -> Details {
+    let mut lines = vec![Line::from(pack.pack.name.clone())];
+    match pack.status {
+        Status::Enabled => {}
+        Status::Disabled => lines.push(Line::from(status_span(pack.status))),
+        Status::New => lines.push(Line::from(Span::styled(
+            "enabled when the world next loads",
+            Style::default().fg(Color::Yellow),
+        ))),
+    }
+    lines.extend(
+        pack.pack
+            .description
+            .lines()
+            .map(|line| Line::from(line.to_string())),
+    );
+    let format = match (pack.pack.supported, pack.pack.pack_format) {
+        (Some((min, max)), _) => format!("formats {}-{}", min, max),
+        (None, Some(format)) => format!("format {}", format),
+        (None, None) => "no pack.mcmeta".to_string(),
+    };
+    lines.push(Line::from(format));
+    if pack.pack.folder {
+        lines.push(Line::from(Span::styled("folder", Style::default().fg(Color::DarkGray))));
+    }
+
+    Details {
+        title: pack.pack.name.clone(),
+        lines,
+        icon: pack.pack.icon().and_then(|bytes| ascii::render(&bytes, ICON_WIDTH)),
+    }
+}
+
+pub fn render(frame: &mut Frame, area: Rect, mut block: Block, state: &mut State) {
+    block = block.title(Line::from(format!(" {} data packs (Esc to go back) ", state.world.name)));
+    if let Some(input) = &state.input {
+        block = block.title_bottom(Line::from(Span::styled(
+            format!(" Install zip or Modrinth project: {}_ ", input),
+            Style::default().fg(Color::Yellow),
+        )));
+    } else if let Some(message) = &state.message {
+        block = block.title_bottom(Line::from(format!(" {} ", message)));
+    }
+    let enabled = state.packs.iter().filter(|pack| pack.status.enabled()).count();
+    block = block.title_bottom(Line::from(format!(" {} of {} enabled ", enabled, state.packs.len())).right_aligned());
+
+    let rows = state.packs.iter().enumerate().map(|(i, pack)| {
+        let background_color = if i % 2 == 0 {
+            SETTINGS.colors.row_background
+        } else {
+            SETTINGS.colors.row_alternate_bg
+        };
+
+        let (marker, style) = if pack.status.enabled() {
+            ("[x]", Style::default().bg(background_color))
+        } else {
+            (
+                "[ ]",
+                Style::default().bg(background_color).fg(Color::DarkGray),
+            )
+        };
+
+        Row::new(vec![
+            Cell::from(marker),
+            Cell::from(pack.pack.name.clone()),
+            Cell::from(pack.pack.description.lines().next().unwrap_or_default().to_string()),
+            Cell::from(status_span(pack.status)),
+        ])
+        .style(style)
+    });
+
+    let widths = [
+        Constraint::Length(3),
+        Constraint::Percentage(35),
+        Constraint::Percentage(50),
+        Constraint::Percentage(15),
+    ];
+
+    let table = Table::new(rows, widths)
+        .block(block)
+        .row_highlight_style(
+            Style::default()
+                .add_modifier(Modifier::REVERSED)
+                .fg(SETTINGS.colors.row_highlight),
+        );
+
+    frame.render_stateful_widget(table, area, &mut state.table_state);
+}
@@ -1,7 +1,8 @@
 use ratatui::{
-    layout::Rect,
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Style},
-    widgets::{Block, BorderType, Borders, Paragraph},
+    text::Line,
+    widgets::{Block, BorderType, Borders, Paragraph, Wrap},
     Frame,
 };
 
@@ -9,19 +10,52 @@ use crate::tui::layout::FocusedArea;
 
 use super::styled_title;
 
-pub fn render(frame: &mut Frame, area: Rect, focused: FocusedArea) {
+/// What the Details panel shows for the item selected elsewhere
+#[derive(Debug, Clone, Default)]
+pub struct Details {
+    pub title: String,
+    pub lines: Vec<Line<'static>>,
+    /// Pre-rendered ASCII art shown left of the text
+    pub icon: Option<String>,
+}
+
+pub fn render(frame: &mut Frame, area: Rect, focused: FocusedArea, details: Option<&Details>) {
     let color = if focused == FocusedArea::Details {
         Color::White
     } else {
         Color::DarkGray
     };
 
-    let block = Block::default()
+    let mut block = Block::default()
         .title(styled_title("Details", true))
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
         .border_style(Style::default().fg(color));
 
-    let widget = Paragraph::new("TEST CONTENT").block(block);
-    frame.render_widget(widget, area);
+    let Some(details) = details else {
+        frame.render_widget(block, area);
+        return;
+    };
+
+    if !details.title.is_empty() {
+        block = block.title_bottom(Line::from(format!(" {} ", details.title)));
+    }
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let text_area = match &details.icon {
+        Some(icon) => {
+            let width = icon.lines().map(|line| line.chars().count()).max().unwrap_or(0) as u16;
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(width + 1), Constraint::Min(0)])
+                .split(inner);
+            frame.render_widget(Paragraph::new(icon.as_str()), chunks[0]);
+            chunks[1]
+        }
+        None => inner,
+    };
+
+    let widget = Paragraph::new(details.lines.clone()).wrap(Wrap { trim: false });
+    frame.render_widget(widget, text_area);
 }
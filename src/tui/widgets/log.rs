@@ -0,0 +1,608 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use chrono::{Local, NaiveDate};
+use crossterm::event::{KeyCode, KeyModifiers};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
+    Frame,
+};
+
+use crate::{
+    instance::{process, Instance},
+    logs::{
+        self,
+        history::{self, Filter, LogFile},
+        redact::Redactor,
+        Event, Level, Parser,
+    },
+};
+
+use super::WidgetKey;
+
+/// Older events are dropped so a long session doesn't grow without bound
+const MAX_EVENTS: usize = 20_000;
+
+/// Events kept of a log file, enough for a long `debug.log`
+const MAX_FILE_EVENTS: usize = 200_000;
+
+/// Lines moved by PageUp/PageDown
+const PAGE: usize = 20;
+
+/// Where the shown events come from
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Source {
+    /// Output of the current or last run, see [`logs::output_log`]
+    #[default]
+    Live,
+    File(LogFile),
+}
+
+impl Source {
+    fn name(&self) -> String {
+        match self {
+            Source::Live => "live output".to_string(),
+            Source::File(file) => file.name.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Input {
+    Search(String),
+    /// `since..until`, either side optional
+    Time(String),
+}
+
+/// Events of a log file read in the background, and how many were dropped
+type Loaded = Result<(Vec<Event>, usize), String>;
+
+/// Where each event passing the filters starts in the display lines, so a
+/// frame only formats the events in view
+#[derive(Debug, Default)]
+struct Layout {
+    /// Index into the events and first display line of each shown event
+    starts: Vec<(usize, usize)>,
+    lines: usize,
+}
+
+impl Layout {
+    fn new(events: &[Event], filter: &Filter) -> Layout {
+        let mut layout = Layout::default();
+        let mut last_time = None;
+        for (index, event) in events.iter().enumerate() {
+            last_time = event.time.or(last_time);
+            if filter.matches(event, last_time) {
+                layout.starts.push((index, layout.lines));
+                layout.lines += line_count(event);
+            }
+        }
+        layout
+    }
+}
+
+/// Game output of the selected instance: the live output of its latest
+/// run or one of the logs in `logs/`
+#[derive(Debug, Default)]
+pub struct State {
+    pub instance: Option<Instance>,
+    pub source: Source,
+    pub events: Vec<Event>,
+    pub filter: Filter,
+    /// Search as typed, shown in the status line
+    pub query: String,
+    /// Time range as typed
+    pub range: String,
+    /// Lines scrolled up from the bottom, 0 follows new output
+    pub scroll: usize,
+    /// Result of the last action, e.g. an export
+    pub message: Option<String>,
+    input: Option<Input>,
+    loading: Option<Receiver<Loaded>>,
+    /// Events of the file left out because of [`MAX_FILE_EVENTS`]
+    dropped: usize,
+    offset: u64,
+    /// Bytes after the last newline, waiting for the rest of the line
+    partial: Vec<u8>,
+    parser: Parser,
+    /// Game process the events belong to, a new run starts over
+    pid: Option<u32>,
+    /// Rebuilt after the events or filters change
+    layout: Option<Layout>,
+}
+
+impl State {
+    pub fn select(&mut self, instance: Option<Instance>) {
+        self.instance = instance;
+        self.input = None;
+        self.query.clear();
+        self.range.clear();
+        self.filter = Filter::default();
+        self.message = None;
+        self.open(Source::Live);
+    }
+
+    fn open(&mut self, source: Source) {
+        self.source = source;
+        self.clear();
+        match &self.source {
+            Source::Live => self.poll(),
+            Source::File(file) => self.load(file.clone()),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.events.clear();
+        self.loading = None;
+        self.dropped = 0;
+        self.offset = 0;
+        self.partial.clear();
+        self.parser = Parser::default();
+        self.scroll = 0;
+        self.pid = None;
+        self.layout = None;
+    }
+
+    pub fn capturing_input(&self) -> bool {
+        self.input.is_some()
+    }
+
+    /// Reads a log file on a background thread, big gzipped logs take a
+    /// while
+    fn load(&mut self, file: LogFile) {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut events = Vec::new();
+            let mut dropped = 0;
+            let result = history::read(&file, &Filter::default(), |event| {
+                events.push(event);
+                if events.len() >= MAX_FILE_EVENTS * 2 {
+                    dropped += events.len() - MAX_FILE_EVENTS;
+                    events.drain(..events.len() - MAX_FILE_EVENTS);
+                }
+            });
+            if events.len() > MAX_FILE_EVENTS {
+                dropped += events.len() - MAX_FILE_EVENTS;
+                events.drain(..events.len() - MAX_FILE_EVENTS);
+            }
+            let _ = sender.send(result.map(|_| (events, dropped)).map_err(|err| format!("{:#}", err)));
+        });
+        self.loading = Some(receiver);
+    }
+
+    /// Picks up new live output or a log file that finished loading
+    pub fn poll(&mut self) {
+        if let Some(receiver) = &self.loading {
+            match receiver.try_recv() {
+                Ok(Ok((events, dropped))) => {
+                    self.events = events;
+                    self.dropped = dropped;
+                    self.loading = None;
+                    self.layout = None;
+                }
+                Ok(Err(err)) => {
+                    self.message = Some(err);
+                    self.loading = None;
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => self.loading = None,
+            }
+            return;
+        }
+        if self.source == Source::Live {
+            self.tail();
+        }
+    }
+
+    /// Reads whatever the game wrote since the last call
+    fn tail(&mut self) {
+        let Some(instance) = &self.instance else {
+            return;
+        };
+        let path = logs::output_log(&instance.path);
+        let pid = process::session_at(&instance.path).map(|session| session.pid);
+        let Ok(mut file) = File::open(path) else {
+            return;
+        };
+        let Ok(length) = file.metadata().map(|metadata| metadata.len()) else {
+            return;
+        };
+        if pid != self.pid || length < self.offset {
+            self.clear();
+            self.pid = pid;
+        }
+        if length == self.offset {
+            return;
+        }
+
+        let mut bytes = Vec::new();
+        if file.seek(SeekFrom::Start(self.offset)).is_err() || file.read_to_end(&mut bytes).is_err() {
+            return;
+        }
+        self.offset += bytes.len() as u64;
+        self.partial.extend_from_slice(&bytes);
+
+        let Some(end) = self.partial.iter().rposition(|&byte| byte == b'\n') else {
+            return;
+        };
+        let complete: Vec<u8> = self.partial.drain(..=end).collect();
+        let before = self.line_count();
+        for line in String::from_utf8_lossy(&complete).lines() {
+            if let Some(parsed) = self.parser.push(line) {
+                logs::append(&mut self.events, parsed);
+            }
+        }
+        if self.events.len() > MAX_EVENTS {
+            self.events.drain(..self.events.len() - MAX_EVENTS);
+        }
+        self.layout = None;
+        // keep the view still while scrolled up
+        if self.scroll > 0 {
+            self.scroll += self.line_count().saturating_sub(before);
+        }
+    }
+
+    /// Events passing the filters
+    fn shown(&self) -> Vec<&Event> {
+        let mut last_time = None;
+        self.events
+            .iter()
+            .filter(|event| {
+                last_time = event.time.or(last_time);
+                self.filter.matches(event, last_time)
+            })
+            .collect()
+    }
+
+    /// Number of display lines of the events passing the filters
+    fn line_count(&mut self) -> usize {
+        self.layout.get_or_insert_with(|| Layout::new(&self.events, &self.filter)).lines
+    }
+
+    /// Display lines `start..start + height` of the events passing the
+    /// filters, formatting only the events they belong to
+    fn window(&mut self, start: usize, height: usize) -> Vec<Line<'static>> {
+        let layout = self.layout.get_or_insert_with(|| Layout::new(&self.events, &self.filter));
+        let first = layout.starts.partition_point(|&(_, line)| line <= start).saturating_sub(1);
+        let events = &self.events;
+        layout.starts[first..]
+            .iter()
+            .flat_map(|&(index, line)| (line..).zip(event_lines(&events[index])))
+            .skip_while(|(line, _)| *line < start)
+            .take(height)
+            .map(|(_, line)| line)
+            .collect()
+    }
+
+    /// Day times without a date refer to
+    fn date(&self) -> NaiveDate {
+        match &self.source {
+            Source::Live => Local::now().date_naive(),
+            Source::File(file) => file.date(),
+        }
+    }
+
+    /// Switches between the live output and the log files, `step` files at a
+    /// time
+    fn cycle_source(&mut self, step: isize) {
+        let Some(instance) = &self.instance else {
+            return;
+        };
+        let mut sources = vec![Source::Live];
+        sources.extend(history::list(&instance.path).into_iter().map(Source::File));
+        let current = sources
+            .iter()
+            .position(|source| match (source, &self.source) {
+                (Source::File(a), Source::File(b)) => a.path == b.path,
+                (a, b) => a == b,
+            })
+            .unwrap_or(0);
+        let next = (current as isize + step).rem_euclid(sources.len() as isize) as usize;
+        self.message = None;
+        self.open(sources.swap_remove(next));
+    }
+
+    /// Cycles the level filter: all, debug, info, warn, error
+    fn cycle_level(&mut self) {
+        self.filter.min_level = match self.filter.min_level {
+            None => Some(Level::Debug),
+            Some(Level::Trace) | Some(Level::Debug) => Some(Level::Info),
+            Some(Level::Info) => Some(Level::Warn),
+            Some(Level::Warn) => Some(Level::Error),
+            Some(Level::Error) | Some(Level::Fatal) => None,
+        };
+        self.layout = None;
+        self.scroll = 0;
+    }
+
+    fn set_query(&mut self, query: &str) {
+        self.query = query.trim().to_string();
+        self.filter.pattern = if self.query.is_empty() {
+            None
+        } else {
+            // anything that isn't a valid regex is searched for literally
+            history::search(&self.query)
+                .or_else(|_| history::search(&regex::escape(&self.query)))
+                .ok()
+        };
+        self.layout = None;
+        self.scroll = 0;
+    }
+
+    fn set_range(&mut self, range: &str) {
+        let range = range.trim();
+        let (since, until) = range.split_once("..").unwrap_or((range, ""));
+        let date = self.date();
+        let parse = |value: &str| {
+            let value = value.trim();
+            (!value.is_empty()).then(|| history::parse_time(value, date)).transpose()
+        };
+        match (parse(since), parse(until)) {
+            (Ok(since), Ok(until)) => {
+                self.filter.since = since;
+                self.filter.until = until;
+                self.range = range.to_string();
+                self.message = None;
+            }
+            (Err(err), _) | (_, Err(err)) => self.message = Some(format!("{:#}", err)),
+        }
+        self.layout = None;
+        self.scroll = 0;
+    }
+
+    /// Writes the shown events with personal details redacted, for
+    /// attaching to a bug report
+    fn export(&mut self) {
+        let Some(instance) = &self.instance else {
+            return;
+        };
+        let stem = match &self.source {
+            Source::Live => "output".to_string(),
+            Source::File(file) => file.name.trim_end_matches(".gz").trim_end_matches(".log").to_string(),
+        };
+        let path = history::logs_dir(&instance.path).join("redacted").join(format!("{}.log", stem));
+        let result = (|| -> std::io::Result<usize> {
+            fs::create_dir_all(path.parent().unwrap_or(&path))?;
+            let mut out = BufWriter::new(File::create(&path)?);
+            let mut redactor = Redactor::default();
+            // names are learned from filtered out lines too, like `Setting user`
+            for event in &self.events {
+                redactor.learn(&event.format());
+            }
+            let shown = self.shown();
+            for event in &shown {
+                writeln!(out, "{}", redactor.redact(&event.format()))?;
+            }
+            out.flush()?;
+            Ok(shown.len())
+        })();
+        self.message = Some(match result {
+            Ok(count) => format!("Exported {} records to {}", count, display(&path)),
+            Err(err) => format!("Export failed: {}", err),
+        });
+    }
+
+    fn handle_input(&mut self, code: KeyCode) {
+        let Some(input) = &mut self.input else {
+            return;
+        };
+        let text = match input {
+            Input::Search(text) | Input::Time(text) => text,
+        };
+        match code {
+            KeyCode::Enter => match self.input.take() {
+                Some(Input::Search(query)) => self.set_query(&query),
+                Some(Input::Time(range)) => self.set_range(&range),
+                None => {}
+            },
+            KeyCode::Esc => self.input = None,
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Char(c) => text.push(c),
+            _ => {}
+        }
+    }
+}
+
+/// Path with the home directory shortened to `~`
+fn display(path: &Path) -> String {
+    match dirs_next::home_dir().and_then(|home| path.strip_prefix(home).ok().map(PathBuf::from)) {
+        Some(relative) => format!("~/{}", relative.display()),
+        None => path.display().to_string(),
+    }
+}
+
+impl WidgetKey for State {
+    fn handle_key(&mut self, key_event: &crossterm::event::KeyEvent) {
+        if self.capturing_input() {
+            self.handle_input(key_event.code);
+            return;
+        }
+
+        let ctrl = key_event.modifiers.contains(KeyModifiers::CONTROL);
+        match key_event.code {
+            KeyCode::Char('k') | KeyCode::Up => self.scroll += 1,
+            KeyCode::Char('j') | KeyCode::Down => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Char('u') if ctrl => self.scroll += PAGE,
+            KeyCode::Char('d') if ctrl => self.scroll = self.scroll.saturating_sub(PAGE),
+            KeyCode::PageUp => self.scroll += PAGE,
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(PAGE),
+            KeyCode::Char('g') | KeyCode::Home => self.scroll = usize::MAX,
+            KeyCode::Char('G') | KeyCode::End => self.scroll = 0,
+            KeyCode::Char('f') => self.cycle_level(),
+            KeyCode::Char('/') => self.input = Some(Input::Search(self.query.clone())),
+            KeyCode::Char('t') => self.input = Some(Input::Time(self.range.clone())),
+            KeyCode::Char(']') => self.cycle_source(1),
+            KeyCode::Char('[') => self.cycle_source(-1),
+            KeyCode::Char('e') => self.export(),
+            KeyCode::Esc => {
+                self.set_query("");
+                self.set_range("");
+                self.message = None;
+            }
+            KeyCode::Char('r') => self.open(self.source.clone()),
+            _ => {}
+        }
+    }
+}
+
+pub fn level_color(level: Level) -> Color {
+    match level {
+        Level::Fatal | Level::Error => Color::Red,
+        Level::Warn => Color::Yellow,
+        Level::Info => Color::Reset,
+        Level::Debug | Level::Trace => Color::DarkGray,
+    }
+}
+
+/// How many lines [`event_lines`] gives, without formatting them
+fn line_count(event: &Event) -> usize {
+    event.message.lines().count().max(1) + event.throwable.as_deref().map_or(0, |throwable| throwable.lines().count())
+}
+
+fn event_lines(event: &Event) -> Vec<Line<'static>> {
+    let color = level_color(event.level);
+    let mut prefix = Vec::new();
+    if let Some(time) = event.time {
+        prefix.push(Span::styled(
+            format!("{} ", time.format("%H:%M:%S")),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    if !event.thread.is_empty() || event.time.is_some() {
+        prefix.push(Span::styled(
+            format!("{:<5} ", event.level.name()),
+            Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if !event.logger.is_empty() {
+        prefix.push(Span::styled(
+            format!("{} ", logs::short_logger(&event.logger)),
+            Style::default().fg(Color::Cyan),
+        ));
+    }
+
+    let mut lines = Vec::new();
+    for (i, text) in event.message.lines().enumerate() {
+        let span = Span::styled(text.replace('\t', "    "), Style::default().fg(color));
+        if i == 0 {
+            let mut spans = prefix.clone();
+            spans.push(span);
+            lines.push(Line::from(spans));
+        } else {
+            lines.push(Line::from(span));
+        }
+    }
+    if lines.is_empty() {
+        lines.push(Line::from(prefix));
+    }
+    if let Some(throwable) = &event.throwable {
+        lines.extend(throwable.lines().map(|text| {
+            Line::from(Span::styled(text.replace('\t', "    "), Style::default().fg(Color::Red)))
+        }));
+    }
+    lines
+}
+
+pub fn render(frame: &mut Frame, area: Rect, mut block: Block, state: &mut State) {
+    let height = area.height.saturating_sub(2) as usize;
+    let max_scroll = state.line_count().saturating_sub(height);
+    state.scroll = state.scroll.min(max_scroll);
+    let start = max_scroll - state.scroll;
+
+    block = block.title(Line::from(format!(" {} ", state.source.name())).right_aligned());
+    if let Some(input) = &state.input {
+        let (label, text) = match input {
+            Input::Search(text) => ("Search (regex)", text),
+            Input::Time(text) => ("Time range (since..until)", text),
+        };
+        block = block.title_bottom(Line::from(Span::styled(
+            format!(" {}: {}_ ", label, text),
+            Style::default().fg(Color::Yellow),
+        )));
+    } else if let Some(message) = &state.message {
+        block = block.title_bottom(Line::from(Span::styled(
+            format!(" {} ", message),
+            Style::default().fg(Color::Yellow),
+        )));
+    } else {
+        let errors = state.events.iter().filter(|event| event.level >= Level::Error).count();
+        let warnings = state.events.iter().filter(|event| event.level == Level::Warn).count();
+        let mut status = format!(" {} events, {} errors, {} warnings", state.events.len(), errors, warnings);
+        if state.dropped > 0 {
+            status.push_str(&format!(" ({} oldest not loaded)", state.dropped));
+        }
+        if let Some(level) = state.filter.min_level {
+            status.push_str(&format!(", {}+", level));
+        }
+        if !state.range.is_empty() {
+            status.push_str(&format!(", {}", state.range));
+        }
+        if !state.query.is_empty() {
+            status.push_str(&format!(", matching /{}/", state.query));
+        }
+        status.push(' ');
+        block = block.title_bottom(Line::from(status));
+    }
+    let position = if state.scroll == 0 {
+        " following ".to_string()
+    } else {
+        format!(" {} lines up ", state.scroll)
+    };
+    block = block.title_bottom(Line::from(position).right_aligned());
+
+    if state.loading.is_some() {
+        let widget = Paragraph::new(format!("Reading {}...", state.source.name())).block(block);
+        frame.render_widget(widget, area);
+        return;
+    }
+    if state.events.is_empty() {
+        let text = match state.source {
+            Source::Live => "No output yet, launch the game to see its log ([ and ] switch to saved logs)",
+            Source::File(_) => "This log is empty",
+        };
+        frame.render_widget(Paragraph::new(text).block(block), area);
+        return;
+    }
+
+    let visible = state.window(start, height);
+    frame.render_widget(Paragraph::new(visible).block(block), area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_matches_formatting_every_event() {
+        let mut parser = Parser::default();
+        let text = "[12:00:00] [Render thread/INFO]: Starting\n\
+                    [12:00:01] [Render thread/WARN]: first\n  second\n\
+                    [12:00:02] [Server thread/ERROR]: Crashed\n\
+                    java.lang.IllegalStateException: boom\n\tat a.B.c(B.java:1)\n\
+                    [12:00:03] [Render thread/INFO]: Stopping\n";
+        let mut state = State {
+            events: logs::parse(text, &mut parser),
+            ..State::default()
+        };
+        let all: Vec<Line> = state.events.iter().flat_map(event_lines).collect();
+        assert_eq!(state.line_count(), all.len());
+        for start in 0..all.len() {
+            for height in 0..=all.len() {
+                let end = (start + height).min(all.len());
+                assert_eq!(state.window(start, height), all[start..end], "{}..{}", start, end);
+            }
+        }
+
+        state.set_query("crashed");
+        assert_eq!(state.line_count(), 3);
+        assert_eq!(state.window(0, 1), event_lines(&state.events[2])[..1]);
+    }
+}
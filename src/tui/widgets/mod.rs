@@ -5,13 +5,21 @@ use ratatui::{
 };
 
 pub mod account;
+pub mod ascii;
 pub mod content;
+pub mod datapacks;
 pub mod details;
+pub mod log;
+pub mod options;
 pub mod profiles;
+pub mod resourcepacks;
+pub mod servers;
+pub mod shaders;
 pub mod status;
+pub mod worlds;
 pub mod popups;
 
-pub fn styled_title(title: &str, highlight: bool) -> Line {
+pub fn styled_title(title: &str, highlight: bool) -> Line<'_> {
     if !highlight || title.is_empty() {
         Line::from(Span::raw(title))
     } else {
@@ -0,0 +1,329 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Cell, Row, Table, TableState},
+    Frame,
+};
+
+use crate::{
+    config::SETTINGS,
+    instance::{
+        options::{Kind, Options, COMMON},
+        process, template, Instance,
+    },
+};
+
+use super::{details::Details, WidgetKey};
+
+/// A row of the editor, one of [`COMMON`] or a key binding from the file
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub key: String,
+    pub label: String,
+    pub kind: Kind,
+    pub raw: Option<String>,
+}
+
+impl Entry {
+    fn display(&self) -> String {
+        match self.raw.as_deref().and_then(|raw| self.kind.parse(raw)) {
+            Some(value) => self.kind.display(&value),
+            None => "-".to_string(),
+        }
+    }
+}
+
+/// What is being typed in the bottom line
+#[derive(Debug, Clone)]
+enum Input {
+    Value(String),
+    /// Instance or `preset:<name>` to copy key bindings from
+    Template(String),
+}
+
+#[derive(Debug, Default)]
+pub struct State {
+    pub instance: Option<Instance>,
+    pub options: Option<Options>,
+    pub entries: Vec<Entry>,
+    pub table_state: TableState,
+    pub message: Option<String>,
+    pub details: Option<Details>,
+    input: Option<Input>,
+}
+
+impl State {
+    pub fn select(&mut self, instance: Option<Instance>) {
+        self.instance = instance;
+        self.message = None;
+        self.input = None;
+        self.table_state.select(None);
+        self.reload();
+    }
+
+    pub fn reload(&mut self) {
+        self.options = self.instance.as_ref().and_then(|instance| Options::load(instance).ok());
+        self.entries = match &self.options {
+            Some(options) => entries(options),
+            None => Vec::new(),
+        };
+        if self.entries.is_empty() {
+            self.table_state.select(None);
+        } else {
+            let index = self.table_state.selected().unwrap_or(0);
+            self.table_state.select(Some(index.min(self.entries.len() - 1)));
+        }
+        self.update_details();
+    }
+
+    pub fn capturing_input(&self) -> bool {
+        self.input.is_some()
+    }
+
+    pub fn selected(&self) -> Option<&Entry> {
+        self.table_state.selected().and_then(|i| self.entries.get(i))
+    }
+
+    fn update_details(&mut self) {
+        self.details = self.selected().map(|entry| {
+            let mut lines = vec![
+                Line::from(entry.label.clone()),
+                Line::from(format!("value: {}", entry.display())),
+                Line::from(Span::styled(
+                    format!("{}:{}", entry.key, entry.raw.as_deref().unwrap_or("")),
+                    Style::default().fg(Color::DarkGray),
+                )),
+            ];
+            if self.options.as_ref().is_some_and(|options| !options.exists()) {
+                lines.push(Line::from(Span::styled(
+                    "options.txt is created on first launch",
+                    Style::default().fg(Color::Yellow),
+                )));
+            }
+            Details {
+                title: entry.key.clone(),
+                lines,
+                icon: None,
+            }
+        });
+    }
+
+    /// The game writes options.txt on exit, undoing edits made meanwhile
+    fn check_running(&mut self) -> bool {
+        match self.instance.as_ref().and_then(process::running) {
+            Some(_) => {
+                self.message = Some("Close the game first, it overwrites options.txt on exit".to_string());
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn write(&mut self, key: &str, raw: String) {
+        if self.check_running() {
+            return;
+        }
+        let Some(options) = &mut self.options else {
+            return;
+        };
+        options.set(key, raw);
+        self.message = Some(match options.save() {
+            Ok(()) => format!("Saved {}", key),
+            Err(err) => format!("{:#}", err),
+        });
+        self.reload();
+    }
+
+    fn step_selected(&mut self, forward: bool) {
+        let Some(entry) = self.selected() else {
+            return;
+        };
+        let Some(value) = entry.raw.as_deref().and_then(|raw| entry.kind.parse(raw)) else {
+            return;
+        };
+        if let Some(next) = entry.kind.step(&value, forward) {
+            let key = entry.key.clone();
+            self.write(&key, next.to_raw());
+        }
+    }
+
+    fn finish_input(&mut self) {
+        match self.input.take() {
+            Some(Input::Value(text)) => {
+                let Some(entry) = self.selected() else {
+                    return;
+                };
+                match entry.kind.parse_input(&text) {
+                    Some(value) => {
+                        let key = entry.key.clone();
+                        self.write(&key, value.to_raw());
+                    }
+                    None => self.message = Some(format!("'{}' is not a valid {}", text, entry.label)),
+                }
+            }
+            Some(Input::Template(source)) => self.apply_template(source.trim()),
+            None => {}
+        }
+    }
+
+    fn apply_template(&mut self, source: &str) {
+        let Some(instance) = &self.instance else {
+            return;
+        };
+        let patterns = template::expand(&["keys".to_string()]);
+        let result = template::load_source(source)
+            .and_then(|options| template::apply_to(&options, instance, &patterns));
+        self.message = Some(match result {
+            Ok(applied) if applied.skipped.is_empty() => {
+                format!("Copied {} key bindings from {}", applied.changed, source)
+            }
+            Ok(applied) => format!(
+                "Copied {} key bindings from {}, kept {} with a different format",
+                applied.changed,
+                source,
+                applied.skipped.len()
+            ),
+            Err(err) => format!("{:#}", err),
+        });
+        self.reload();
+    }
+
+    fn handle_input(&mut self, code: KeyCode) {
+        let Some(Input::Value(text) | Input::Template(text)) = &mut self.input else {
+            return;
+        };
+        match code {
+            KeyCode::Enter => self.finish_input(),
+            KeyCode::Esc => self.input = None,
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Char(c) => text.push(c),
+            _ => {}
+        }
+    }
+
+    fn next(&mut self) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let i = match self.table_state.selected() {
+            Some(i) if i + 1 < self.entries.len() => i + 1,
+            _ => 0,
+        };
+        self.table_state.select(Some(i));
+        self.update_details();
+    }
+
+    fn previous(&mut self) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let i = match self.table_state.selected() {
+            Some(0) | None => self.entries.len() - 1,
+            Some(i) => i - 1,
+        };
+        self.table_state.select(Some(i));
+        self.update_details();
+    }
+}
+
+impl WidgetKey for State {
+    fn handle_key(&mut self, key_event: &crossterm::event::KeyEvent) {
+        if self.capturing_input() {
+            self.handle_input(key_event.code);
+            return;
+        }
+
+        match key_event.code {
+            KeyCode::Char('j') | KeyCode::Down => self.next(),
+            KeyCode::Char('k') | KeyCode::Up => self.previous(),
+            KeyCode::Char('l') | KeyCode::Right => self.step_selected(true),
+            KeyCode::Char('h') | KeyCode::Left => self.step_selected(false),
+            KeyCode::Enter => {
+                self.input = self.selected().map(|entry| {
+                    let current = entry.raw.as_deref().and_then(|raw| entry.kind.parse(raw));
+                    Input::Value(current.map(|value| entry.kind.display(&value)).unwrap_or_default())
+                });
+            }
+            KeyCode::Char('t') => self.input = Some(Input::Template(String::new())),
+            KeyCode::Char('r') => self.reload(),
+            _ => {}
+        }
+    }
+}
+
+/// Common settings first, then every key binding in the file
+fn entries(options: &Options) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = COMMON
+        .iter()
+        .map(|setting| Entry {
+            key: setting.key.to_string(),
+            label: setting.label.to_string(),
+            kind: setting.kind,
+            raw: options.get(setting.key).map(String::from),
+        })
+        .collect();
+    entries.extend(
+        options
+            .entries()
+            .filter(|(key, _)| key.starts_with("key_"))
+            .map(|(key, value)| Entry {
+                key: key.to_string(),
+                label: key.trim_start_matches("key_key.").to_string(),
+                kind: Kind::Key,
+                raw: Some(value.to_string()),
+            }),
+    );
+    entries
+}
+
+pub fn render(frame: &mut Frame, area: Rect, mut block: Block, state: &mut State) {
+    match &state.input {
+        Some(Input::Value(text)) => {
+            block = block.title_bottom(Line::from(Span::styled(
+                format!(" Value: {}_ ", text),
+                Style::default().fg(Color::Yellow),
+            )));
+        }
+        Some(Input::Template(text)) => {
+            block = block.title_bottom(Line::from(Span::styled(
+                format!(" Copy key bindings from (instance or preset:<name>): {}_ ", text),
+                Style::default().fg(Color::Yellow),
+            )));
+        }
+        None => {
+            if let Some(message) = &state.message {
+                block = block.title_bottom(Line::from(format!(" {} ", message)));
+            }
+        }
+    }
+
+    let rows = state.entries.iter().enumerate().map(|(i, entry)| {
+        let background_color = if i % 2 == 0 {
+            SETTINGS.colors.row_background
+        } else {
+            SETTINGS.colors.row_alternate_bg
+        };
+        let style = if entry.raw.is_some() {
+            Style::default().bg(background_color)
+        } else {
+            Style::default().bg(background_color).fg(Color::DarkGray)
+        };
+
+        Row::new(vec![Cell::from(entry.label.clone()), Cell::from(entry.display())]).style(style)
+    });
+
+    let widths = [Constraint::Percentage(60), Constraint::Percentage(40)];
+    let table = Table::new(rows, widths)
+        .block(block)
+        .row_highlight_style(
+            Style::default()
+                .add_modifier(Modifier::REVERSED)
+                .fg(SETTINGS.colors.row_highlight),
+        );
+
+    frame.render_stateful_widget(table, area, &mut state.table_state);
+}
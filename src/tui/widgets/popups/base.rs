@@ -6,12 +6,14 @@ use ratatui::{
     widgets::{Block, Borders, Clear, Widget},
 };
 
+pub type RenderFn<'a> = Box<dyn Fn(Rect, &mut Buffer) + 'a>;
+
 pub struct Popup<'a> {
     pub title: Line<'a>,
     pub border_style: Style,
     pub title_style: Style,
     pub style: Style,
-    pub content: Box<dyn Fn(Rect, &mut Buffer) + 'a>,
+    pub content: RenderFn<'a>,
 }
 
 impl<'a> Widget for Popup<'a> {
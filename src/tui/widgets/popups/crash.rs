@@ -0,0 +1,124 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Paragraph, Widget, Wrap},
+    Frame,
+};
+
+use super::base::Popup;
+use crate::logs::crash::Analysis;
+use crate::tui::widgets::profiles;
+
+/// Stack trace lines shown below the diagnosis
+const STACK_PREVIEW: usize = 30;
+
+/// Diagnosis of an instance's latest crash
+#[derive(Debug)]
+pub struct State {
+    pub instance: String,
+    pub analysis: Analysis,
+    scroll: u16,
+}
+
+impl State {
+    pub fn new(instance: &str, analysis: Analysis) -> State {
+        State {
+            instance: instance.to_string(),
+            analysis,
+            scroll: 0,
+        }
+    }
+
+    fn lines(&self) -> Vec<Line<'static>> {
+        let report = &self.analysis.report;
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let dim = Style::default().fg(Color::DarkGray);
+
+        let modified = report
+            .modified
+            .map(|time| format!(", {}", time.format("%Y-%m-%d %H:%M")))
+            .unwrap_or_default();
+        let mut lines = vec![
+            Line::from(Span::styled(
+                format!("{} ({}{})", report.path.display(), report.kind.name(), modified),
+                dim,
+            )),
+            Line::default(),
+        ];
+        lines.extend(report.description.lines().map(|line| Line::from(Span::styled(line.to_string(), bold))));
+        lines.push(Line::default());
+
+        if self.analysis.diagnoses.is_empty() {
+            lines.push(Line::from("No known problem recognized, the stack trace is the best lead."));
+            lines.push(Line::default());
+        }
+        for diagnosis in &self.analysis.diagnoses {
+            lines.push(Line::from(Span::styled(
+                diagnosis.title.clone(),
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            )));
+            lines.push(Line::from(diagnosis.advice.clone()));
+            lines.push(Line::from(Span::styled(format!("> {}", diagnosis.evidence), dim)));
+            lines.push(Line::default());
+        }
+
+        if !report.stack_trace.is_empty() {
+            lines.push(Line::from(Span::styled("Stack trace", bold)));
+            let stack: Vec<&str> = report.stack_trace.lines().collect();
+            lines.extend(stack.iter().take(STACK_PREVIEW).map(|line| {
+                Line::from(Span::styled(line.replace('\t', "    "), Style::default().fg(Color::Red)))
+            }));
+            if stack.len() > STACK_PREVIEW {
+                lines.push(Line::from(Span::styled(
+                    format!("... {} more lines, see mcl logs crash --full", stack.len() - STACK_PREVIEW),
+                    dim,
+                )));
+            }
+        }
+        if !report.mods.is_empty() {
+            lines.push(Line::default());
+            lines.push(Line::from(Span::styled(format!("{} mods loaded", report.mods.len()), dim)));
+        }
+        lines
+    }
+}
+
+pub fn render(frame: &mut Frame, area: Rect, state: &State) {
+    let lines = state.lines();
+    let scroll = state.scroll;
+    let popup = Popup {
+        title: Line::from(format!(" {} crashed ", state.instance)),
+        content: Box::new(move |area, buf| {
+            let [text, hint] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(area);
+            Paragraph::new(lines.clone())
+                .wrap(Wrap { trim: false })
+                .scroll((scroll, 0))
+                .render(text, buf);
+            Line::from(Span::styled("j/k scroll, Esc close", Style::default().fg(Color::DarkGray)))
+                .right_aligned()
+                .render(hint, buf);
+        }),
+        border_style: Style::default().fg(Color::Red),
+        title_style: Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        style: Default::default(),
+    };
+
+    frame.render_widget(popup, area);
+}
+
+pub fn handle_key(key_event: &crossterm::event::KeyEvent, state: &mut profiles::State) {
+    let Some(crash) = &mut state.crash else {
+        return;
+    };
+    match key_event.code {
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => state.crash = None,
+        KeyCode::Char('j') | KeyCode::Down => crash.scroll = crash.scroll.saturating_add(1),
+        KeyCode::Char('k') | KeyCode::Up => crash.scroll = crash.scroll.saturating_sub(1),
+        KeyCode::PageDown => crash.scroll = crash.scroll.saturating_add(10),
+        KeyCode::PageUp => crash.scroll = crash.scroll.saturating_sub(10),
+        KeyCode::Char('g') => crash.scroll = 0,
+        _ => {}
+    }
+}
@@ -1,2 +1,3 @@
 pub mod base;
+pub mod crash;
 pub mod new_instance;
@@ -1,3 +1,6 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
 use crossterm::event::KeyCode;
 use ratatui::{
     layout::{Constraint, Rect},
@@ -10,7 +13,15 @@ use ratatui::{
     Frame,
 };
 
-use crate::{config::SETTINGS, tui::layout::FocusedArea};
+use crate::{
+    config::SETTINGS,
+    instance::{
+        self,
+        process::{self, Status},
+    },
+    logs::crash,
+    tui::layout::FocusedArea,
+};
 
 use super::{popups, styled_title, WidgetKey};
 
@@ -20,16 +31,136 @@ pub struct State {
     pub table_state: TableState,
     pub scrollbar_state: ScrollbarState,
     pub show_popup: bool,
+    /// Diagnosis shown after a game crashed or on `c`
+    pub crash: Option<popups::crash::State>,
+    /// Profile id waiting for a second `x` to be stopped
+    confirm_kill: Option<String>,
+    last_refresh: Option<Instant>,
 }
 
+/// How often the Running/Stopped column checks the game processes
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Games get this long to exit after a stop request before a second one
+/// kills them
+const KILL_GRACE: Duration = Duration::from_secs(10);
+
 #[derive(Debug, Default)]
 pub struct Data {
     pub title: String,
     pub id: String,
+    pub path: PathBuf,
     pub running: bool,
+    pub status: Option<Status>,
+    /// When the game was last asked to stop, to escalate to a kill
+    stop_requested: Option<Instant>,
+    /// End of the latest session already seen, so each crash pops up once
+    seen_end: Option<i64>,
+}
+
+impl Data {
+    /// Second line of the status column: start time while running, the
+    /// exit code once stopped
+    fn status_detail(&self) -> String {
+        match &self.status {
+            Some(Status::Running(session)) => session
+                .started()
+                .map(|started| format!("since {}", started.format("%H:%M")))
+                .unwrap_or_default(),
+            Some(Status::Stopped(Some(session))) if session.ended.is_some() => session.outcome(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// Diagnosis of the latest crash report of a profile, if it has one
+fn analyze(data: &Data) -> Option<popups::crash::State> {
+    let report = crash::latest(&data.path).ok()??;
+    Some(popups::crash::State::new(&data.title, crash::analyze(report)))
 }
 
 impl State {
+    /// Fills the table with the instances found in the data directory
+    pub fn load(&mut self) {
+        self.profiles = instance::list()
+            .into_iter()
+            .map(|instance| Data {
+                seen_end: process::session_at(&instance.path).and_then(|session| session.ended),
+                title: instance.name,
+                id: instance.id,
+                path: instance.path,
+                ..Default::default()
+            })
+            .collect();
+        self.last_refresh = None;
+        self.refresh();
+        self.update_scrollbar();
+    }
+
+    /// Updates the Running/Stopped column, at most once per
+    /// [`REFRESH_INTERVAL`] so the UI loop stays cheap
+    pub fn refresh(&mut self) {
+        if self.last_refresh.is_some_and(|last| last.elapsed() < REFRESH_INTERVAL) {
+            return;
+        }
+        self.last_refresh = Some(Instant::now());
+        for data in &mut self.profiles {
+            let status = process::status_at(&data.path);
+            data.running = matches!(status, Status::Running(_));
+            if let Status::Stopped(Some(session)) = &status {
+                if session.ended.is_some() && session.ended != data.seen_end {
+                    data.seen_end = session.ended;
+                    if session.crashed() && data.stop_requested.is_none() && self.crash.is_none() {
+                        self.crash = analyze(data);
+                    }
+                }
+            }
+            if !data.running {
+                data.stop_requested = None;
+            }
+            data.status = Some(status);
+        }
+    }
+
+    /// Opens the diagnosis of the selected instance's latest crash
+    fn show_crash(&mut self) {
+        self.crash = self.selected().and_then(analyze);
+    }
+
+    /// Stops the selected game on the second `x`, killing it if an earlier
+    /// stop request was ignored
+    fn kill_selected(&mut self) {
+        let Some(index) = self.table_state.selected() else {
+            return;
+        };
+        let Some(data) = self.profiles.get_mut(index) else {
+            return;
+        };
+        let Some(pid) = process::running_at(&data.path) else {
+            self.confirm_kill = None;
+            return;
+        };
+        if self.confirm_kill.as_deref() != Some(data.id.as_str()) {
+            self.confirm_kill = Some(data.id.clone());
+            return;
+        }
+
+        self.confirm_kill = None;
+        let force = data
+            .stop_requested
+            .is_some_and(|requested| requested.elapsed() >= KILL_GRACE);
+        if process::send_kill(pid, force).is_ok() && data.stop_requested.is_none() {
+            data.stop_requested = Some(Instant::now());
+        }
+        self.last_refresh = None;
+    }
+
+    pub fn selected(&self) -> Option<&Data> {
+        self.table_state
+            .selected()
+            .and_then(|i| self.profiles.get(i))
+    }
+
     fn next(&mut self) {
         let i = match self.table_state.selected() {
             Some(i) => {
@@ -76,12 +207,15 @@ impl State {
     }
 
     pub fn wants_popup(&self) -> bool {
-        self.show_popup
+        self.show_popup || self.crash.is_some()
     }
 }
 
 impl WidgetKey for State {
     fn handle_key(&mut self, key_event: &crossterm::event::KeyEvent) {
+        if key_event.code != KeyCode::Char('x') {
+            self.confirm_kill = None;
+        }
         match key_event.code {
             KeyCode::Char('a') => {
                 self.show_popup = true;
@@ -93,6 +227,8 @@ impl WidgetKey for State {
             }
             KeyCode::Char('j') | KeyCode::Down => self.next(),
             KeyCode::Char('k') | KeyCode::Up => self.previous(),
+            KeyCode::Char('x') => self.kill_selected(),
+            KeyCode::Char('c') => self.show_crash(),
             _ => {}
         }
     }
@@ -123,7 +259,15 @@ pub fn render(frame: &mut Frame, area: Rect, focused: FocusedArea, state: &mut S
     let table_area = block.inner(area);
 
     let rows = state.profiles.iter().enumerate().map(|(i, data)| {
-        let status = if data.running { "Running" } else { "Stopped" };
+        let status = if state.confirm_kill.as_deref() == Some(data.id.as_str()) {
+            "x to stop".to_string()
+        } else if data.stop_requested.is_some() {
+            "Stopping".to_string()
+        } else if data.running {
+            "Running".to_string()
+        } else {
+            "Stopped".to_string()
+        };
 
         let background_color = if i % 2 == 0 {
             SETTINGS.colors.row_background
@@ -134,7 +278,7 @@ pub fn render(frame: &mut Frame, area: Rect, focused: FocusedArea, state: &mut S
         Row::new(vec![
             Cell::from(Text::from(format!("\n{}\n", data.title))),
             Cell::from(Text::from(format!("\n{}\n", data.id))),
-            Cell::from(Text::from(format!("\n{}\n", status))),
+            Cell::from(Text::from(format!("\n{}\n{}", status, data.status_detail()))),
         ])
         .height(4)
         .style(Style::default().bg(background_color))
@@ -181,4 +325,13 @@ pub fn render(frame: &mut Frame, area: Rect, focused: FocusedArea, state: &mut S
         };
         popups::new_instance::render(frame, popup_area, focused);
     }
+    if let Some(crash) = &state.crash {
+        let popup_area = Rect {
+            x: frame.area().width / 6,
+            y: frame.area().height / 6,
+            width: frame.area().width * 2 / 3,
+            height: frame.area().height * 2 / 3,
+        };
+        popups::crash::render(frame, popup_area, crash);
+    }
 }
@@ -0,0 +1,229 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Cell, Row, Table, TableState},
+    Frame,
+};
+
+use crate::{
+    config::SETTINGS,
+    instance::Instance,
+    packs::resource::{self, Compatibility, ResourcePack},
+};
+
+use super::{ascii, details::Details, WidgetKey};
+
+const ICON_WIDTH: u32 = 16;
+
+#[derive(Debug, Default)]
+pub struct State {
+    pub instance: Option<Instance>,
+    pub packs: Vec<ResourcePack>,
+    pub table_state: TableState,
+    pub message: Option<String>,
+    pub details: Option<Details>,
+}
+
+impl State {
+    pub fn select(&mut self, instance: Option<Instance>) {
+        self.instance = instance;
+        self.message = None;
+        self.table_state.select(None);
+        self.reload();
+    }
+
+    pub fn reload(&mut self) {
+        self.packs = self.instance.as_ref().map(resource::list).unwrap_or_default();
+        if self.packs.is_empty() {
+            self.table_state.select(None);
+        } else {
+            let index = self.table_state.selected().unwrap_or(0);
+            self.table_state.select(Some(index.min(self.packs.len() - 1)));
+        }
+        self.update_details();
+    }
+
+    pub fn selected(&self) -> Option<&ResourcePack> {
+        self.table_state.selected().and_then(|i| self.packs.get(i))
+    }
+
+    fn update_details(&mut self) {
+        let expected = self
+            .instance
+            .as_ref()
+            .and_then(|instance| resource::pack_format(&instance.game_version));
+        self.details = self.selected().map(|pack| pack_details(pack, expected));
+    }
+
+    /// Selects the pack with the given name after a reload reordered them
+    fn reselect(&mut self, name: &str) {
+        self.reload();
+        if let Some(index) = self.packs.iter().position(|pack| pack.pack.name == name) {
+            self.table_state.select(Some(index));
+            self.update_details();
+        }
+    }
+
+    fn toggle_selected(&mut self) {
+        let (Some(instance), Some(pack)) = (&self.instance, self.selected()) else {
+            return;
+        };
+        let name = pack.pack.name.clone();
+        let enable = !pack.enabled;
+        self.message = Some(match resource::set_enabled(instance, pack, enable) {
+            Ok(()) if enable && pack.compatibility != Compatibility::Compatible => {
+                format!("Enabled {}, it may not work with this version", name)
+            }
+            Ok(()) => format!("{} {}", if enable { "Enabled" } else { "Disabled" }, name),
+            Err(err) => format!("Failed to update options.txt: {}", err),
+        });
+        self.reselect(&name);
+    }
+
+    fn move_selected(&mut self, up: bool) {
+        let (Some(instance), Some(pack)) = (&self.instance, self.selected()) else {
+            return;
+        };
+        if !pack.enabled {
+            self.message = Some("Only enabled packs can be reordered".to_string());
+            return;
+        }
+        let name = pack.pack.name.clone();
+        if let Err(err) = resource::move_pack(instance, pack, up) {
+            self.message = Some(format!("Failed to update options.txt: {}", err));
+        }
+        self.reselect(&name);
+    }
+
+    fn next(&mut self) {
+        if self.packs.is_empty() {
+            return;
+        }
+        let i = match self.table_state.selected() {
+            Some(i) if i + 1 < self.packs.len() => i + 1,
+            _ => 0,
+        };
+        self.table_state.select(Some(i));
+        self.update_details();
+    }
+
+    fn previous(&mut self) {
+        if self.packs.is_empty() {
+            return;
+        }
+        let i = match self.table_state.selected() {
+            Some(0) | None => self.packs.len() - 1,
+            Some(i) => i - 1,
+        };
+        self.table_state.select(Some(i));
+        self.update_details();
+    }
+}
+
+impl WidgetKey for State {
+    fn handle_key(&mut self, key_event: &crossterm::event::KeyEvent) {
+        match key_event.code {
+            KeyCode::Char('j') | KeyCode::Down => self.next(),
+            KeyCode::Char('k') | KeyCode::Up => self.previous(),
+            KeyCode::Char(' ') => self.toggle_selected(),
+            KeyCode::Char('K') => self.move_selected(true),
+            KeyCode::Char('J') => self.move_selected(false),
+            KeyCode::Char('r') => self.reload(),
+            _ => {}
+        }
+    }
+}
+
+fn compatibility_span(compatibility: Compatibility) -> Span<'static> {
+    match compatibility {
+        Compatibility::Compatible => Span::raw(""),
+        Compatibility::TooOld => Span::styled("too old", Style::default().fg(Color::Yellow)),
+        Compatibility::TooNew => Span::styled("too new", Style::default().fg(Color::Yellow)),
+        Compatibility::Unknown => Span::styled("?", Style::default().fg(Color::DarkGray)),
+    }
+}
+
+fn pack_details(pack: &ResourcePack, expected: Option<u32>) -> Details {
+    let mut lines = vec![Line::from(pack.pack.name.clone())];
+    if !pack.enabled {
+        lines.push(Line::from(Span::styled("disabled", Style::default().fg(Color::Red))));
+    }
+    lines.extend(
+        pack.pack
+            .description
+            .lines()
+            .map(|line| Line::from(line.to_string())),
+    );
+
+    let format = match (pack.pack.supported, pack.pack.pack_format) {
+        (Some((min, max)), _) => format!("formats {}-{}", min, max),
+        (None, Some(format)) => format!("format {}", format),
+        (None, None) => "no pack.mcmeta".to_string(),
+    };
+    let expected = expected.map_or("unknown".to_string(), |format| format.to_string());
+    lines.push(Line::from(format!("{}, instance expects {}", format, expected)));
+    if matches!(pack.compatibility, Compatibility::TooOld | Compatibility::TooNew) {
+        lines.push(Line::from(compatibility_span(pack.compatibility)));
+    }
+    if pack.pack.folder {
+        lines.push(Line::from(Span::styled("folder", Style::default().fg(Color::DarkGray))));
+    }
+
+    Details {
+        title: pack.pack.name.clone(),
+        lines,
+        icon: pack.pack.icon().and_then(|bytes| ascii::render(&bytes, ICON_WIDTH)),
+    }
+}
+
+pub fn render(frame: &mut Frame, area: Rect, mut block: Block, state: &mut State) {
+    if let Some(message) = &state.message {
+        block = block.title_bottom(Line::from(format!(" {} ", message)));
+    }
+    let enabled = state.packs.iter().filter(|pack| pack.enabled).count();
+    block = block.title_bottom(Line::from(format!(" {} of {} enabled ", enabled, state.packs.len())).right_aligned());
+
+    let rows = state.packs.iter().enumerate().map(|(i, pack)| {
+        let background_color = if i % 2 == 0 {
+            SETTINGS.colors.row_background
+        } else {
+            SETTINGS.colors.row_alternate_bg
+        };
+
+        let (marker, style) = if pack.enabled {
+            ("[x]", Style::default().bg(background_color))
+        } else {
+            (
+                "[ ]",
+                Style::default().bg(background_color).fg(Color::DarkGray),
+            )
+        };
+
+        Row::new(vec![
+            Cell::from(marker),
+            Cell::from(pack.pack.name.clone()),
+            Cell::from(pack.pack.description.lines().next().unwrap_or_default().to_string()),
+            Cell::from(compatibility_span(pack.compatibility)),
+        ])
+        .style(style)
+    });
+
+    let widths = [
+        Constraint::Length(3),
+        Constraint::Percentage(35),
+        Constraint::Percentage(50),
+        Constraint::Percentage(15),
+    ];
+
+    let table = Table::new(rows, widths)
+        .block(block)
+        .row_highlight_style(
+            Style::default()
+                .add_modifier(Modifier::REVERSED)
+                .fg(SETTINGS.colors.row_highlight),
+        );
+
+    frame.render_stateful_widget(table, area, &mut state.table_state);
+}
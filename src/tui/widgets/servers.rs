@@ -0,0 +1,488 @@
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Cell, Row, Table, TableState},
+    Frame,
+};
+
+use crate::{
+    config::SETTINGS,
+    instance::{self, Instance},
+    launch::{self, LaunchOptions, QuickPlay},
+    servers::{self, ping::Status, Server, Textures},
+};
+
+use super::{ascii, details::Details, WidgetKey};
+
+const ICON_WIDTH: u32 = 16;
+
+/// Outcome of pinging one address
+type PingResult = Result<Status, String>;
+
+/// What is being typed in the bottom line. Adding and editing ask for the
+/// name first, then the address
+#[derive(Debug, Clone)]
+enum Input {
+    Name {
+        /// Index of the server being edited, `None` when adding
+        editing: Option<usize>,
+        text: String,
+    },
+    Address {
+        editing: Option<usize>,
+        name: String,
+        text: String,
+    },
+    /// Instance to copy the server list from
+    CopyFrom(String),
+}
+
+#[derive(Debug, Default)]
+pub struct State {
+    pub instance: Option<Instance>,
+    pub servers: Vec<Server>,
+    pub table_state: TableState,
+    pub message: Option<String>,
+    pub details: Option<Details>,
+    input: Option<Input>,
+    /// Set after the first `d`, the second one removes
+    confirm_remove: bool,
+    /// Latest ping result per address
+    statuses: HashMap<String, PingResult>,
+    pings: Option<Receiver<(String, PingResult)>>,
+    /// Game being started to join the server with this name
+    launch: Option<(String, Receiver<Result<u32>>)>,
+}
+
+impl State {
+    pub fn select(&mut self, instance: Option<Instance>) {
+        self.instance = instance;
+        self.message = None;
+        self.input = None;
+        self.confirm_remove = false;
+        self.statuses.clear();
+        self.pings = None;
+        self.launch = None;
+        self.table_state.select(None);
+        self.reload();
+    }
+
+    pub fn reload(&mut self) {
+        self.servers = match &self.instance {
+            Some(instance) => servers::load(instance).unwrap_or_else(|err| {
+                self.message = Some(format!("Failed to read servers.dat: {:#}", err));
+                Vec::new()
+            }),
+            None => Vec::new(),
+        };
+        if self.servers.is_empty() {
+            self.table_state.select(None);
+        } else {
+            let index = self.table_state.selected().unwrap_or(0);
+            self.table_state.select(Some(index.min(self.servers.len() - 1)));
+        }
+        self.update_details();
+    }
+
+    pub fn capturing_input(&self) -> bool {
+        self.input.is_some()
+    }
+
+    pub fn selected(&self) -> Option<&Server> {
+        self.table_state.selected().and_then(|i| self.servers.get(i))
+    }
+
+    fn update_details(&mut self) {
+        self.details = self
+            .selected()
+            .map(|server| server_details(server, self.statuses.get(&server.ip)));
+    }
+
+    /// Pings every server at once in the background, see [`State::poll`]
+    fn ping_all(&mut self) {
+        if self.servers.is_empty() {
+            return;
+        }
+        let (sender, receiver) = mpsc::channel();
+        for server in &self.servers {
+            let server = server.clone();
+            let sender = sender.clone();
+            thread::spawn(move || {
+                let result = server.ping().map_err(|err| format!("{:#}", err));
+                let _ = sender.send((server.ip, result));
+            });
+        }
+        self.statuses.clear();
+        self.message = Some(format!("Pinging {} servers...", self.servers.len()));
+        self.pings = Some(receiver);
+    }
+
+    pub fn poll(&mut self) {
+        self.poll_launch();
+        let Some(receiver) = &self.pings else {
+            return;
+        };
+        let (mut changed, mut done) = (false, false);
+        loop {
+            match receiver.try_recv() {
+                Ok((ip, result)) => {
+                    self.statuses.insert(ip, result);
+                    changed = true;
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    done = true;
+                    break;
+                }
+            }
+        }
+        if done {
+            let online = self.statuses.values().filter(|result| result.is_ok()).count();
+            self.message = Some(format!("{} of {} servers online", online, self.servers.len()));
+            self.pings = None;
+        }
+        if changed {
+            self.update_details();
+        }
+    }
+
+    /// Starts the game and joins the selected server
+    fn join_selected(&mut self) {
+        let (Some(instance), Some(server)) = (self.instance.clone(), self.selected()) else {
+            return;
+        };
+        if self.launch.is_some() {
+            return;
+        }
+
+        let options = LaunchOptions {
+            quick_play: Some(QuickPlay::Server(server.ip.clone())),
+            ..Default::default()
+        };
+        let name = server.name.clone();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(launch::start(&instance, &options));
+        });
+        self.message = Some(format!("Starting to join {}...", name));
+        self.launch = Some((name, receiver));
+    }
+
+    fn poll_launch(&mut self) {
+        let Some((server, receiver)) = &self.launch else {
+            return;
+        };
+        let message = match receiver.try_recv() {
+            Ok(Ok(pid)) => {
+                let name = self.instance.as_ref().map_or("", |instance| instance.name.as_str());
+                format!("Started {} joining {} (pid {})", name, server, pid)
+            }
+            Ok(Err(err)) => format!("{:#}", err),
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => "Launch failed".to_string(),
+        };
+        self.message = Some(message);
+        self.launch = None;
+    }
+
+    /// Saves the edited list, reloading from disk either way
+    fn save(&mut self, message: String) {
+        let Some(instance) = &self.instance else {
+            return;
+        };
+        self.message = Some(match servers::save(instance, &self.servers) {
+            Ok(()) => message,
+            Err(err) => format!("{:#}", err),
+        });
+        self.reload();
+    }
+
+    fn remove_selected(&mut self) {
+        let Some(index) = self.table_state.selected() else {
+            return;
+        };
+        if index >= self.servers.len() {
+            return;
+        }
+        if !self.confirm_remove {
+            self.confirm_remove = true;
+            self.message = Some(format!("Press d again to remove {}", self.servers[index].name));
+            return;
+        }
+
+        self.confirm_remove = false;
+        let removed = self.servers.remove(index);
+        self.save(format!("Removed {}", removed.name));
+    }
+
+    /// Moves the selected server one place up or down
+    fn shift_selected(&mut self, down: bool) {
+        let Some(index) = self.table_state.selected() else {
+            return;
+        };
+        let target = if down { index + 1 } else { index.wrapping_sub(1) };
+        if target >= self.servers.len() {
+            return;
+        }
+        self.servers.swap(index, target);
+        self.table_state.select(Some(target));
+        let name = self.servers[target].name.clone();
+        self.save(format!("Moved {} to position {}", name, target + 1));
+    }
+
+    fn cycle_textures(&mut self) {
+        let Some(index) = self.table_state.selected() else {
+            return;
+        };
+        let Some(server) = self.servers.get_mut(index) else {
+            return;
+        };
+        server.textures = match server.textures {
+            Textures::Prompt => Textures::Enabled,
+            Textures::Enabled => Textures::Disabled,
+            Textures::Disabled => Textures::Prompt,
+        };
+        let message = format!("Server resource pack: {}", server.textures.name());
+        self.save(message);
+    }
+
+    fn finish_input(&mut self) {
+        match self.input.take() {
+            Some(Input::Name { editing, text }) => {
+                let name = text.trim().to_string();
+                if name.is_empty() {
+                    return;
+                }
+                let text = editing
+                    .and_then(|index| self.servers.get(index))
+                    .map(|server| server.ip.clone())
+                    .unwrap_or_default();
+                self.input = Some(Input::Address { editing, name, text });
+            }
+            Some(Input::Address { editing, name, text }) => {
+                let address = text.trim();
+                if address.is_empty() {
+                    return;
+                }
+                match editing.and_then(|index| self.servers.get_mut(index)) {
+                    Some(server) => {
+                        server.name = name.clone();
+                        server.ip = address.to_string();
+                        self.save(format!("Updated {}", name));
+                    }
+                    None => {
+                        self.servers.push(Server::new(&name, address));
+                        self.table_state.select(Some(self.servers.len() - 1));
+                        self.save(format!("Added {}", name));
+                    }
+                }
+            }
+            Some(Input::CopyFrom(source)) => self.copy_from(source.trim()),
+            None => {}
+        }
+    }
+
+    fn copy_from(&mut self, source: &str) {
+        let Some(target) = &self.instance else {
+            return;
+        };
+        let result = instance::find(source).and_then(|source| servers::copy(&source, target, false));
+        self.message = Some(match result {
+            Ok(added) => format!("Added {} servers from {}", added, source),
+            Err(err) => format!("{:#}", err),
+        });
+        self.reload();
+    }
+
+    fn handle_input(&mut self, code: KeyCode) {
+        let Some(Input::Name { text, .. } | Input::Address { text, .. } | Input::CopyFrom(text)) =
+            &mut self.input
+        else {
+            return;
+        };
+        match code {
+            KeyCode::Enter => self.finish_input(),
+            KeyCode::Esc => self.input = None,
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Char(c) => text.push(c),
+            _ => {}
+        }
+    }
+
+    fn next(&mut self) {
+        if self.servers.is_empty() {
+            return;
+        }
+        let i = match self.table_state.selected() {
+            Some(i) if i + 1 < self.servers.len() => i + 1,
+            _ => 0,
+        };
+        self.table_state.select(Some(i));
+        self.update_details();
+    }
+
+    fn previous(&mut self) {
+        if self.servers.is_empty() {
+            return;
+        }
+        let i = match self.table_state.selected() {
+            Some(0) | None => self.servers.len() - 1,
+            Some(i) => i - 1,
+        };
+        self.table_state.select(Some(i));
+        self.update_details();
+    }
+}
+
+impl WidgetKey for State {
+    fn handle_key(&mut self, key_event: &crossterm::event::KeyEvent) {
+        if self.capturing_input() {
+            self.handle_input(key_event.code);
+            return;
+        }
+        if key_event.code != KeyCode::Char('d') {
+            self.confirm_remove = false;
+        }
+
+        let shift = key_event.modifiers.contains(KeyModifiers::SHIFT);
+        match key_event.code {
+            KeyCode::Char('J') => self.shift_selected(true),
+            KeyCode::Char('K') => self.shift_selected(false),
+            KeyCode::Down if shift => self.shift_selected(true),
+            KeyCode::Up if shift => self.shift_selected(false),
+            KeyCode::Char('j') | KeyCode::Down => self.next(),
+            KeyCode::Char('k') | KeyCode::Up => self.previous(),
+            KeyCode::Char('a') => {
+                self.input = Some(Input::Name {
+                    editing: None,
+                    text: String::new(),
+                })
+            }
+            KeyCode::Char('e') => {
+                self.input = self.table_state.selected().and_then(|index| {
+                    self.servers.get(index).map(|server| Input::Name {
+                        editing: Some(index),
+                        text: server.name.clone(),
+                    })
+                })
+            }
+            KeyCode::Char('t') => self.cycle_textures(),
+            KeyCode::Char('d') => self.remove_selected(),
+            KeyCode::Char('c') => self.input = Some(Input::CopyFrom(String::new())),
+            KeyCode::Char('p') => self.ping_all(),
+            KeyCode::Enter => self.join_selected(),
+            KeyCode::Char('r') => self.reload(),
+            _ => {}
+        }
+    }
+}
+
+fn server_details(server: &Server, status: Option<&PingResult>) -> Details {
+    let (host, port) = server.address();
+    let mut lines = vec![
+        Line::from(server.name.clone()),
+        Line::from(format!("{}:{}", host, port)),
+    ];
+    match status {
+        Some(Ok(status)) => {
+            lines.extend(status.motd.lines().map(|line| Line::from(line.trim().to_string())));
+            lines.push(Line::from(format!(
+                "{}/{} players, {} ms",
+                status.online,
+                status.max,
+                status.latency.as_millis()
+            )));
+            if !status.sample.is_empty() {
+                lines.push(Line::from(status.sample.join(", ")));
+            }
+            lines.push(Line::from(Span::styled(
+                status.version.clone(),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        Some(Err(err)) => lines.push(Line::from(Span::styled(err.clone(), Style::default().fg(Color::Red)))),
+        None => {}
+    }
+    lines.push(Line::from(format!("resource pack: {}", server.textures.name())));
+
+    // a fresh favicon wins over the one the game stored
+    let favicon = match status {
+        Some(Ok(status)) => status.favicon.clone(),
+        _ => None,
+    };
+    Details {
+        title: server.name.clone(),
+        lines,
+        icon: favicon
+            .or_else(|| server.icon_png())
+            .and_then(|bytes| ascii::render(&bytes, ICON_WIDTH)),
+    }
+}
+
+/// Player count and latency, or why the ping failed
+fn status_span(status: Option<&PingResult>) -> Span<'static> {
+    match status {
+        Some(Ok(status)) => Span::styled(
+            format!("{}/{}  {} ms", status.online, status.max, status.latency.as_millis()),
+            Style::default().fg(Color::Green),
+        ),
+        Some(Err(_)) => Span::styled("offline", Style::default().fg(Color::Red)),
+        None => Span::raw(""),
+    }
+}
+
+pub fn render(frame: &mut Frame, area: Rect, mut block: Block, state: &mut State) {
+    let prompt = match &state.input {
+        Some(Input::Name { text, .. }) => Some(format!(" Name: {}_ ", text)),
+        Some(Input::Address { text, .. }) => Some(format!(" Address (host[:port]): {}_ ", text)),
+        Some(Input::CopyFrom(text)) => Some(format!(" Copy servers from instance: {}_ ", text)),
+        None => None,
+    };
+    if let Some(prompt) = prompt {
+        block = block.title_bottom(Line::from(Span::styled(prompt, Style::default().fg(Color::Yellow))));
+    } else if let Some(message) = &state.message {
+        block = block.title_bottom(Line::from(format!(" {} ", message)));
+    }
+
+    let rows = state.servers.iter().enumerate().map(|(i, server)| {
+        let background_color = if i % 2 == 0 {
+            SETTINGS.colors.row_background
+        } else {
+            SETTINGS.colors.row_alternate_bg
+        };
+
+        Row::new(vec![
+            Cell::from(format!("{}", i + 1)),
+            Cell::from(server.name.clone()),
+            Cell::from(server.ip.clone()),
+            Cell::from(status_span(state.statuses.get(&server.ip))),
+        ])
+        .style(Style::default().bg(background_color))
+    });
+
+    let widths = [
+        Constraint::Length(3),
+        Constraint::Percentage(35),
+        Constraint::Percentage(40),
+        Constraint::Percentage(25),
+    ];
+
+    let table = Table::new(rows, widths)
+        .block(block)
+        .row_highlight_style(
+            Style::default()
+                .add_modifier(Modifier::REVERSED)
+                .fg(SETTINGS.colors.row_highlight),
+        );
+
+    frame.render_stateful_widget(table, area, &mut state.table_state);
+}
@@ -0,0 +1,201 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Cell, Row, Table, TableState},
+    Frame,
+};
+
+use crate::{
+    config::SETTINGS,
+    instance::Instance,
+    packs::{
+        shader::{self, ShaderLoader},
+        Pack,
+    },
+};
+
+use super::{details::Details, WidgetKey};
+
+#[derive(Debug, Default)]
+pub struct State {
+    pub instance: Option<Instance>,
+    pub packs: Vec<Pack>,
+    pub loader: Option<ShaderLoader>,
+    /// File name of the pack the loader currently uses
+    pub active: Option<String>,
+    pub table_state: TableState,
+    pub message: Option<String>,
+    pub details: Option<Details>,
+}
+
+impl State {
+    pub fn select(&mut self, instance: Option<Instance>) {
+        self.instance = instance;
+        self.message = None;
+        self.table_state.select(None);
+        self.reload();
+    }
+
+    pub fn reload(&mut self) {
+        let Some(instance) = &self.instance else {
+            self.packs.clear();
+            self.loader = None;
+            self.active = None;
+            self.details = None;
+            return;
+        };
+
+        self.packs = shader::list(instance);
+        self.loader = shader::detect(instance);
+        self.active = self.loader.and_then(|loader| shader::active(instance, loader));
+        if self.message.is_none() {
+            self.message = shader::warning(instance, &self.packs, self.loader);
+        }
+
+        if self.packs.is_empty() {
+            self.table_state.select(None);
+        } else {
+            let index = self.table_state.selected().unwrap_or(0);
+            self.table_state.select(Some(index.min(self.packs.len() - 1)));
+        }
+        self.update_details();
+    }
+
+    pub fn selected(&self) -> Option<&Pack> {
+        self.table_state.selected().and_then(|i| self.packs.get(i))
+    }
+
+    fn is_active(&self, pack: &Pack) -> bool {
+        self.active.as_deref() == Some(pack.name.as_str())
+    }
+
+    fn update_details(&mut self) {
+        self.details = self.selected().map(|pack| {
+            let mut lines = vec![Line::from(pack.name.clone())];
+            if self.is_active(pack) {
+                lines.push(Line::from(Span::styled("active", Style::default().fg(Color::Green))));
+            }
+            lines.push(Line::from(if pack.folder { "folder" } else { "zip" }));
+            lines.push(Line::from(match self.loader {
+                Some(loader) => format!("loaded by {}", loader.name()),
+                None => "no shader loader installed".to_string(),
+            }));
+            Details {
+                title: pack.name.clone(),
+                lines,
+                icon: None,
+            }
+        });
+    }
+
+    /// Makes the selected pack the active one, or turns shaders off if it
+    /// already is
+    fn toggle_selected(&mut self) {
+        let (Some(instance), Some(pack)) = (&self.instance, self.selected()) else {
+            return;
+        };
+        let Some(loader) = self.loader else {
+            self.message = Some("Install Iris or OptiFine to use shader packs".to_string());
+            return;
+        };
+
+        let name = pack.name.clone();
+        let target = (!self.is_active(pack)).then_some(name.as_str());
+        self.message = Some(match shader::set_active(instance, loader, target) {
+            Ok(()) if target.is_some() => format!("Using {}", name),
+            Ok(()) => "Shaders off".to_string(),
+            Err(err) => format!("Failed to select {}: {}", name, err),
+        });
+        self.reload();
+    }
+
+    fn next(&mut self) {
+        if self.packs.is_empty() {
+            return;
+        }
+        let i = match self.table_state.selected() {
+            Some(i) if i + 1 < self.packs.len() => i + 1,
+            _ => 0,
+        };
+        self.table_state.select(Some(i));
+        self.update_details();
+    }
+
+    fn previous(&mut self) {
+        if self.packs.is_empty() {
+            return;
+        }
+        let i = match self.table_state.selected() {
+            Some(0) | None => self.packs.len() - 1,
+            Some(i) => i - 1,
+        };
+        self.table_state.select(Some(i));
+        self.update_details();
+    }
+}
+
+impl WidgetKey for State {
+    fn handle_key(&mut self, key_event: &crossterm::event::KeyEvent) {
+        match key_event.code {
+            KeyCode::Char('j') | KeyCode::Down => self.next(),
+            KeyCode::Char('k') | KeyCode::Up => self.previous(),
+            KeyCode::Char(' ') => self.toggle_selected(),
+            KeyCode::Char('r') => {
+                self.message = None;
+                self.reload();
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn render(frame: &mut Frame, area: Rect, mut block: Block, state: &mut State) {
+    if let Some(message) = &state.message {
+        block = block.title_bottom(Line::from(format!(" {} ", message)));
+    }
+    let loader = match state.loader {
+        Some(loader) => Span::raw(format!(" {} ", loader.name())),
+        None => Span::styled(" no shader loader ", Style::default().fg(Color::Yellow)),
+    };
+    block = block.title_bottom(Line::from(loader).right_aligned());
+
+    let rows = state.packs.iter().enumerate().map(|(i, pack)| {
+        let background_color = if i % 2 == 0 {
+            SETTINGS.colors.row_background
+        } else {
+            SETTINGS.colors.row_alternate_bg
+        };
+
+        let active = state.is_active(pack);
+        let style = if active {
+            Style::default().bg(background_color)
+        } else {
+            Style::default().bg(background_color).fg(Color::DarkGray)
+        };
+
+        Row::new(vec![
+            Cell::from(if active { "(*)" } else { "( )" }),
+            Cell::from(pack.name.clone()),
+            Cell::from(if pack.folder { "folder" } else { "zip" }),
+        ])
+        .style(style)
+    });
+
+    let widths = [
+        Constraint::Length(3),
+        Constraint::Percentage(80),
+        Constraint::Percentage(20),
+    ];
+
+    let table = Table::new(rows, widths)
+        .block(block)
+        .row_highlight_style(
+            Style::default()
+                .add_modifier(Modifier::REVERSED)
+                .fg(SETTINGS.colors.row_highlight),
+        );
+
+    frame.render_stateful_widget(table, area, &mut state.table_state);
+}
@@ -1,27 +1,86 @@
+use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
     layout::Rect,
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
     widgets::{Block, BorderType, Borders, Paragraph},
     Frame,
 };
 
+use crate::logger::{Level, Logger};
 use crate::tui::layout::FocusedArea;
 
-use super::styled_title;
+use super::{styled_title, WidgetKey};
 
-pub fn render(frame: &mut Frame, area: Rect, focused: FocusedArea) {
+/// The launcher's own messages, logged while the TUI is up
+#[derive(Debug, Default)]
+pub struct State {
+    /// Lines scrolled up from the newest message
+    scroll: usize,
+}
+
+impl WidgetKey for State {
+    fn handle_key(&mut self, key_event: &KeyEvent) {
+        match key_event.code {
+            KeyCode::Char('k') | KeyCode::Up => self.scroll = self.scroll.saturating_add(1),
+            KeyCode::Char('j') | KeyCode::Down => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_add(10),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::Char('G') | KeyCode::End => self.scroll = 0,
+            _ => {}
+        }
+    }
+}
+
+fn level_style(level: Level) -> Style {
+    match level {
+        Level::Error => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        Level::Warn => Style::default().fg(Color::Yellow),
+        Level::Info => Style::default().fg(Color::Green),
+        Level::Debug => Style::default().fg(Color::Cyan),
+        Level::Trace => Style::default().fg(Color::Magenta),
+    }
+}
+
+pub fn render(frame: &mut Frame, area: Rect, focused: FocusedArea, state: &mut State) {
     let color = if focused == FocusedArea::Status {
         Color::White
     } else {
         Color::DarkGray
     };
 
-    let block = Block::default()
+    let messages = Logger::messages();
+    let height = area.height.saturating_sub(2) as usize;
+    state.scroll = state.scroll.min(messages.len().saturating_sub(height));
+    let end = messages.len() - state.scroll;
+    let start = end.saturating_sub(height);
+
+    let mut block = Block::default()
         .title(styled_title("Status", true))
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
         .border_style(Style::default().fg(color));
+    if state.scroll > 0 {
+        block = block.title_bottom(Line::from(format!(" {} newer ", state.scroll)).right_aligned());
+    }
+
+    let lines: Vec<Line> = if messages.is_empty() {
+        vec![Line::from(Span::styled("No messages", Style::default().fg(Color::DarkGray)))]
+    } else {
+        messages[start..end]
+            .iter()
+            .map(|record| {
+                Line::from(vec![
+                    Span::styled(
+                        record.time.format("%H:%M:%S ").to_string(),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                    Span::styled(format!("{:<5} ", record.level.name()), level_style(record.level)),
+                    Span::raw(record.message.clone()),
+                ])
+            })
+            .collect()
+    };
 
-    let widget = Paragraph::new("TEST CONTENT").block(block);
-    frame.render_widget(widget, area);
+    frame.render_widget(Paragraph::new(lines).block(block), area);
 }
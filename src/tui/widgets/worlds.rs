@@ -0,0 +1,446 @@
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use color_eyre::eyre::Result;
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Cell, Row, Table, TableState},
+    Frame,
+};
+
+use crate::{
+    config::SETTINGS,
+    instance::Instance,
+    launch::{self, LaunchOptions, QuickPlay},
+    worlds::{
+        self,
+        backup::{self, Backup},
+        map::{self, Area, Dimension, Map},
+        World,
+    },
+};
+
+use super::{ascii, datapacks, details::Details, WidgetKey};
+
+const ICON_WIDTH: u32 = 16;
+/// Blocks around spawn covered by the coarse map
+const MAP_RADIUS: i32 = 128;
+const MAP_WIDTH: usize = 32;
+
+/// Work running off the UI thread
+#[derive(Debug)]
+enum Task {
+    Backup(Receiver<Result<Backup>>),
+    /// Map of the world in this folder
+    Map(String, Receiver<Result<Map>>),
+    /// Game started into the world with this name
+    Launch(String, Receiver<Result<u32>>),
+}
+
+#[derive(Debug, Default)]
+pub struct State {
+    pub instance: Option<Instance>,
+    pub worlds: Vec<World>,
+    pub table_state: TableState,
+    pub message: Option<String>,
+    pub details: Option<Details>,
+    /// New name being typed for the selected world
+    pub input: Option<String>,
+    /// Set after the first `d`, the second one deletes
+    confirm_delete: bool,
+    task: Option<Task>,
+    /// Data pack list of the selected world, opened with Enter
+    pub datapacks: Option<datapacks::State>,
+}
+
+impl State {
+    pub fn select(&mut self, instance: Option<Instance>) {
+        self.instance = instance;
+        self.message = None;
+        self.input = None;
+        self.confirm_delete = false;
+        self.task = None;
+        self.datapacks = None;
+        self.table_state.select(None);
+        self.reload();
+    }
+
+    pub fn reload(&mut self) {
+        self.worlds = self.instance.as_ref().map(worlds::list).unwrap_or_default();
+        if self.worlds.is_empty() {
+            self.table_state.select(None);
+        } else {
+            let index = self.table_state.selected().unwrap_or(0);
+            self.table_state.select(Some(index.min(self.worlds.len() - 1)));
+        }
+        self.update_details();
+    }
+
+    /// Whether key presses are text input, bypassing global shortcuts
+    pub fn capturing_input(&self) -> bool {
+        match &self.datapacks {
+            Some(datapacks) => datapacks.capturing_input(),
+            None => self.input.is_some(),
+        }
+    }
+
+    pub fn details(&self) -> Option<&Details> {
+        match &self.datapacks {
+            Some(datapacks) => datapacks.details.as_ref(),
+            None => self.details.as_ref(),
+        }
+    }
+
+    fn open_datapacks(&mut self) {
+        let (Some(instance), Some(world)) = (self.instance.clone(), self.selected().cloned()) else {
+            return;
+        };
+        self.datapacks = Some(datapacks::State::new(instance, world));
+    }
+
+    pub fn selected(&self) -> Option<&World> {
+        self.table_state.selected().and_then(|i| self.worlds.get(i))
+    }
+
+    fn update_details(&mut self) {
+        self.details = self.selected().map(world_details);
+    }
+
+    fn reselect(&mut self, folder: &str) {
+        self.reload();
+        if let Some(index) = self.worlds.iter().position(|world| world.folder == folder) {
+            self.table_state.select(Some(index));
+            self.update_details();
+        }
+    }
+
+    fn delete_selected(&mut self) {
+        let Some(world) = self.selected().cloned() else {
+            return;
+        };
+        if !self.confirm_delete {
+            self.confirm_delete = true;
+            self.message = Some(format!("Press d again to move {} to the trash", world.name));
+            return;
+        }
+
+        self.confirm_delete = false;
+        self.message = Some(match worlds::delete(&world) {
+            Ok(()) => format!("Moved {} to the trash", world.name),
+            Err(err) => format!("{:#}", err),
+        });
+        self.reload();
+    }
+
+    fn duplicate_selected(&mut self) {
+        let Some(world) = self.selected() else {
+            return;
+        };
+        match worlds::duplicate(world) {
+            Ok(copy) => {
+                self.message = Some(format!("Created {}", copy.name));
+                self.reselect(&copy.folder);
+            }
+            Err(err) => self.message = Some(format!("{:#}", err)),
+        }
+    }
+
+    /// Starts the game straight into the selected world
+    fn play_selected(&mut self) {
+        let (Some(instance), Some(world)) = (self.instance.clone(), self.selected().cloned()) else {
+            return;
+        };
+        if self.task.is_some() {
+            return;
+        }
+
+        let options = LaunchOptions {
+            quick_play: Some(QuickPlay::World(world.folder.clone())),
+            ..Default::default()
+        };
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(launch::start(&instance, &options));
+        });
+        self.task = Some(Task::Launch(world.name.clone(), receiver));
+        self.message = Some(format!("Starting {}...", world.name));
+    }
+
+    fn backup_selected(&mut self) {
+        let (Some(instance), Some(world)) = (self.instance.clone(), self.selected().cloned()) else {
+            return;
+        };
+        if self.task.is_some() {
+            return;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(backup::create(&instance, &world));
+        });
+        self.task = Some(Task::Backup(receiver));
+        self.message = Some("Backing up...".to_string());
+    }
+
+    /// Renders the area around spawn for the Details panel
+    fn map_selected(&mut self) {
+        let Some(world) = self.selected().cloned() else {
+            return;
+        };
+        if self.task.is_some() {
+            return;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        let folder = world.folder.clone();
+        thread::spawn(move || {
+            let area = Area::around(world.spawn.0, world.spawn.1, MAP_RADIUS);
+            let _ = sender.send(map::build(&world, &Dimension::Overworld, Some(area)));
+        });
+        self.task = Some(Task::Map(folder, receiver));
+        self.message = Some("Rendering map...".to_string());
+    }
+
+    /// Collects the result of a running background task
+    pub fn poll(&mut self) {
+        if let Some(datapacks) = &mut self.datapacks {
+            datapacks.poll();
+        }
+        let message = match &self.task {
+            Some(Task::Backup(receiver)) => match receiver.try_recv() {
+                Ok(Ok(backup)) => format!(
+                    "Backed up to {} ({})",
+                    backup.name(),
+                    worlds::format_size(backup.size)
+                ),
+                Ok(Err(err)) => format!("Backup failed: {:#}", err),
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => "Backup failed".to_string(),
+            },
+            Some(Task::Map(folder, receiver)) => match receiver.try_recv() {
+                Ok(Ok(map)) => {
+                    // the selection may have moved on while rendering
+                    let current = self.selected().is_some_and(|world| world.folder == *folder);
+                    if let Some(details) = self.details.as_mut().filter(|_| current) {
+                        details.lines.push(Line::from(""));
+                        details.lines.extend(map_lines(&map));
+                    }
+                    format!("Map shows {0}x{0} blocks around spawn", MAP_RADIUS * 2)
+                }
+                Ok(Err(err)) => format!("Map failed: {:#}", err),
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => "Map failed".to_string(),
+            },
+            Some(Task::Launch(world, receiver)) => match receiver.try_recv() {
+                Ok(Ok(pid)) => {
+                    let name = self.instance.as_ref().map_or("", |instance| instance.name.as_str());
+                    format!("Started {} in {} (pid {})", name, world, pid)
+                }
+                Ok(Err(err)) => format!("{:#}", err),
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => "Launch failed".to_string(),
+            },
+            None => return,
+        };
+        self.message = Some(message);
+        self.task = None;
+    }
+
+    fn finish_rename(&mut self) {
+        let Some(name) = self.input.take() else {
+            return;
+        };
+        let Some(world) = self.selected() else {
+            return;
+        };
+        let folder = world.folder.clone();
+        self.message = Some(match worlds::rename(world, &name) {
+            Ok(()) => format!("Renamed to {}", name.trim()),
+            Err(err) => format!("{:#}", err),
+        });
+        self.reselect(&folder);
+    }
+
+    fn handle_input(&mut self, code: KeyCode) {
+        let Some(input) = &mut self.input else {
+            return;
+        };
+        match code {
+            KeyCode::Enter => self.finish_rename(),
+            KeyCode::Esc => self.input = None,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        }
+    }
+
+    fn next(&mut self) {
+        if self.worlds.is_empty() {
+            return;
+        }
+        let i = match self.table_state.selected() {
+            Some(i) if i + 1 < self.worlds.len() => i + 1,
+            _ => 0,
+        };
+        self.table_state.select(Some(i));
+        self.update_details();
+    }
+
+    fn previous(&mut self) {
+        if self.worlds.is_empty() {
+            return;
+        }
+        let i = match self.table_state.selected() {
+            Some(0) | None => self.worlds.len() - 1,
+            Some(i) => i - 1,
+        };
+        self.table_state.select(Some(i));
+        self.update_details();
+    }
+}
+
+impl WidgetKey for State {
+    fn handle_key(&mut self, key_event: &crossterm::event::KeyEvent) {
+        if let Some(datapacks) = &mut self.datapacks {
+            if key_event.code == KeyCode::Esc && !datapacks.capturing_input() {
+                self.datapacks = None;
+            } else {
+                datapacks.handle_key(key_event);
+            }
+            return;
+        }
+        if self.capturing_input() {
+            self.handle_input(key_event.code);
+            return;
+        }
+        if key_event.code != KeyCode::Char('d') {
+            self.confirm_delete = false;
+        }
+
+        match key_event.code {
+            KeyCode::Char('j') | KeyCode::Down => self.next(),
+            KeyCode::Char('k') | KeyCode::Up => self.previous(),
+            KeyCode::Char('d') => self.delete_selected(),
+            KeyCode::Char('c') => self.duplicate_selected(),
+            KeyCode::Char('b') => self.backup_selected(),
+            KeyCode::Char('m') => self.map_selected(),
+            KeyCode::Char('p') => self.play_selected(),
+            KeyCode::Enter => self.open_datapacks(),
+            KeyCode::Char('n') => {
+                self.input = self.selected().map(|world| world.name.clone());
+            }
+            KeyCode::Char('r') => self.reload(),
+            _ => {}
+        }
+    }
+}
+
+fn world_details(world: &World) -> Details {
+    let mut lines = vec![Line::from(world.name.clone())];
+    lines.push(Line::from(format!(
+        "{}, {}",
+        world.mode_label(),
+        world.difficulty.map_or("Unknown", worlds::difficulty_name)
+    )));
+    if let Some(version) = &world.version {
+        lines.push(Line::from(format!("version: {}", version)));
+    }
+    if let Some(seed) = world.seed {
+        lines.push(Line::from(format!("seed: {}", seed)));
+    }
+    if let Some(played) = world.last_played {
+        lines.push(Line::from(format!("last played: {}", played.format("%Y-%m-%d %H:%M"))));
+    }
+    lines.push(Line::from(format!("size: {}", worlds::format_size(world.size))));
+    lines.push(Line::from(Span::styled(
+        format!("saves/{}", world.folder),
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    Details {
+        title: world.folder.clone(),
+        lines,
+        icon: world.icon().and_then(|bytes| ascii::render(&bytes, ICON_WIDTH)),
+    }
+}
+
+/// Coarse map with a cell per 8x16 blocks, terminal cells being about
+/// twice as tall as wide
+fn map_lines(map: &Map) -> Vec<Line<'static>> {
+    map.ascii(MAP_WIDTH, MAP_WIDTH / 2)
+        .into_iter()
+        .map(|row| {
+            Line::from(
+                row.into_iter()
+                    .map(|cell| match cell {
+                        Some((glyph, [r, g, b])) => {
+                            Span::styled(glyph.to_string(), Style::default().fg(Color::Rgb(r, g, b)))
+                        }
+                        None => Span::raw(" "),
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect()
+}
+
+pub fn render(frame: &mut Frame, area: Rect, mut block: Block, state: &mut State) {
+    if let Some(datapacks) = &mut state.datapacks {
+        datapacks::render(frame, area, block, datapacks);
+        return;
+    }
+    if let Some(input) = &state.input {
+        block = block.title_bottom(Line::from(Span::styled(
+            format!(" Rename: {}_ ", input),
+            Style::default().fg(Color::Yellow),
+        )));
+    } else if let Some(message) = &state.message {
+        block = block.title_bottom(Line::from(format!(" {} ", message)));
+    }
+    block = block.title_bottom(Line::from(format!(" {} worlds ", state.worlds.len())).right_aligned());
+
+    let rows = state.worlds.iter().enumerate().map(|(i, world)| {
+        let background_color = if i % 2 == 0 {
+            SETTINGS.colors.row_background
+        } else {
+            SETTINGS.colors.row_alternate_bg
+        };
+
+        Row::new(vec![
+            Cell::from(world.name.clone()),
+            Cell::from(world.mode_label()),
+            Cell::from(world.version.clone().unwrap_or_default()),
+            Cell::from(
+                world
+                    .last_played
+                    .map(|played| played.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default(),
+            ),
+            Cell::from(worlds::format_size(world.size)),
+        ])
+        .style(Style::default().bg(background_color))
+    });
+
+    let widths = [
+        Constraint::Percentage(35),
+        Constraint::Percentage(15),
+        Constraint::Percentage(15),
+        Constraint::Percentage(20),
+        Constraint::Percentage(15),
+    ];
+
+    let table = Table::new(rows, widths)
+        .block(block)
+        .row_highlight_style(
+            Style::default()
+                .add_modifier(Modifier::REVERSED)
+                .fg(SETTINGS.colors.row_highlight),
+        );
+
+    frame.render_stateful_widget(table, area, &mut state.table_state);
+}
@@ -0,0 +1,260 @@
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{Datelike, Local, NaiveDateTime, Timelike};
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{
+    config::get_data_path,
+    debug,
+    instance::{process, BackupSettings, Instance},
+};
+
+use super::World;
+
+const TIMESTAMP: &str = "%Y-%m-%d_%H-%M-%S";
+
+/// Backups live outside the instance so deleting it keeps them
+pub fn backups_dir(instance: &Instance, folder: &str) -> PathBuf {
+    get_data_path().join("backups").join(&instance.id).join(folder)
+}
+
+#[derive(Debug, Clone)]
+pub struct Backup {
+    pub path: PathBuf,
+    pub created: NaiveDateTime,
+    /// 1 for the first backup of a second, then 2, 3...
+    pub sequence: u32,
+    pub size: u64,
+}
+
+impl Backup {
+    pub fn name(&self) -> String {
+        backup_name(self.created, self.sequence)
+    }
+}
+
+/// `2024-05-01_12-00-00`, with `-2`, `-3`... for later backups in the same
+/// second
+fn backup_name(created: NaiveDateTime, sequence: u32) -> String {
+    match sequence {
+        1 => created.format(TIMESTAMP).to_string(),
+        sequence => format!("{}-{}", created.format(TIMESTAMP), sequence),
+    }
+}
+
+fn parse_name(name: &str) -> Option<(NaiveDateTime, u32)> {
+    if let Ok(created) = NaiveDateTime::parse_from_str(name, TIMESTAMP) {
+        return Some((created, 1));
+    }
+    let (timestamp, sequence) = name.rsplit_once('-')?;
+    let sequence = sequence.parse().ok().filter(|sequence| *sequence > 1)?;
+    Some((NaiveDateTime::parse_from_str(timestamp, TIMESTAMP).ok()?, sequence))
+}
+
+/// Backups of a world, newest first
+pub fn list(instance: &Instance, folder: &str) -> Vec<Backup> {
+    let Ok(entries) = fs::read_dir(backups_dir(instance, folder)) else {
+        return Vec::new();
+    };
+
+    let mut backups: Vec<Backup> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let stem = path.file_name()?.to_str()?.strip_suffix(".zip")?;
+            let (created, sequence) = parse_name(stem)?;
+            Some(Backup {
+                size: entry.metadata().map(|meta| meta.len()).unwrap_or(0),
+                path,
+                created,
+                sequence,
+            })
+        })
+        .collect();
+    backups.sort_by_key(|backup| std::cmp::Reverse((backup.created, backup.sequence)));
+    backups
+}
+
+fn add_dir(zip: &mut ZipWriter<File>, root: &Path, dir: &Path, options: SimpleFileOptions) -> Result<()> {
+    for entry in fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        let relative = path
+            .strip_prefix(root)?
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        if entry.file_type()?.is_dir() {
+            zip.add_directory(format!("{}/", relative), options)?;
+            add_dir(zip, root, &path, options)?;
+        } else if entry.file_name() != "session.lock" {
+            zip.start_file(relative, options)?;
+            io::copy(&mut File::open(&path)?, zip)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes a timestamped zip of the world and applies the retention policy
+pub fn create(instance: &Instance, world: &World) -> Result<Backup> {
+    let dir = backups_dir(instance, &world.folder);
+    fs::create_dir_all(&dir)?;
+
+    // whole seconds keep names readable, so backups made within one get
+    // numbered; creating the partial file claims the name
+    let created = Local::now().naive_local().with_nanosecond(0).unwrap_or_default();
+    let (sequence, path, partial, file) = (1..)
+        .find_map(|sequence| {
+            let name = backup_name(created, sequence);
+            let path = dir.join(format!("{}.zip", name));
+            let partial = dir.join(format!("{}.zip.part", name));
+            if path.exists() {
+                return None;
+            }
+            match OpenOptions::new().write(true).create_new(true).open(&partial) {
+                Ok(file) => Some(Ok((sequence, path, partial, file))),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => None,
+                Err(err) => Some(Err(err)),
+            }
+        })
+        .expect("some backup name is free")?;
+
+    let write = || -> Result<()> {
+        let mut zip = ZipWriter::new(file);
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .large_file(true);
+        add_dir(&mut zip, &world.path, &world.path, options)?;
+        zip.finish()?;
+        Ok(())
+    };
+    if let Err(err) = write() {
+        let _ = fs::remove_file(&partial);
+        return Err(err.wrap_err(format!("failed to back up {}", world.name)));
+    }
+    fs::rename(&partial, &path)?;
+
+    let removed = prune(instance, &world.folder, &instance.backups)?;
+    debug!("Backed up {} to {}, pruned {}", world.folder, path.display(), removed);
+    Ok(Backup {
+        size: fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0),
+        path,
+        created,
+        sequence,
+    })
+}
+
+/// Deletes backups not covered by any rule: the newest `keep_last`, plus
+/// the newest of each of the last `keep_daily` days and `keep_weekly`
+/// weeks. Returns how many were deleted.
+pub fn prune(instance: &Instance, folder: &str, settings: &BackupSettings) -> Result<usize> {
+    let backups = list(instance, folder);
+    let today = Local::now().date_naive();
+
+    let mut keep: HashSet<usize> = (0..settings.keep_last.min(backups.len())).collect();
+    let mut days = HashSet::new();
+    let mut weeks = HashSet::new();
+    for (index, backup) in backups.iter().enumerate() {
+        let date = backup.created.date();
+        let age = (today - date).num_days();
+
+        // backups are newest first, so the first one seen per bucket wins
+        if age < settings.keep_daily as i64 && days.insert(date) {
+            keep.insert(index);
+        }
+        let week = date.iso_week();
+        if age < settings.keep_weekly as i64 * 7 && weeks.insert((week.year(), week.week())) {
+            keep.insert(index);
+        }
+    }
+
+    let mut removed = 0;
+    for (index, backup) in backups.iter().enumerate() {
+        if !keep.contains(&index) {
+            // another launcher pruning at the same time may have got there first
+            match fs::remove_file(&backup.path) {
+                Ok(()) => removed += 1,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+    Ok(removed)
+}
+
+/// Backs up every world of the instance, used before launching
+pub fn create_all(instance: &Instance) -> Result<usize> {
+    let worlds = super::list(instance);
+    for world in &worlds {
+        create(instance, world)?;
+    }
+    Ok(worlds.len())
+}
+
+/// Replaces the world folder with the contents of a backup. The backup is
+/// extracted next to the world first and swapped in with renames, so a
+/// failure leaves the current world untouched.
+pub fn restore(instance: &Instance, folder: &str, backup: &Backup) -> Result<()> {
+    if let Some(pid) = process::running(instance) {
+        return Err(eyre!(
+            "{} is running (pid {}), close the game before restoring",
+            instance.name,
+            pid
+        ));
+    }
+
+    let saves = super::saves_dir(instance);
+    let target = saves.join(folder);
+    let staging = saves.join(format!(".{}.restore", folder));
+    let previous = saves.join(format!(".{}.old", folder));
+    for leftover in [&staging, &previous] {
+        if leftover.exists() {
+            fs::remove_dir_all(leftover)?;
+        }
+    }
+
+    let mut archive = ZipArchive::new(File::open(&backup.path)?)
+        .wrap_err_with(|| format!("{} is not a valid backup", backup.path.display()))?;
+    // enclosed_name rejects entries that would escape the target folder
+    if let Err(err) = archive.extract(&staging) {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(eyre!("failed to extract {}: {}", backup.path.display(), err));
+    }
+
+    if target.exists() {
+        fs::rename(&target, &previous)?;
+    }
+    if let Err(err) = fs::rename(&staging, &target) {
+        if previous.exists() {
+            fs::rename(&previous, &target)?;
+        }
+        return Err(eyre!("failed to restore {}: {}", folder, err));
+    }
+    if previous.exists() {
+        fs::remove_dir_all(&previous)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        let created = NaiveDateTime::parse_from_str("2024-05-01_12-00-09", TIMESTAMP).unwrap();
+        assert_eq!(backup_name(created, 1), "2024-05-01_12-00-09");
+        assert_eq!(backup_name(created, 12), "2024-05-01_12-00-09-12");
+        for sequence in [1, 2, 12] {
+            assert_eq!(parse_name(&backup_name(created, sequence)), Some((created, sequence)));
+        }
+        assert_eq!(parse_name("2024-05-01_12-00-09-1"), None);
+        assert_eq!(parse_name("2024-05-01_12-00"), None);
+        assert_eq!(parse_name("notes"), None);
+    }
+}
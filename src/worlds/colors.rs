@@ -0,0 +1,230 @@
+/// Map colors of common blocks, roughly the average of their top texture.
+/// Anything missing falls back to [`fallback`] by name.
+const BLOCKS: &[(&str, [u8; 3])] = &[
+    ("grass_block", [109, 153, 48]),
+    ("dirt", [134, 96, 67]),
+    ("coarse_dirt", [119, 85, 59]),
+    ("rooted_dirt", [144, 103, 76]),
+    ("podzol", [91, 63, 24]),
+    ("mycelium", [111, 99, 105]),
+    ("dirt_path", [148, 122, 65]),
+    ("farmland", [81, 44, 15]),
+    ("mud", [60, 57, 60]),
+    ("clay", [160, 166, 179]),
+    ("gravel", [131, 127, 126]),
+    ("sand", [219, 207, 163]),
+    ("red_sand", [190, 102, 33]),
+    ("sandstone", [216, 203, 155]),
+    ("red_sandstone", [181, 97, 31]),
+    ("stone", [125, 125, 125]),
+    ("cobblestone", [127, 127, 127]),
+    ("mossy_cobblestone", [110, 118, 94]),
+    ("granite", [149, 103, 85]),
+    ("diorite", [188, 188, 188]),
+    ("andesite", [136, 136, 136]),
+    ("deepslate", [80, 80, 82]),
+    ("tuff", [108, 109, 102]),
+    ("calcite", [223, 224, 220]),
+    ("bedrock", [85, 85, 85]),
+    ("obsidian", [15, 10, 24]),
+    ("water", [63, 118, 228]),
+    ("ice", [145, 183, 253]),
+    ("packed_ice", [141, 180, 250]),
+    ("blue_ice", [116, 167, 253]),
+    ("snow", [249, 254, 254]),
+    ("snow_block", [249, 254, 254]),
+    ("powder_snow", [248, 253, 253]),
+    ("lava", [207, 92, 20]),
+    ("magma_block", [142, 63, 31]),
+    ("short_grass", [94, 145, 42]),
+    ("grass", [94, 145, 42]),
+    ("tall_grass", [94, 145, 42]),
+    ("fern", [88, 140, 40]),
+    ("large_fern", [88, 140, 40]),
+    ("oak_leaves", [60, 110, 30]),
+    ("birch_leaves", [90, 130, 55]),
+    ("spruce_leaves", [55, 90, 55]),
+    ("jungle_leaves", [48, 120, 20]),
+    ("acacia_leaves", [80, 120, 25]),
+    ("dark_oak_leaves", [50, 100, 20]),
+    ("mangrove_leaves", [70, 120, 30]),
+    ("cherry_leaves", [229, 173, 194]),
+    ("azalea_leaves", [90, 117, 44]),
+    ("flowering_azalea_leaves", [100, 111, 61]),
+    ("oak_log", [109, 85, 50]),
+    ("spruce_log", [58, 37, 16]),
+    ("birch_log", [216, 215, 210]),
+    ("jungle_log", [85, 67, 25]),
+    ("acacia_log", [103, 96, 86]),
+    ("dark_oak_log", [60, 46, 26]),
+    ("cactus", [85, 127, 43]),
+    ("sugar_cane", [148, 192, 101]),
+    ("pumpkin", [198, 118, 24]),
+    ("melon", [111, 145, 30]),
+    ("lily_pad", [32, 128, 48]),
+    ("moss_block", [89, 109, 45]),
+    ("terracotta", [152, 94, 67]),
+    ("netherrack", [97, 38, 38]),
+    ("nether_wart_block", [114, 2, 2]),
+    ("warped_wart_block", [22, 119, 121]),
+    ("crimson_nylium", [130, 31, 31]),
+    ("warped_nylium", [43, 114, 101]),
+    ("soul_sand", [81, 62, 50]),
+    ("soul_soil", [75, 57, 46]),
+    ("basalt", [80, 81, 86]),
+    ("blackstone", [42, 35, 40]),
+    ("glowstone", [171, 131, 84]),
+    ("nether_bricks", [44, 21, 26]),
+    ("end_stone", [219, 222, 158]),
+    ("end_stone_bricks", [218, 224, 162]),
+    ("purpur_block", [169, 125, 169]),
+    ("chorus_plant", [93, 57, 93]),
+    ("chorus_flower", [151, 120, 151]),
+    ("oak_planks", [162, 130, 78]),
+    ("spruce_planks", [114, 84, 48]),
+    ("birch_planks", [192, 175, 121]),
+    ("bricks", [150, 97, 83]),
+    ("stone_bricks", [122, 121, 122]),
+    ("glass", [175, 213, 219]),
+    ("white_wool", [233, 236, 236]),
+    ("hay_block", [166, 139, 12]),
+    ("torch", [255, 214, 90]),
+];
+
+/// Block names for numeric ids used before 1.13
+const LEGACY: &[(u8, &str)] = &[
+    (1, "minecraft:stone"),
+    (2, "minecraft:grass_block"),
+    (3, "minecraft:dirt"),
+    (4, "minecraft:cobblestone"),
+    (5, "minecraft:oak_planks"),
+    (6, "minecraft:oak_sapling"),
+    (7, "minecraft:bedrock"),
+    (8, "minecraft:water"),
+    (9, "minecraft:water"),
+    (10, "minecraft:lava"),
+    (11, "minecraft:lava"),
+    (12, "minecraft:sand"),
+    (13, "minecraft:gravel"),
+    (14, "minecraft:gold_ore"),
+    (15, "minecraft:iron_ore"),
+    (16, "minecraft:coal_ore"),
+    (17, "minecraft:oak_log"),
+    (18, "minecraft:oak_leaves"),
+    (20, "minecraft:glass"),
+    (24, "minecraft:sandstone"),
+    (31, "minecraft:grass"),
+    (32, "minecraft:dead_bush"),
+    (35, "minecraft:white_wool"),
+    (37, "minecraft:dandelion"),
+    (38, "minecraft:poppy"),
+    (45, "minecraft:bricks"),
+    (48, "minecraft:mossy_cobblestone"),
+    (49, "minecraft:obsidian"),
+    (50, "minecraft:torch"),
+    (53, "minecraft:oak_stairs"),
+    (60, "minecraft:farmland"),
+    (78, "minecraft:snow"),
+    (79, "minecraft:ice"),
+    (80, "minecraft:snow_block"),
+    (81, "minecraft:cactus"),
+    (82, "minecraft:clay"),
+    (83, "minecraft:sugar_cane"),
+    (86, "minecraft:pumpkin"),
+    (87, "minecraft:netherrack"),
+    (88, "minecraft:soul_sand"),
+    (89, "minecraft:glowstone"),
+    (98, "minecraft:stone_bricks"),
+    (99, "minecraft:brown_mushroom_block"),
+    (100, "minecraft:red_mushroom_block"),
+    (103, "minecraft:melon"),
+    (106, "minecraft:vine"),
+    (110, "minecraft:mycelium"),
+    (111, "minecraft:lily_pad"),
+    (112, "minecraft:nether_bricks"),
+    (121, "minecraft:end_stone"),
+    (159, "minecraft:terracotta"),
+    (161, "minecraft:acacia_leaves"),
+    (162, "minecraft:acacia_log"),
+    (170, "minecraft:hay_block"),
+    (172, "minecraft:terracotta"),
+    (174, "minecraft:packed_ice"),
+    (175, "minecraft:tall_grass"),
+    (179, "minecraft:red_sandstone"),
+    (201, "minecraft:purpur_block"),
+    (208, "minecraft:dirt_path"),
+];
+
+/// Namespaced name of a pre-1.13 block id, unknown ids become stone
+pub fn legacy_name(id: u8) -> &'static str {
+    if id == 0 {
+        return "minecraft:air";
+    }
+    match LEGACY.binary_search_by_key(&id, |(legacy, _)| *legacy) {
+        Ok(index) => LEGACY[index].1,
+        Err(_) => "minecraft:stone",
+    }
+}
+
+/// Guesses a color from the name for blocks missing from the table
+fn fallback(name: &str) -> [u8; 3] {
+    const PATTERNS: &[(&str, [u8; 3])] = &[
+        ("leaves", [60, 110, 30]),
+        ("log", [100, 78, 48]),
+        ("wood", [100, 78, 48]),
+        ("planks", [150, 120, 75]),
+        ("sapling", [70, 120, 35]),
+        ("flower", [200, 90, 110]),
+        ("tulip", [200, 90, 110]),
+        ("mushroom", [150, 100, 80]),
+        ("coral", [220, 90, 140]),
+        ("ice", [145, 183, 253]),
+        ("snow", [249, 254, 254]),
+        ("sand", [219, 207, 163]),
+        ("terracotta", [152, 94, 67]),
+        ("concrete", [160, 160, 160]),
+        ("wool", [220, 220, 220]),
+        ("glass", [175, 213, 219]),
+        ("brick", [140, 90, 80]),
+        ("copper", [192, 107, 79]),
+        ("nether", [97, 38, 38]),
+        ("crimson", [130, 31, 31]),
+        ("warped", [43, 114, 101]),
+        ("deepslate", [80, 80, 82]),
+        ("ore", [125, 125, 125]),
+        ("stone", [125, 125, 125]),
+        ("vine", [60, 110, 30]),
+        ("grass", [94, 145, 42]),
+    ];
+    PATTERNS
+        .iter()
+        .find(|(pattern, _)| name.contains(pattern))
+        .map_or([140, 140, 140], |(_, color)| *color)
+}
+
+/// Map color of a namespaced block id
+pub fn color(block: &str) -> [u8; 3] {
+    let name = block.strip_prefix("minecraft:").unwrap_or(block);
+    BLOCKS
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map_or_else(|| fallback(name), |(_, color)| *color)
+}
+
+/// Character for the coarse text map
+pub fn glyph(block: &str, water: u16) -> char {
+    if water > 0 {
+        return '~';
+    }
+    let name = block.strip_prefix("minecraft:").unwrap_or(block);
+    match name {
+        _ if name.contains("leaves") || name.contains("log") => 'T',
+        _ if name.contains("snow") || name.contains("ice") => '*',
+        _ if name.contains("sand") => ':',
+        _ if name.contains("lava") || name.contains("magma") => '%',
+        "grass_block" | "short_grass" | "grass" | "tall_grass" | "fern" | "moss_block" => '"',
+        _ if name.contains("stone") || name.contains("deepslate") || name == "gravel" => '^',
+        _ if name.contains("planks") || name.contains("brick") || name.contains("glass") => '#',
+        _ => '.',
+    }
+}
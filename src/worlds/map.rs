@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use image::{Rgba, RgbaImage};
+
+use crate::debug;
+
+use super::{
+    colors,
+    region::{self, Chunk, Region},
+    World,
+};
+
+/// A character of the text map with its color, `None` where nothing was
+/// generated
+pub type Cell = Option<(char, [u8; 3])>;
+
+/// Largest map rendered in one go, about 16 million columns
+const MAX_BLOCKS: i64 = 4096 * 4096;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dimension {
+    Overworld,
+    Nether,
+    End,
+    /// Datapack dimension such as `mypack:mining`
+    Custom(String),
+}
+
+impl Dimension {
+    pub fn parse(value: &str) -> Result<Dimension> {
+        let id = value.trim().to_lowercase();
+        Ok(match id.strip_prefix("minecraft:").unwrap_or(&id) {
+            "overworld" => Dimension::Overworld,
+            "nether" | "the_nether" => Dimension::Nether,
+            "end" | "the_end" => Dimension::End,
+            _ if id.split_once(':').is_some_and(|(ns, path)| !ns.is_empty() && !path.is_empty()) => {
+                Dimension::Custom(id)
+            }
+            _ => {
+                return Err(eyre!(
+                    "unknown dimension '{}', expected overworld, nether, end or namespace:id",
+                    value
+                ))
+            }
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Dimension::Overworld => "overworld",
+            Dimension::Nether => "nether",
+            Dimension::End => "end",
+            Dimension::Custom(id) => id,
+        }
+    }
+
+    pub fn region_dir(&self, world: &World) -> PathBuf {
+        match self {
+            Dimension::Overworld => world.path.join("region"),
+            Dimension::Nether => world.path.join("DIM-1").join("region"),
+            Dimension::End => world.path.join("DIM1").join("region"),
+            Dimension::Custom(id) => {
+                let (namespace, path) = id.split_once(':').unwrap_or(("minecraft", id));
+                world.path.join("dimensions").join(namespace).join(path).join("region")
+            }
+        }
+    }
+
+    /// The nether is all roof from above, so the map starts below it
+    fn ceiling(&self) -> Option<i32> {
+        match self {
+            Dimension::Nether => Some(127),
+            _ => None,
+        }
+    }
+}
+
+/// Rectangle of world block coordinates, max exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Area {
+    pub min_x: i32,
+    pub min_z: i32,
+    pub max_x: i32,
+    pub max_z: i32,
+}
+
+impl Area {
+    pub fn around(x: i32, z: i32, radius: i32) -> Area {
+        Area {
+            min_x: x - radius,
+            min_z: z - radius,
+            max_x: x + radius,
+            max_z: z + radius,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        (self.max_x - self.min_x).max(0) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.max_z - self.min_z).max(0) as usize
+    }
+
+    fn overlaps(&self, min_x: i32, min_z: i32, size: i32) -> bool {
+        min_x < self.max_x && min_x + size > self.min_x && min_z < self.max_z && min_z + size > self.min_z
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Column {
+    /// Index into [`Map::blocks`]
+    block: u16,
+    y: i16,
+    water: u16,
+}
+
+/// Top-down view of a dimension, one column per block
+#[derive(Debug, Clone)]
+pub struct Map {
+    pub area: Area,
+    blocks: Vec<String>,
+    columns: Vec<Option<Column>>,
+}
+
+/// Reads the region files of a dimension and finds the top block of every
+/// column in `area`, or of every generated chunk when no area is given
+pub fn build(world: &World, dimension: &Dimension, area: Option<Area>) -> Result<Map> {
+    let regions = region::list(&dimension.region_dir(world));
+    if regions.is_empty() {
+        return Err(eyre!("{} has no {} region files", world.name, dimension.name()));
+    }
+    let area = match area {
+        Some(area) => area,
+        None => Area {
+            min_x: regions.iter().map(|(x, _, _)| x * 512).min().unwrap_or(0),
+            min_z: regions.iter().map(|(_, z, _)| z * 512).min().unwrap_or(0),
+            max_x: regions.iter().map(|(x, _, _)| (x + 1) * 512).max().unwrap_or(0),
+            max_z: regions.iter().map(|(_, z, _)| (z + 1) * 512).max().unwrap_or(0),
+        },
+    };
+    if area.width() as i64 * area.height() as i64 > MAX_BLOCKS {
+        return Err(eyre!(
+            "{} spans {}x{} blocks, pass a smaller radius",
+            dimension.name(),
+            area.width(),
+            area.height()
+        ));
+    }
+
+    let mut map = Map {
+        area,
+        blocks: Vec::new(),
+        columns: vec![None; area.width() * area.height()],
+    };
+    let mut interned: HashMap<String, u16> = HashMap::new();
+
+    for (region_x, region_z, path) in regions {
+        if !area.overlaps(region_x * 512, region_z * 512, 512) {
+            continue;
+        }
+        let region = Region::open(&path)?;
+        for chunk_z in 0..32 {
+            for chunk_x in 0..32 {
+                let base_x = region_x * 512 + chunk_x as i32 * 16;
+                let base_z = region_z * 512 + chunk_z as i32 * 16;
+                if !area.overlaps(base_x, base_z, 16) {
+                    continue;
+                }
+                // one corrupt chunk should not cost the whole map
+                let chunk = match region.chunk(chunk_x, chunk_z) {
+                    Ok(Some(root)) => Chunk::parse(&root),
+                    Ok(None) => continue,
+                    Err(err) => Err(err),
+                };
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(err) => {
+                        debug!("Skipping chunk {},{} of {}: {:#}", chunk_x, chunk_z, path.display(), err);
+                        continue;
+                    }
+                };
+
+                for z in 0..16 {
+                    for x in 0..16 {
+                        let (world_x, world_z) = (base_x + x as i32, base_z + z as i32);
+                        let Some(index) = map.index(world_x, world_z) else {
+                            continue;
+                        };
+                        let Some(top) = chunk.top(x, z, dimension.ceiling()) else {
+                            continue;
+                        };
+                        let next = interned.len() as u16;
+                        let block = *interned.entry(top.block.clone()).or_insert_with(|| {
+                            map.blocks.push(top.block);
+                            next
+                        });
+                        map.columns[index] = Some(Column {
+                            block,
+                            y: top.y.clamp(i16::MIN as i32, i16::MAX as i32) as i16,
+                            water: top.water,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    Ok(map)
+}
+
+fn scale(color: [u8; 3], factor: f32) -> [u8; 3] {
+    color.map(|channel| (channel as f32 * factor).clamp(0.0, 255.0) as u8)
+}
+
+fn mix(a: [u8; 3], b: [u8; 3], weight: f32) -> [u8; 3] {
+    [0, 1, 2].map(|i| (a[i] as f32 * (1.0 - weight) + b[i] as f32 * weight) as u8)
+}
+
+impl Map {
+    fn index(&self, x: i32, z: i32) -> Option<usize> {
+        let area = self.area;
+        if x < area.min_x || x >= area.max_x || z < area.min_z || z >= area.max_z {
+            return None;
+        }
+        Some((z - area.min_z) as usize * area.width() + (x - area.min_x) as usize)
+    }
+
+    fn column(&self, x: i32, z: i32) -> Option<Column> {
+        self.columns[self.index(x, z)?]
+    }
+
+    /// Top block at world coordinates
+    pub fn block(&self, x: i32, z: i32) -> Option<&str> {
+        self.column(x, z).map(|column| self.blocks[column.block as usize].as_str())
+    }
+
+    /// Shaded color: slopes facing north get lighter, water darkens with depth
+    pub fn color(&self, x: i32, z: i32) -> Option<[u8; 3]> {
+        let column = self.column(x, z)?;
+        let block = &self.blocks[column.block as usize];
+        if column.water > 0 {
+            let water = colors::color("minecraft:water");
+            let depth = column.water.min(24) as f32;
+            // shallow water shows some of the ground below
+            let base = if column.water <= 2 {
+                mix(water, colors::color(block), 0.3)
+            } else {
+                water
+            };
+            return Some(scale(base, 1.0 - depth / 48.0));
+        }
+
+        let color = colors::color(block);
+        let factor = match self.column(x, z - 1) {
+            Some(north) if north.water == 0 && north.y < column.y => 1.12,
+            Some(north) if north.water == 0 && north.y > column.y => 0.82,
+            _ => 1.0,
+        };
+        Some(scale(color, factor))
+    }
+
+    pub fn image(&self) -> RgbaImage {
+        let area = self.area;
+        RgbaImage::from_fn(area.width() as u32, area.height() as u32, |px, pz| {
+            let (x, z) = (area.min_x + px as i32, area.min_z + pz as i32);
+            match self.color(x, z) {
+                Some([r, g, b]) => Rgba([r, g, b, 255]),
+                None => Rgba([0, 0, 0, 0]),
+            }
+        })
+    }
+
+    pub fn save_png(&self, path: &Path) -> Result<()> {
+        self.image()
+            .save_with_format(path, image::ImageFormat::Png)
+            .wrap_err_with(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Downsamples the map into `width` x `height` characters. Each cell
+    /// shows the most common glyph of a few samples.
+    pub fn ascii(&self, width: usize, height: usize) -> Vec<Vec<Cell>> {
+        const SAMPLES: usize = 4;
+        let area = self.area;
+        let cell_width = area.width() as f32 / width as f32;
+        let cell_height = area.height() as f32 / height as f32;
+
+        (0..height)
+            .map(|row| {
+                (0..width)
+                    .map(|col| {
+                        let mut counts: Vec<(char, [u8; 3], usize)> = Vec::new();
+                        for sz in 0..SAMPLES {
+                            for sx in 0..SAMPLES {
+                                let x = area.min_x
+                                    + ((col as f32 + (sx as f32 + 0.5) / SAMPLES as f32) * cell_width) as i32;
+                                let z = area.min_z
+                                    + ((row as f32 + (sz as f32 + 0.5) / SAMPLES as f32) * cell_height) as i32;
+                                let (Some(column), Some(color)) = (self.column(x, z), self.color(x, z)) else {
+                                    continue;
+                                };
+                                let glyph = colors::glyph(&self.blocks[column.block as usize], column.water);
+                                match counts.iter_mut().find(|(known, _, _)| *known == glyph) {
+                                    Some((_, _, count)) => *count += 1,
+                                    None => counts.push((glyph, color, 1)),
+                                }
+                            }
+                        }
+                        counts
+                            .into_iter()
+                            .max_by_key(|(_, _, count)| *count)
+                            .map(|(glyph, color, _)| (glyph, color))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
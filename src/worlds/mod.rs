@@ -0,0 +1,287 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, TimeZone};
+use color_eyre::eyre::{eyre, Result, WrapErr};
+
+use crate::{
+    debug,
+    instance::{process, Instance},
+    nbt::{self, Compound, Tag},
+};
+
+pub mod backup;
+mod colors;
+pub mod map;
+pub mod region;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    Survival,
+    Creative,
+    Adventure,
+    Spectator,
+}
+
+impl GameMode {
+    fn from_id(id: i64) -> Option<GameMode> {
+        match id {
+            0 => Some(GameMode::Survival),
+            1 => Some(GameMode::Creative),
+            2 => Some(GameMode::Adventure),
+            3 => Some(GameMode::Spectator),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Survival => "Survival",
+            GameMode::Creative => "Creative",
+            GameMode::Adventure => "Adventure",
+            GameMode::Spectator => "Spectator",
+        }
+    }
+}
+
+pub fn difficulty_name(id: i64) -> &'static str {
+    match id {
+        0 => "Peaceful",
+        1 => "Easy",
+        2 => "Normal",
+        3 => "Hard",
+        _ => "Unknown",
+    }
+}
+
+/// A world in the instance's `saves/` folder
+#[derive(Debug, Clone)]
+pub struct World {
+    pub path: PathBuf,
+    /// Folder name, which is what `--quickPlaySingleplayer` expects
+    pub folder: String,
+    /// `LevelName`, shown in the game's world list
+    pub name: String,
+    pub game_mode: Option<GameMode>,
+    pub hardcore: bool,
+    pub last_played: Option<DateTime<Local>>,
+    pub version: Option<String>,
+    pub seed: Option<i64>,
+    pub difficulty: Option<i64>,
+    /// World spawn as block x and z
+    pub spawn: (i32, i32),
+    /// Size of the whole folder in bytes
+    pub size: u64,
+}
+
+impl World {
+    pub fn level_dat(&self) -> PathBuf {
+        self.path.join("level.dat")
+    }
+
+    pub fn icon(&self) -> Option<Vec<u8>> {
+        fs::read(self.path.join("icon.png")).ok()
+    }
+
+    pub fn mode_label(&self) -> String {
+        match (self.hardcore, self.game_mode) {
+            (true, _) => "Hardcore".to_string(),
+            (false, Some(mode)) => mode.name().to_string(),
+            (false, None) => "Unknown".to_string(),
+        }
+    }
+
+    /// Reads a world folder, `None` if it has no readable `level.dat`
+    pub fn load(path: PathBuf) -> Option<World> {
+        let folder = path.file_name()?.to_string_lossy().to_string();
+        let root = match nbt::read_file(&path.join("level.dat")) {
+            Ok(root) => root,
+            Err(err) => {
+                debug!("Skipping world {}: {:#}", folder, err);
+                return None;
+            }
+        };
+        let data = root.compound("Data")?;
+
+        // 1.16 moved the seed into the world generation settings
+        let seed = data.number("RandomSeed").or_else(|| {
+            data.compound("WorldGenSettings")
+                .and_then(|settings| settings.number("seed"))
+        });
+
+        // 1.21.9 replaced SpawnX/SpawnZ with a spawn compound
+        let spawn = match (data.number("SpawnX"), data.number("SpawnZ")) {
+            (Some(x), Some(z)) => (x as i32, z as i32),
+            _ => match data.compound("spawn").and_then(|spawn| spawn.get("pos")) {
+                Some(Tag::IntArray(pos)) if pos.len() == 3 => (pos[0], pos[2]),
+                _ => (0, 0),
+            },
+        };
+
+        Some(World {
+            name: data.string("LevelName").unwrap_or(&folder).to_string(),
+            game_mode: data.number("GameType").and_then(GameMode::from_id),
+            hardcore: data.number("hardcore").unwrap_or(0) != 0,
+            last_played: data
+                .number("LastPlayed")
+                .and_then(|millis| Local.timestamp_millis_opt(millis).single()),
+            version: data
+                .compound("Version")
+                .and_then(|version| version.string("Name"))
+                .map(String::from),
+            seed,
+            difficulty: data.number("Difficulty"),
+            spawn,
+            size: dir_size(&path),
+            folder,
+            path,
+        })
+    }
+}
+
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(kind) if kind.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map(|meta| meta.len()).unwrap_or(0),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+pub fn saves_dir(instance: &Instance) -> PathBuf {
+    instance.path.join("saves")
+}
+
+/// Lists the instance's worlds, most recently played first
+pub fn list(instance: &Instance) -> Vec<World> {
+    let Ok(entries) = fs::read_dir(saves_dir(instance)) else {
+        return Vec::new();
+    };
+
+    let mut worlds: Vec<World> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| World::load(entry.path()))
+        .collect();
+    worlds.sort_by(|a, b| b.last_played.cmp(&a.last_played).then_with(|| a.name.cmp(&b.name)));
+    worlds
+}
+
+/// Finds a world by folder or level name
+pub fn find(instance: &Instance, query: &str) -> Result<World> {
+    list(instance)
+        .into_iter()
+        .find(|world| world.folder == query || world.name.eq_ignore_ascii_case(query))
+        .ok_or_else(|| eyre!("no world '{}' in '{}'", query, instance.name))
+}
+
+/// Refuses to touch a world while the game that owns its `saves` folder is
+/// running, it would overwrite level.dat on exit or hold the files open
+fn ensure_closed(world: &World, action: &str) -> Result<()> {
+    let Some(game_dir) = world.path.parent().and_then(Path::parent) else {
+        return Ok(());
+    };
+    match process::running_at(game_dir) {
+        Some(pid) => Err(eyre!(
+            "the game is running (pid {}), close it before you {} {}",
+            pid,
+            action,
+            world.folder
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Reads `level.dat`, applies `edit` to its `Data` compound and writes it
+/// back with every other tag untouched
+pub fn edit_level(world: &World, edit: impl FnOnce(&mut Compound)) -> Result<()> {
+    ensure_closed(world, "edit")?;
+    let path = world.level_dat();
+    let (name, mut root) = nbt::read_named_file(&path)?;
+    let data = root
+        .compound_mut("Data")
+        .ok_or_else(|| eyre!("{} has no Data tag", path.display()))?;
+    edit(data);
+
+    // the game falls back to level.dat_old if level.dat is broken
+    fs::copy(&path, world.path.join("level.dat_old"))?;
+    nbt::write_file(&path, &name, &root, true)
+}
+
+/// Changes the name shown in the game, the folder stays the same
+pub fn rename(world: &World, name: &str) -> Result<()> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(eyre!("world name cannot be empty"));
+    }
+    edit_level(world, |data| data.insert("LevelName", Tag::String(name.to_string())))
+}
+
+/// Moves the world folder to the system trash
+pub fn delete(world: &World) -> Result<()> {
+    ensure_closed(world, "delete")?;
+    trash::delete(&world.path).wrap_err_with(|| format!("failed to move {} to the trash", world.folder))
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)?.flatten() {
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else if entry.file_name() != "session.lock" {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Free folder name next to `path`, e.g. `World (2)`
+pub fn unique_folder(dir: &Path, base: &str) -> String {
+    let mut candidate = base.to_string();
+    let mut n = 2;
+    while dir.join(&candidate).exists() {
+        candidate = format!("{} ({})", base, n);
+        n += 1;
+    }
+    candidate
+}
+
+/// Copies a world into a new folder, naming the copy "<name> (Copy)"
+pub fn duplicate(world: &World) -> Result<World> {
+    ensure_closed(world, "duplicate")?;
+    let parent = world
+        .path
+        .parent()
+        .ok_or_else(|| eyre!("{} has no parent directory", world.path.display()))?;
+    let folder = unique_folder(parent, &world.folder);
+    let target = parent.join(&folder);
+    if let Err(err) = copy_dir(&world.path, &target) {
+        let _ = fs::remove_dir_all(&target);
+        return Err(err.wrap_err(format!("failed to copy {}", world.folder)));
+    }
+
+    let copy = World::load(target).ok_or_else(|| eyre!("copied world {} is unreadable", folder))?;
+    rename(&copy, &format!("{} (Copy)", world.name))?;
+    World::load(copy.path).ok_or_else(|| eyre!("copied world {} is unreadable", folder))
+}
@@ -0,0 +1,504 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::eyre::{eyre, Result, WrapErr};
+use flate2::read::{GzDecoder, ZlibDecoder};
+
+use crate::nbt::{self, Compound, Tag};
+
+const SECTOR: usize = 4096;
+
+/// First data version without values spanning two longs (20w17a, 1.16)
+const NO_SPANNING_VERSION: i64 = 2527;
+
+/// An Anvil `r.<x>.<z>.mca` file holding 32x32 chunks
+pub struct Region {
+    path: PathBuf,
+    pub x: i32,
+    pub z: i32,
+    bytes: Vec<u8>,
+}
+
+/// Region coordinates from a file name like `r.-1.0.mca`
+pub fn parse_name(name: &str) -> Option<(i32, i32)> {
+    let mut parts = name.strip_prefix("r.")?.strip_suffix(".mca")?.split('.');
+    let x = parts.next()?.parse().ok()?;
+    let z = parts.next()?.parse().ok()?;
+    parts.next().is_none().then_some((x, z))
+}
+
+/// Region files in a dimension's `region/` folder
+pub fn list(dir: &Path) -> Vec<(i32, i32, PathBuf)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let (x, z) = parse_name(entry.file_name().to_str()?)?;
+            Some((x, z, entry.path()))
+        })
+        .collect()
+}
+
+impl Region {
+    pub fn open(path: &Path) -> Result<Region> {
+        let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        let (x, z) = parse_name(name).ok_or_else(|| eyre!("{} is not a region file", path.display()))?;
+        let bytes = fs::read(path).wrap_err_with(|| format!("failed to read {}", path.display()))?;
+        Ok(Region {
+            path: path.to_path_buf(),
+            x,
+            z,
+            bytes,
+        })
+    }
+
+    /// Reads the chunk at region-local coordinates, `None` if it was
+    /// never generated
+    pub fn chunk(&self, x: usize, z: usize) -> Result<Option<Compound>> {
+        let entry = 4 * (x % 32 + (z % 32) * 32);
+        let Some(header) = self.bytes.get(entry..entry + 4) else {
+            return Ok(None);
+        };
+        let offset = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize * SECTOR;
+        if offset == 0 {
+            return Ok(None);
+        }
+
+        let start = self
+            .bytes
+            .get(offset..offset + 5)
+            .ok_or_else(|| eyre!("chunk {},{} points past the end of the file", x, z))?;
+        let length = u32::from_be_bytes([start[0], start[1], start[2], start[3]]) as usize;
+        let compression = start[4];
+        if length == 0 {
+            return Ok(None);
+        }
+
+        // chunks too big for the region live in c.<x>.<z>.mcc next to it
+        let external;
+        let data = if compression & 0x80 != 0 {
+            let chunk_x = self.x * 32 + x as i32;
+            let chunk_z = self.z * 32 + z as i32;
+            let path = self.path.with_file_name(format!("c.{}.{}.mcc", chunk_x, chunk_z));
+            external = fs::read(&path).wrap_err_with(|| format!("failed to read {}", path.display()))?;
+            &external[..]
+        } else {
+            self.bytes
+                .get(offset + 5..offset + 4 + length)
+                .ok_or_else(|| eyre!("chunk {},{} is truncated", x, z))?
+        };
+
+        let (_, root) = match compression & 0x7f {
+            1 => nbt::read(GzDecoder::new(data))?,
+            2 => nbt::read(ZlibDecoder::new(data))?,
+            3 => nbt::read(data)?,
+            other => return Err(eyre!("unsupported chunk compression {}", other)),
+        };
+        Ok(Some(root))
+    }
+}
+
+/// Splits packed palette indices out of a long array. Before 1.16 values
+/// could span two longs, later versions pad each long instead.
+fn unpack(data: &[i64], bits: usize, count: usize, spanning: bool) -> Vec<u16> {
+    let mask = (1u64 << bits) - 1;
+    let mut values = Vec::with_capacity(count);
+    if spanning {
+        for i in 0..count {
+            let bit = i * bits;
+            let (index, offset) = (bit / 64, bit % 64);
+            let Some(&long) = data.get(index) else {
+                break;
+            };
+            let mut value = (long as u64) >> offset;
+            if offset + bits > 64 {
+                let next = data.get(index + 1).copied().unwrap_or(0) as u64;
+                value |= next << (64 - offset);
+            }
+            values.push((value & mask) as u16);
+        }
+    } else {
+        let per_long = 64 / bits;
+        for i in 0..count {
+            let Some(&long) = data.get(i / per_long) else {
+                break;
+            };
+            values.push((((long as u64) >> ((i % per_long) * bits)) & mask) as u16);
+        }
+    }
+    values.resize(count, 0);
+    values
+}
+
+fn bits_for(len: usize) -> usize {
+    (usize::BITS - len.saturating_sub(1).leading_zeros()) as usize
+}
+
+struct Section {
+    y: i32,
+    palette: Vec<String>,
+    /// Palette index per block, empty when the palette has a single entry
+    blocks: Vec<u16>,
+}
+
+impl Section {
+    fn block(&self, x: usize, y: usize, z: usize) -> Option<&str> {
+        let index = if self.blocks.is_empty() {
+            0
+        } else {
+            *self.blocks.get(y * 256 + z * 16 + x)? as usize
+        };
+        self.palette.get(index).map(String::as_str)
+    }
+}
+
+pub fn is_air(block: &str) -> bool {
+    matches!(
+        block,
+        "minecraft:air" | "minecraft:cave_air" | "minecraft:void_air"
+    )
+}
+
+/// Water and the plants that only grow in it
+fn is_water(block: &str) -> bool {
+    matches!(
+        block,
+        "minecraft:water"
+            | "minecraft:bubble_column"
+            | "minecraft:kelp"
+            | "minecraft:kelp_plant"
+            | "minecraft:seagrass"
+            | "minecraft:tall_seagrass"
+    )
+}
+
+/// Top block of a column as seen from above
+#[derive(Debug, Clone)]
+pub struct Top {
+    pub block: String,
+    pub y: i32,
+    /// Water above the block, so lakes and oceans get shaded by depth
+    pub water: u16,
+}
+
+/// The blocks of one chunk, enough to find the top of each column
+pub struct Chunk {
+    /// Sorted from the highest section down
+    sections: Vec<Section>,
+    /// Column heights to start searching from, `z * 16 + x`
+    heights: Option<Vec<i32>>,
+}
+
+impl Chunk {
+    /// Parses the chunk NBT of 1.18+ (`sections`, `block_states`), 1.13 to
+    /// 1.17 (`Level.Sections` with `Palette`) and numeric-id worlds
+    pub fn parse(root: &Compound) -> Result<Chunk> {
+        let version = root.number("DataVersion").unwrap_or(0);
+        let mut sections = Vec::new();
+
+        let heights = if root.get("sections").is_some() {
+            for section in root.list("sections").iter().filter_map(Tag::as_compound) {
+                let Some(states) = section.compound("block_states") else {
+                    continue;
+                };
+                if let Some(parsed) = parse_section(section, states, "palette", "data", false)? {
+                    sections.push(parsed);
+                }
+            }
+            let min_y = root.number("yPos").map_or(-64, |y| y as i32 * 16);
+            heightmap(root, min_y)
+        } else {
+            let level = root
+                .compound("Level")
+                .ok_or_else(|| eyre!("chunk has neither sections nor a Level tag"))?;
+            let spanning = version < NO_SPANNING_VERSION;
+            for section in level.list("Sections").iter().filter_map(Tag::as_compound) {
+                let parsed = if section.get("Palette").is_some() {
+                    parse_section(section, section, "Palette", "BlockStates", spanning)?
+                } else {
+                    parse_legacy_section(section)
+                };
+                sections.extend(parsed);
+            }
+            heightmap(level, 0).or_else(|| match level.get("HeightMap") {
+                Some(Tag::IntArray(values)) if values.len() == 256 => Some(values.clone()),
+                _ => None,
+            })
+        };
+
+        sections.sort_by_key(|section| std::cmp::Reverse(section.y));
+        Ok(Chunk { sections, heights })
+    }
+
+    fn block(&self, x: usize, y: i32, z: usize) -> Option<&str> {
+        let section = self.sections.iter().find(|section| section.y == y.div_euclid(16))?;
+        section.block(x, y.rem_euclid(16) as usize, z)
+    }
+
+    /// Highest block of the column. With a `ceiling` (the nether roof) the
+    /// search starts there and skips solid blocks until it reaches air.
+    pub fn top(&self, x: usize, z: usize, ceiling: Option<i32>) -> Option<Top> {
+        let highest = self.sections.first()?.y * 16 + 15;
+        let lowest = self.sections.last()?.y * 16;
+        let start = match (ceiling, &self.heights) {
+            (Some(ceiling), _) => ceiling.min(highest),
+            // heightmaps store the y above the top block
+            (None, Some(heights)) => heights[z * 16 + x].min(highest),
+            (None, None) => highest,
+        };
+
+        let mut below_roof = ceiling.is_none();
+        let mut water = 0;
+        for y in (lowest..=start).rev() {
+            let block = match self.block(x, y, z) {
+                Some(block) if !is_air(block) => block,
+                _ => {
+                    below_roof = true;
+                    continue;
+                }
+            };
+            if !below_roof {
+                continue;
+            }
+            if is_water(block) {
+                water += 1;
+                continue;
+            }
+            return Some(Top {
+                block: block.to_string(),
+                y: y + water as i32,
+                water,
+            });
+        }
+        (water > 0).then(|| Top {
+            block: "minecraft:water".to_string(),
+            y: lowest + water as i32 - 1,
+            water,
+        })
+    }
+}
+
+fn parse_section(
+    section: &Compound,
+    states: &Compound,
+    palette_key: &str,
+    data_key: &str,
+    spanning: bool,
+) -> Result<Option<Section>> {
+    let Some(y) = section.number("Y") else {
+        return Ok(None);
+    };
+    let palette: Vec<String> = states
+        .list(palette_key)
+        .iter()
+        .filter_map(|entry| entry.as_compound()?.string("Name").map(String::from))
+        .collect();
+    if palette.is_empty() || palette.iter().all(|block| is_air(block)) {
+        return Ok(None);
+    }
+
+    let blocks = match states.get(data_key) {
+        Some(Tag::LongArray(data)) if palette.len() > 1 => {
+            let bits = bits_for(palette.len()).max(4);
+            if bits > 16 {
+                return Err(eyre!("section {} has an oversized palette", y));
+            }
+            unpack(data, bits, 4096, spanning)
+        }
+        _ => Vec::new(),
+    };
+    Ok(Some(Section {
+        y: y as i32,
+        palette,
+        blocks,
+    }))
+}
+
+/// Pre-1.13 sections store one numeric block id per byte
+fn parse_legacy_section(section: &Compound) -> Option<Section> {
+    let y = section.number("Y")?;
+    let Some(Tag::ByteArray(ids)) = section.get("Blocks") else {
+        return None;
+    };
+    let mut palette: Vec<String> = Vec::new();
+    let mut blocks = Vec::with_capacity(ids.len());
+    for &id in ids {
+        let name = super::colors::legacy_name(id as u8);
+        let index = match palette.iter().position(|known| known == name) {
+            Some(index) => index,
+            None => {
+                palette.push(name.to_string());
+                palette.len() - 1
+            }
+        };
+        blocks.push(index as u16);
+    }
+    Some(Section {
+        y: y as i32,
+        palette,
+        blocks,
+    })
+}
+
+/// `WORLD_SURFACE` heights as absolute y values
+fn heightmap(level: &Compound, min_y: i32) -> Option<Vec<i32>> {
+    let maps = level.compound("Heightmaps")?;
+    let Some(Tag::LongArray(data)) = maps.get("WORLD_SURFACE") else {
+        return None;
+    };
+    // the bit width depends on the world height, recover it from the length
+    let (bits, spanning) = (1..=16).find_map(|bits| {
+        if data.len() * 64 == 256 * bits {
+            Some((bits, true))
+        } else if data.len() == 256usize.div_ceil(64 / bits) {
+            Some((bits, false))
+        } else {
+            None
+        }
+    })?;
+    let values = unpack(data, bits, 256, spanning);
+    Some(values.into_iter().map(|value| min_y + value as i32).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::ZlibEncoder, Compression};
+
+    use super::*;
+
+    /// Packs values the way the game does, the inverse of [`unpack`]
+    fn pack(values: &[u16], bits: usize, spanning: bool) -> Vec<i64> {
+        let mut data = Vec::new();
+        if spanning {
+            data.resize((values.len() * bits).div_ceil(64), 0u64);
+            for (i, &value) in values.iter().enumerate() {
+                let bit = i * bits;
+                let (index, offset) = (bit / 64, bit % 64);
+                data[index] |= (value as u64) << offset;
+                if offset + bits > 64 {
+                    data[index + 1] |= (value as u64) >> (64 - offset);
+                }
+            }
+        } else {
+            let per_long = 64 / bits;
+            data.resize(values.len().div_ceil(per_long), 0u64);
+            for (i, &value) in values.iter().enumerate() {
+                data[i / per_long] |= (value as u64) << ((i % per_long) * bits);
+            }
+        }
+        data.into_iter().map(|long| long as i64).collect()
+    }
+
+    fn compound(entries: Vec<(&str, Tag)>) -> Compound {
+        Compound(entries.into_iter().map(|(key, tag)| (key.to_string(), tag)).collect())
+    }
+
+    fn block(name: &str) -> Tag {
+        Tag::Compound(compound(vec![("Name", Tag::String(name.to_string()))]))
+    }
+
+    /// A 1.18 chunk with stone at y 64 under two blocks of water
+    fn chunk_nbt() -> Compound {
+        let blocks: Vec<u16> = (0..4096)
+            .map(|i| match i / 256 {
+                0 => 1,
+                1 | 2 => 2,
+                _ => 0,
+            })
+            .collect();
+        let states = compound(vec![
+            (
+                "palette",
+                Tag::List(10, vec![block("minecraft:air"), block("minecraft:stone"), block("minecraft:water")]),
+            ),
+            ("data", Tag::LongArray(pack(&blocks, 4, false))),
+        ]);
+        let section = compound(vec![("Y", Tag::Byte(4)), ("block_states", Tag::Compound(states))]);
+        compound(vec![
+            ("DataVersion", Tag::Int(3465)),
+            ("yPos", Tag::Int(-4)),
+            ("sections", Tag::List(10, vec![Tag::Compound(section)])),
+        ])
+    }
+
+    #[test]
+    fn region_names() {
+        assert_eq!(parse_name("r.-1.0.mca"), Some((-1, 0)));
+        assert_eq!(parse_name("r.3.-12.mca"), Some((3, -12)));
+        assert_eq!(parse_name("r.1.2.mcr"), None);
+        assert_eq!(parse_name("r.1.2.3.mca"), None);
+    }
+
+    #[test]
+    fn unpack_padded_and_spanning() {
+        let values: Vec<u16> = (0..4096).map(|i| (i * 7 % 29) as u16).collect();
+        for spanning in [false, true] {
+            let data = pack(&values, 5, spanning);
+            assert_eq!(unpack(&data, 5, values.len(), spanning), values);
+        }
+        // 12 values to a long when padded, exactly 4096 * 5 bits when spanning
+        assert_eq!(pack(&values, 5, false).len(), 4096usize.div_ceil(12));
+        assert_eq!(pack(&values, 5, true).len(), 320);
+    }
+
+    #[test]
+    fn bits_for_palette_sizes() {
+        assert_eq!(bits_for(1), 0);
+        assert_eq!(bits_for(2), 1);
+        assert_eq!(bits_for(16), 4);
+        assert_eq!(bits_for(17), 5);
+    }
+
+    #[test]
+    fn reads_zlib_chunk_from_region() {
+        let mut nbt = Vec::new();
+        nbt::write(&mut nbt, "", &chunk_nbt()).unwrap();
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&nbt).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut bytes = vec![0u8; 2 * SECTOR];
+        let entry = 4 * (1 + 2 * 32);
+        let sectors = (compressed.len() + 5).div_ceil(SECTOR) as u8;
+        bytes[entry..entry + 4].copy_from_slice(&[0, 0, 2, sectors]);
+        bytes.extend_from_slice(&(compressed.len() as u32 + 1).to_be_bytes());
+        bytes.push(2);
+        bytes.extend_from_slice(&compressed);
+        bytes.resize(bytes.len().div_ceil(SECTOR) * SECTOR, 0);
+
+        let dir = std::env::temp_dir().join(format!("mcl-region-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("r.0.-1.mca");
+        fs::write(&path, &bytes).unwrap();
+        let region = Region::open(&path).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!((region.x, region.z), (0, -1));
+        assert!(region.chunk(0, 0).unwrap().is_none());
+        assert_eq!(region.chunk(1, 2).unwrap(), Some(chunk_nbt()));
+    }
+
+    #[test]
+    fn top_block_under_water() {
+        let chunk = Chunk::parse(&chunk_nbt()).unwrap();
+        let top = chunk.top(3, 7, None).unwrap();
+        assert_eq!(top.block, "minecraft:stone");
+        assert_eq!((top.y, top.water), (66, 2));
+    }
+
+    #[test]
+    fn legacy_numeric_sections() {
+        let mut ids = vec![0i8; 4096];
+        ids[..256].fill(1);
+        let section = compound(vec![("Y", Tag::Byte(0)), ("Blocks", Tag::ByteArray(ids))]);
+        let level = compound(vec![("Sections", Tag::List(10, vec![Tag::Compound(section)]))]);
+        let chunk = Chunk::parse(&compound(vec![("Level", Tag::Compound(level))])).unwrap();
+        let top = chunk.top(0, 0, None).unwrap();
+        assert_eq!(top.block, crate::worlds::colors::legacy_name(1));
+        assert_eq!((top.y, top.water), (0, 0));
+    }
+}